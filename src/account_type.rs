@@ -0,0 +1,97 @@
+use anyhow::Result;
+use mongodb::{
+    ClientSession, Database,
+    bson::{doc, oid::ObjectId},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_util;
+
+/// The tax registration of a brokerage account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum AccountType {
+    /// Canadian Tax-Free Savings Account.
+    Tfsa,
+    /// Canadian First Home Savings Account.
+    Fhsa,
+    /// Canadian Registered Retirement Savings Plan.
+    Rrsp,
+    /// Canadian spousal Registered Retirement Savings Plan.
+    SpousalRrsp,
+    /// Canadian Registered Retirement Income Fund.
+    Rrif,
+    /// Canadian Registered Education Savings Plan.
+    Resp,
+    /// Canadian Locked-In Retirement Account.
+    Lira,
+    /// Canadian Life Income Fund.
+    Lif,
+    /// A non-registered margin account.
+    Margin,
+    /// A non-registered cash account.
+    Cash,
+}
+
+impl AccountType {
+    /// Whether the account is registered, i.e. sheltered from ordinary taxation.
+    pub fn is_registered(&self) -> bool {
+        !matches!(self, AccountType::Margin | AccountType::Cash)
+    }
+}
+
+/// The account type recorded for a brokerage account.
+///
+/// `brokerage-db` accounts only hold the brokerage and account ids, so the type is kept in its
+/// own collection, keyed by the brokerage account.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct BrokerageAccountType {
+    _id: ObjectId,
+    brokerage_account_id: ObjectId,
+    account_type: AccountType,
+}
+
+impl BrokerageAccountType {
+    pub const COLLECTION_NAME: &'static str = "brokerage_account_types";
+
+    pub fn new(brokerage_account_id: ObjectId, account_type: AccountType) -> Self {
+        Self {
+            _id: ObjectId::new(),
+            brokerage_account_id,
+            account_type,
+        }
+    }
+
+    pub fn id(&self) -> ObjectId {
+        self._id
+    }
+
+    pub fn brokerage_account_id(&self) -> ObjectId {
+        self.brokerage_account_id
+    }
+
+    pub fn account_type(&self) -> &AccountType {
+        &self.account_type
+    }
+
+    pub async fn insert(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+    ) -> Result<()> {
+        db_util::insert(self, db, Self::COLLECTION_NAME, session).await
+    }
+
+    pub async fn find_by_brokerage_account_id(
+        db: &Database,
+        brokerage_account_id: ObjectId,
+    ) -> Result<Option<Self>> {
+        let result = db
+            .collection::<Self>(Self::COLLECTION_NAME)
+            .find_one(doc! { "brokerage_account_id": brokerage_account_id })
+            .await?;
+
+        Ok(result)
+    }
+}
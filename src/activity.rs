@@ -0,0 +1,76 @@
+use brokerage_db::trade_execution::TradeSide;
+
+use crate::cash_transaction::CashTransactionKind;
+
+/// A single account activity parsed from a statement, prior to being written to the database.
+///
+/// Statement formats that don't map onto a dedicated parser crate parse into activities, which
+/// are then written with `writers::write_account_activities`.
+#[derive(Debug, PartialEq)]
+pub enum Activity {
+    Trade(TradeActivity),
+    Cash(CashActivity),
+}
+
+impl Activity {
+    /// Splits a reinvestment, income that is immediately used to buy more of a security, into
+    /// the income and the buy.
+    ///
+    /// The income is recorded as a cash activity of the security, with the trade's id suffixed by
+    /// `:income` and the absolute `amount`, followed by the trade itself.
+    pub fn reinvestment(
+        trade: TradeActivity,
+        kind: CashTransactionKind,
+        amount: f64,
+        currency: &str,
+        description: &str,
+    ) -> [Activity; 2] {
+        [
+            Activity::Cash(CashActivity {
+                brokerage_transaction_id: format!("{}:income", trade.brokerage_execution_id),
+                kind,
+                amount: amount.abs(),
+                currency: currency.to_owned(),
+                timestamp_ms: trade.execution_timestamp_ms,
+                ticker: (!trade.ticker.is_empty()).then(|| trade.ticker.clone()),
+                listing_exchange: trade.listing_exchange.clone(),
+                description: description.to_owned(),
+            }),
+            Activity::Trade(trade),
+        ]
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TradeActivity {
+    pub brokerage_execution_id: String,
+    pub ticker: String,
+    /// The listing exchange, if the statement provides one.
+    pub listing_exchange: Option<String>,
+    pub side: TradeSide,
+    pub quantity: f64,
+    pub price: f64,
+    pub commission: f64,
+    pub execution_timestamp_ms: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CashActivity {
+    pub brokerage_transaction_id: String,
+    pub kind: CashTransactionKind,
+    pub amount: f64,
+    pub currency: String,
+    pub timestamp_ms: i64,
+    /// The ticker of the security this cash movement relates to, e.g. for dividends.
+    pub ticker: Option<String>,
+    /// The listing exchange of the security, if the statement provides one.
+    pub listing_exchange: Option<String>,
+    pub description: String,
+}
+
+/// The activities parsed from a statement for one brokerage account.
+#[derive(Debug, PartialEq)]
+pub struct AccountActivities {
+    pub account_id: String,
+    pub activities: Vec<Activity>,
+}
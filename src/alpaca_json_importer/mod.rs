@@ -0,0 +1,286 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::{DateTime, NaiveDate};
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use serde::Deserialize;
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    option_contract::{OptionContract, OptionRight},
+    parse_util::{self, NEW_YORK_TZ},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const ALPACA_BROKERAGE_ID: &str = "alpaca";
+
+const DEFAULT_CURRENCY: &str = "USD";
+
+/// A decimal that the API may return either as a string or as a JSON number.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum JsonDecimal {
+    Text(String),
+    Number(f64),
+}
+
+impl JsonDecimal {
+    fn value(&self) -> Result<f64> {
+        match self {
+            JsonDecimal::Text(text) => parse_util::parse_amount_or_zero(text),
+            JsonDecimal::Number(number) => Ok(*number),
+        }
+    }
+}
+
+/// An entry of the account activities endpoint.
+///
+/// Trade activities (`FILL`) carry the execution fields, while non-trade activities carry a
+/// date and net amount.
+#[derive(Deserialize, Debug)]
+struct AlpacaActivity {
+    id: String,
+    activity_type: String,
+    /// Only present in Broker API responses, which can span several accounts.
+    account_id: Option<String>,
+    transaction_time: Option<String>,
+    date: Option<String>,
+    symbol: Option<String>,
+    side: Option<String>,
+    qty: Option<JsonDecimal>,
+    price: Option<JsonDecimal>,
+    net_amount: Option<JsonDecimal>,
+    currency: Option<String>,
+    description: Option<String>,
+}
+
+/// Converts Alpaca's unpadded option symbol, e.g. `AAPL250516C00210000`, to the OCC symbol.
+/// Returns `None` for symbols that aren't options.
+fn option_occ_symbol(symbol: &str) -> Option<String> {
+    let split = symbol.len().checked_sub(15)?;
+    let (underlying, contract) = symbol.split_at_checked(split)?;
+    if underlying.is_empty() || !contract.is_ascii() {
+        return None;
+    }
+
+    let strike = contract[7..].parse::<u64>().ok()?;
+    let contract = OptionContract {
+        underlying: underlying.to_owned(),
+        expiration: NaiveDate::parse_from_str(&contract[..6], "%y%m%d").ok()?,
+        strike: strike as f64 / 1000.0,
+        right: match &contract[6..7] {
+            "C" => OptionRight::Call,
+            "P" => OptionRight::Put,
+            _ => return None,
+        },
+    };
+    Some(contract.occ_symbol())
+}
+
+/// Imports account activities saved from the Alpaca Trading or Broker API as a JSON array.
+///
+/// Each fill becomes a trade execution under its activity id, so the partial fills of an order
+/// are recorded as separate executions. Fills carry no commission; fees are their own `FEE`
+/// activities. Option symbols are recorded as OCC symbols.
+///
+/// Trading API activities don't name the account, so the account id is given when constructing
+/// the importer. Broker API activities carry an `account_id`, which takes precedence and may
+/// split the file into several accounts.
+pub struct AlpacaJsonImporter {
+    account_id: String,
+}
+
+impl AlpacaJsonImporter {
+    pub fn new(account_id: &str) -> Self {
+        Self {
+            account_id: account_id.to_owned(),
+        }
+    }
+
+    fn activities(content: &str) -> Result<Vec<AlpacaActivity>> {
+        serde_json::from_str(content).map_err(|e| anyhow!("invalid Alpaca activities: {}", e))
+    }
+
+    fn parse_transaction_time_ms(value: &str) -> Result<i64> {
+        DateTime::parse_from_rfc3339(value)
+            .map(|dt| dt.timestamp_millis())
+            .map_err(|e| anyhow!("invalid Alpaca transaction time '{}': {}", value, e))
+    }
+
+    fn timestamp_ms(activity: &AlpacaActivity) -> Result<i64> {
+        if let Some(transaction_time) = &activity.transaction_time {
+            return Self::parse_transaction_time_ms(transaction_time);
+        }
+
+        let date = activity
+            .date
+            .as_deref()
+            .ok_or_else(|| anyhow!("Alpaca activity {} has no date", activity.id))?;
+        // Dates are sometimes returned as timestamps too.
+        match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => parse_util::date_timestamp_ms(date, NEW_YORK_TZ),
+            Err(_) => Self::parse_transaction_time_ms(date),
+        }
+    }
+
+    fn decimal(value: &Option<JsonDecimal>) -> Result<f64> {
+        value.as_ref().map_or(Ok(0.0), JsonDecimal::value)
+    }
+
+    fn ticker(symbol: &str) -> String {
+        option_occ_symbol(symbol).unwrap_or_else(|| symbol.to_owned())
+    }
+
+    fn fill(activity: &AlpacaActivity) -> Result<Activity> {
+        let symbol = activity
+            .symbol
+            .as_deref()
+            .ok_or_else(|| anyhow!("Alpaca fill {} has no symbol", activity.id))?;
+        let side = match activity.side.as_deref() {
+            Some("buy") => TradeSide::Buy,
+            Some("sell" | "sell_short") => TradeSide::Sell,
+            other => return Err(anyhow!("invalid Alpaca fill side {:?}", other)),
+        };
+
+        Ok(Activity::Trade(TradeActivity {
+            brokerage_execution_id: activity.id.clone(),
+            ticker: Self::ticker(symbol),
+            listing_exchange: None,
+            side,
+            quantity: Self::decimal(&activity.qty)?.abs(),
+            price: Self::decimal(&activity.price)?,
+            commission: 0.0,
+            execution_timestamp_ms: Self::timestamp_ms(activity)?,
+        }))
+    }
+
+    fn cash_kind(activity_type: &str) -> Option<CashTransactionKind> {
+        let kind = match activity_type {
+            "DIVCGL" | "DIVCGS" => CashTransactionKind::CapitalGainDistribution,
+            "DIVNRA" | "DIVFT" | "DIVTW" | "INTNRA" | "INTTW" | "PTC" => CashTransactionKind::Tax,
+            "DIVFEE" | "FEE" | "CFEE" => CashTransactionKind::Fee,
+            kind if kind.starts_with("DIV") => CashTransactionKind::Dividend,
+            kind if kind.starts_with("INT") => CashTransactionKind::Interest,
+            "CSD" => CashTransactionKind::Deposit,
+            "CSW" => CashTransactionKind::Withdrawal,
+            "JNL" | "JNLC" => CashTransactionKind::Journal,
+            "TRANS" | "ACATC" => CashTransactionKind::Transfer,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    fn cash(activity: &AlpacaActivity, kind: CashTransactionKind) -> Result<Activity> {
+        Ok(Activity::Cash(CashActivity {
+            brokerage_transaction_id: activity.id.clone(),
+            kind,
+            amount: Self::decimal(&activity.net_amount)?,
+            currency: activity
+                .currency
+                .clone()
+                .unwrap_or_else(|| DEFAULT_CURRENCY.to_owned()),
+            timestamp_ms: Self::timestamp_ms(activity)?,
+            ticker: activity
+                .symbol
+                .as_deref()
+                .filter(|symbol| !symbol.is_empty())
+                .map(Self::ticker),
+            listing_exchange: None,
+            description: activity
+                .description
+                .clone()
+                .unwrap_or_else(|| activity.activity_type.clone()),
+        }))
+    }
+
+    /// Parses the saved activities into account activities, one entry per account.
+    pub fn parse(&self, content: &str) -> Result<Vec<AccountActivities>> {
+        let mut accounts = Vec::<AccountActivities>::new();
+
+        for activity in Self::activities(content)? {
+            let parsed = match activity.activity_type.as_str() {
+                "FILL" => Self::fill(&activity)?,
+                activity_type => match Self::cash_kind(activity_type) {
+                    Some(kind) => Self::cash(&activity, kind)?,
+                    None => {
+                        debug!(
+                            "skipping unsupported Alpaca activity type '{}'",
+                            activity_type
+                        );
+                        continue;
+                    }
+                },
+            };
+
+            let account_id = activity.account_id.as_deref().unwrap_or(&self.account_id);
+            match accounts.iter_mut().find(|a| a.account_id == account_id) {
+                Some(account) => account.activities.push(parsed),
+                None => accounts.push(AccountActivities {
+                    account_id: account_id.to_owned(),
+                    activities: vec![parsed],
+                }),
+            }
+        }
+
+        Ok(accounts)
+    }
+}
+
+#[async_trait]
+impl StatementImporter for AlpacaJsonImporter {
+    fn importer_name(&self) -> &'static str {
+        "alpaca-json"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path.extension().is_some_and(|ext| ext == "json") {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        match Self::activities(content) {
+            Ok(activities) if !activities.is_empty() => {
+                ContentMatch::likely("JSON array of Alpaca account activities")
+            }
+            _ => ContentMatch::no("no Alpaca account activities"),
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing Alpaca activities with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        for account_activities in self.parse(content)? {
+            writers::write_account_activities(
+                db,
+                session.clone(),
+                ALPACA_BROKERAGE_ID,
+                &account_activities,
+                write_batch_size,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+use anyhow::{Result, anyhow};
+use flate2::read::GzDecoder;
+use std::{
+    fs,
+    io::{self, BufReader, Cursor, Read},
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+use tokio::{
+    io::{AsyncWriteExt, DuplexStream},
+    runtime::Handle,
+    sync::{mpsc as async_mpsc, oneshot},
+};
+
+/// Separates an archive's path from the path of an entry within it, e.g. `2024.zip!/jan.xml`.
+pub const ENTRY_SEPARATOR: &str = "!/";
+
+/// The size of the chunks in which streamed entries are passed to their reader.
+const STREAM_CHUNK_LEN: usize = 64 * 1024;
+
+/// A file within an archive.
+#[derive(Debug, PartialEq)]
+pub struct ArchiveEntry {
+    /// The archive-qualified path of the entry, e.g. `2024.zip!/jan.xml`.
+    pub path: PathBuf,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    Gz,
+}
+
+fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else if name.ends_with(".gz") {
+        Some(ArchiveKind::Gz)
+    } else {
+        None
+    }
+}
+
+/// Whether the path names a `.zip`, `.tar`, `.tar.gz`, `.tgz` or `.gz` file.
+pub fn is_archive(path: &Path) -> bool {
+    archive_kind(path).is_some()
+}
+
+/// Whether the archive is a `.tar`, `.tar.gz`, `.tgz` or `.gz` file, whose entries can be read
+/// in order as it is decompressed, without reading it into memory.
+pub fn is_streamable(path: &Path) -> bool {
+    matches!(
+        archive_kind(path),
+        Some(ArchiveKind::Tar | ArchiveKind::TarGz | ArchiveKind::Gz)
+    )
+}
+
+fn entry_path(archive_path: &Path, name: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "{}{}{}",
+        archive_path.display(),
+        ENTRY_SEPARATOR,
+        name
+    ))
+}
+
+fn zip_entries(path: &Path, bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let mut entries = Vec::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if !file.is_file() {
+            continue;
+        }
+        let name = file.name()?.into_owned();
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)
+            .map_err(|e| anyhow!("failed to read {} in {:?}: {}", name, path, e))?;
+        entries.push((name, contents));
+    }
+    Ok(entries)
+}
+
+fn tar_entries(reader: impl Read) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+        entries.push((name, contents));
+    }
+    Ok(entries)
+}
+
+/// Reads the files of an archive, named by their archive-qualified paths.
+///
+/// A `.gz` file that isn't a tarball holds one entry, named after the file without its `.gz`
+/// extension. Archives within archives are expanded in turn, e.g. to
+/// `2024.zip!/q1.tar.gz!/jan.xml`. Directories and links are skipped.
+pub fn read_entries(path: &Path, bytes: &[u8]) -> Result<Vec<ArchiveEntry>> {
+    let kind = archive_kind(path).ok_or_else(|| anyhow!("{:?} is not an archive", path))?;
+    let entries = match kind {
+        ArchiveKind::Zip => zip_entries(path, bytes)?,
+        ArchiveKind::Tar => tar_entries(bytes)?,
+        ArchiveKind::TarGz => tar_entries(GzDecoder::new(bytes))?,
+        ArchiveKind::Gz => {
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| anyhow!("{:?} has no file name", path))?
+                .to_owned();
+            let mut contents = Vec::new();
+            GzDecoder::new(bytes)
+                .read_to_end(&mut contents)
+                .map_err(|e| anyhow!("failed to decompress {:?}: {}", path, e))?;
+            vec![(name, contents)]
+        }
+    };
+
+    let mut archive_entries = Vec::new();
+    for (name, contents) in entries {
+        let entry_path = entry_path(path, &name);
+        if is_archive(&entry_path) {
+            archive_entries.extend(read_entries(&entry_path, &contents)?);
+        } else {
+            archive_entries.push(ArchiveEntry {
+                path: entry_path,
+                bytes: contents,
+            });
+        }
+    }
+    Ok(archive_entries)
+}
+
+/// How to go on with an entry of an archive being streamed.
+enum EntryDecision {
+    Skip,
+    Read(oneshot::Sender<io::Result<Vec<u8>>>),
+    Stream(DuplexStream, oneshot::Sender<io::Result<()>>),
+}
+
+/// An entry of an archive being streamed, with the start of its content.
+///
+/// The archive is read no further until the entry is skipped, read or streamed. Dropping the
+/// entry stops reading the archive.
+pub struct StreamedEntry {
+    /// The archive-qualified path of the entry, e.g. `2024.tar.gz!/jan.xml`.
+    pub path: PathBuf,
+    pub prefix: Vec<u8>,
+    decision: mpsc::Sender<EntryDecision>,
+}
+
+/// The content of a streamed entry, read from the archive as it is consumed.
+pub struct EntryStream {
+    pub reader: tokio::io::BufReader<DuplexStream>,
+    read_result: oneshot::Receiver<io::Result<()>>,
+}
+
+impl StreamedEntry {
+    fn decide(&self, decision: EntryDecision) -> Result<()> {
+        self.decision
+            .send(decision)
+            .map_err(|_| anyhow!("stopped reading the archive of {:?}", self.path))
+    }
+
+    pub fn skip(self) -> Result<()> {
+        self.decide(EntryDecision::Skip)
+    }
+
+    /// Reads the whole entry into memory.
+    pub async fn read(self) -> Result<Vec<u8>> {
+        let (sender, receiver) = oneshot::channel();
+        self.decide(EntryDecision::Read(sender))?;
+        receiver
+            .await?
+            .map_err(|e| anyhow!("failed to read {:?}: {}", self.path, e))
+    }
+
+    /// Streams the entry, from its start.
+    pub fn stream(self) -> Result<EntryStream> {
+        let (writer, reader) = tokio::io::duplex(STREAM_CHUNK_LEN);
+        let (sender, read_result) = oneshot::channel();
+        self.decide(EntryDecision::Stream(writer, sender))?;
+        Ok(EntryStream {
+            reader: tokio::io::BufReader::new(reader),
+            read_result,
+        })
+    }
+}
+
+impl EntryStream {
+    /// Returns whether the entry was read from the archive without error. A truncated or
+    /// corrupt archive otherwise just ends the stream early.
+    pub async fn finish(self) -> Result<()> {
+        // Drop the reader so that an entry the importer didn't read to its end is abandoned.
+        drop(self.reader);
+        self.read_result
+            .await?
+            .map_err(|e| anyhow!("failed to decompress the streamed entry: {}", e))
+    }
+}
+
+/// Copies the entry to the writer, stopping early if its reader is dropped.
+fn copy_entry(
+    handle: &Handle,
+    reader: &mut impl Read,
+    prefix: &[u8],
+    mut writer: DuplexStream,
+) -> io::Result<()> {
+    if handle.block_on(writer.write_all(prefix)).is_err() {
+        return Ok(());
+    }
+    let mut chunk = vec![0; STREAM_CHUNK_LEN];
+    loop {
+        let len = reader.read(&mut chunk)?;
+        if len == 0 || handle.block_on(writer.write_all(&chunk[..len])).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+/// Sends the entry with the first `prefix_len` bytes of its content, and goes on as decided.
+///
+/// Returns `Ok(false)` once the entries are no longer wanted.
+fn send_entry(
+    handle: &Handle,
+    entries: &async_mpsc::Sender<Result<StreamedEntry>>,
+    path: PathBuf,
+    mut reader: impl Read,
+    prefix_len: u64,
+) -> Result<bool> {
+    let mut prefix = Vec::new();
+    (&mut reader).take(prefix_len).read_to_end(&mut prefix)?;
+    let (decision, decisions) = mpsc::channel();
+    let entry = StreamedEntry {
+        path,
+        prefix: prefix.clone(),
+        decision,
+    };
+    if entries.blocking_send(Ok(entry)).is_err() {
+        return Ok(false);
+    }
+    match decisions.recv() {
+        Err(_) => Ok(false),
+        Ok(EntryDecision::Skip) => Ok(true),
+        Ok(EntryDecision::Read(sender)) => {
+            let result = reader.read_to_end(&mut prefix).map(|_| prefix);
+            let _ = sender.send(result);
+            Ok(true)
+        }
+        Ok(EntryDecision::Stream(writer, sender)) => {
+            let _ = sender.send(copy_entry(handle, &mut reader, &prefix, writer));
+            Ok(true)
+        }
+    }
+}
+
+fn send_tar_entries(
+    handle: &Handle,
+    entries: &async_mpsc::Sender<Result<StreamedEntry>>,
+    path: &Path,
+    reader: impl Read,
+    prefix_len: u64,
+) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().into_owned();
+        if !send_entry(handle, entries, entry_path(path, &name), entry, prefix_len)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads the files of a `.tar`, `.tar.gz`, `.tgz` or `.gz` archive in order as it is
+/// decompressed on a blocking thread, without reading the archive into memory.
+///
+/// Each entry is received with the first `prefix_len` bytes of its content, to decide whether
+/// to skip, read or stream it. Entries are named as by `read_entries`, but archives within the
+/// archive are not expanded.
+pub fn stream_entries(
+    path: &Path,
+    prefix_len: u64,
+) -> Result<async_mpsc::Receiver<Result<StreamedEntry>>> {
+    if !is_streamable(path) {
+        return Err(anyhow!("{:?} is not a streamable archive", path));
+    }
+    let kind = archive_kind(path);
+    let file = BufReader::new(fs::File::open(path)?);
+    let path = path.to_owned();
+    let handle = Handle::current();
+    let (sender, receiver) = async_mpsc::channel(1);
+    tokio::task::spawn_blocking(move || {
+        let result = match kind {
+            Some(ArchiveKind::Tar) => send_tar_entries(&handle, &sender, &path, file, prefix_len),
+            Some(ArchiveKind::TarGz) => {
+                send_tar_entries(&handle, &sender, &path, GzDecoder::new(file), prefix_len)
+            }
+            _ => path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| anyhow!("{:?} has no file name", path))
+                .and_then(|name| {
+                    let entry_path = entry_path(&path, name);
+                    send_entry(
+                        &handle,
+                        &sender,
+                        entry_path,
+                        GzDecoder::new(file),
+                        prefix_len,
+                    )
+                })
+                .map(|_| ()),
+        };
+        if let Err(e) = result {
+            let _ = sender.blocking_send(Err(anyhow!("failed to read {:?}: {}", path, e)));
+        }
+    });
+    Ok(receiver)
+}
@@ -0,0 +1,503 @@
+use anyhow::{Result, anyhow};
+use brokerage_db::{
+    account::BrokerageAccount,
+    security::{Security, SecurityType},
+    trade_execution::TradeExecution,
+};
+use mongodb::{
+    ClientSession, Database,
+    bson::{Document, doc, oid::ObjectId},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::{cash_transaction::CashTransaction, db_util, writers::UNKNOWN_LISTING_EXCHANGE};
+
+/// The number of trades and cash transactions buffered before they are written.
+pub const DEFAULT_WRITE_BATCH_SIZE: usize = 1000;
+
+/// Returns the write batch size, failing for 0, with which nothing would ever be written.
+pub fn validate_batch_size(batch_size: usize) -> Result<usize> {
+    if batch_size == 0 {
+        return Err(anyhow!("write batch size must be at least 1"));
+    }
+    Ok(batch_size)
+}
+
+/// Writes accounts, securities, trades and cash transactions in bulk.
+///
+/// Existing accounts, securities, execution ids and cash transaction ids are loaded with one
+/// query per batch of records rather than one per record, and looked up in memory. Trades and
+/// cash transactions whose account already has their id are skipped. New documents are buffered
+/// and written with ordered bulk inserts, accounts and securities first, once the batch size of
+/// trades and cash transactions is buffered and when the writer is flushed. Writers must be
+/// flushed once everything is added.
+pub struct BatchWriter {
+    batch_size: usize,
+    accounts: HashMap<(String, String), ObjectId>,
+    preloaded_accounts: HashSet<(String, String)>,
+    securities: HashMap<(String, String), ObjectId>,
+    securities_by_ticker: HashMap<String, Vec<ObjectId>>,
+    preloaded_tickers: HashSet<String>,
+    trade_execution_ids: HashSet<(ObjectId, String)>,
+    preloaded_trade_execution_ids: HashSet<(ObjectId, String)>,
+    cash_transaction_ids: HashSet<(ObjectId, String)>,
+    preloaded_cash_transaction_ids: HashSet<(ObjectId, String)>,
+    new_accounts: Vec<BrokerageAccount>,
+    new_securities: Vec<Security>,
+    trades: Vec<TradeExecution>,
+    cash_transactions: Vec<CashTransaction>,
+}
+
+impl Default for BatchWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchWriter {
+    pub fn new() -> Self {
+        Self {
+            batch_size: DEFAULT_WRITE_BATCH_SIZE,
+            accounts: HashMap::new(),
+            preloaded_accounts: HashSet::new(),
+            securities: HashMap::new(),
+            securities_by_ticker: HashMap::new(),
+            preloaded_tickers: HashSet::new(),
+            trade_execution_ids: HashSet::new(),
+            preloaded_trade_execution_ids: HashSet::new(),
+            cash_transaction_ids: HashSet::new(),
+            preloaded_cash_transaction_ids: HashSet::new(),
+            new_accounts: Vec::new(),
+            new_securities: Vec::new(),
+            trades: Vec::new(),
+            cash_transactions: Vec::new(),
+        }
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Result<Self> {
+        self.batch_size = validate_batch_size(batch_size)?;
+        Ok(self)
+    }
+
+    /// Loads the existing accounts of the brokerage with the given account ids.
+    pub async fn preload_accounts(
+        &mut self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        brokerage_id: &str,
+        account_ids: &[&str],
+    ) -> Result<()> {
+        let account_ids = account_ids
+            .iter()
+            .filter(|account_id| {
+                !self
+                    .preloaded_accounts
+                    .contains(&(brokerage_id.to_owned(), account_id.to_string()))
+            })
+            .copied()
+            .collect::<HashSet<&str>>();
+        if account_ids.is_empty() {
+            return Ok(());
+        }
+
+        let accounts = db_util::find::<BrokerageAccount>(
+            doc! {
+                "brokerage_id": brokerage_id,
+                "account_id": { "$in": account_ids.iter().collect::<Vec<_>>() },
+            },
+            None,
+            db,
+            BrokerageAccount::COLLECTION_NAME,
+            session,
+        )
+        .await?;
+        debug!(
+            "preloaded {} of {} {} accounts",
+            accounts.len(),
+            account_ids.len(),
+            brokerage_id
+        );
+        for account in accounts {
+            self.accounts.insert(
+                (
+                    account.brokerage_id().to_owned(),
+                    account.account_id().to_owned(),
+                ),
+                account.id(),
+            );
+        }
+        self.preloaded_accounts.extend(
+            account_ids
+                .into_iter()
+                .map(|account_id| (brokerage_id.to_owned(), account_id.to_owned())),
+        );
+        Ok(())
+    }
+
+    /// Loads the existing securities with the given tickers, on any exchange.
+    pub async fn preload_securities(
+        &mut self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        tickers: &[&str],
+    ) -> Result<()> {
+        let tickers = tickers
+            .iter()
+            .filter(|ticker| !self.preloaded_tickers.contains(**ticker))
+            .copied()
+            .collect::<HashSet<&str>>();
+        if tickers.is_empty() {
+            return Ok(());
+        }
+
+        let securities = db_util::find::<Security>(
+            doc! { "ticker": { "$in": tickers.iter().collect::<Vec<_>>() } },
+            None,
+            db,
+            Security::COLLECTION_NAME,
+            session,
+        )
+        .await?;
+        debug!(
+            "preloaded {} securities for {} tickers",
+            securities.len(),
+            tickers.len()
+        );
+        for security in securities {
+            self.securities.insert(
+                (
+                    security.ticker().to_owned(),
+                    security.listing_exchange().to_owned(),
+                ),
+                security.id(),
+            );
+            self.securities_by_ticker
+                .entry(security.ticker().to_owned())
+                .or_default()
+                .push(security.id());
+        }
+        self.preloaded_tickers
+            .extend(tickers.into_iter().map(str::to_owned));
+        Ok(())
+    }
+
+    /// Returns which of the ids the brokerage account already has in the collection, reading
+    /// just the id field of each document.
+    async fn existing_ids(
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        collection_name: &str,
+        id_field: &str,
+        brokerage_account_id: ObjectId,
+        ids: &HashSet<&str>,
+    ) -> Result<Vec<String>> {
+        let documents = db_util::find::<Document>(
+            doc! {
+                "brokerage_account_id": brokerage_account_id,
+                id_field: { "$in": ids.iter().collect::<Vec<_>>() },
+            },
+            Some(doc! { "_id": 0, id_field: 1 }),
+            db,
+            collection_name,
+            session,
+        )
+        .await?;
+        debug!(
+            "preloaded {} of {} {} ids",
+            documents.len(),
+            ids.len(),
+            collection_name
+        );
+        documents
+            .iter()
+            .map(|document| Ok(document.get_str(id_field)?.to_owned()))
+            .collect()
+    }
+
+    /// Loads which of the execution ids the brokerage account already has.
+    pub async fn preload_trade_executions(
+        &mut self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        brokerage_account_id: ObjectId,
+        brokerage_execution_ids: &[&str],
+    ) -> Result<()> {
+        let brokerage_execution_ids = brokerage_execution_ids
+            .iter()
+            .filter(|id| {
+                !self
+                    .preloaded_trade_execution_ids
+                    .contains(&(brokerage_account_id, id.to_string()))
+            })
+            .copied()
+            .collect::<HashSet<&str>>();
+        if brokerage_execution_ids.is_empty() {
+            return Ok(());
+        }
+
+        let existing_ids = Self::existing_ids(
+            db,
+            session,
+            TradeExecution::COLLECTION_NAME,
+            "brokerage_execution_id",
+            brokerage_account_id,
+            &brokerage_execution_ids,
+        )
+        .await?;
+        self.trade_execution_ids.extend(
+            existing_ids
+                .into_iter()
+                .map(|id| (brokerage_account_id, id)),
+        );
+        self.preloaded_trade_execution_ids.extend(
+            brokerage_execution_ids
+                .into_iter()
+                .map(|id| (brokerage_account_id, id.to_owned())),
+        );
+        Ok(())
+    }
+
+    /// Loads which of the cash transaction ids the brokerage account already has.
+    pub async fn preload_cash_transactions(
+        &mut self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        brokerage_account_id: ObjectId,
+        brokerage_transaction_ids: &[&str],
+    ) -> Result<()> {
+        let brokerage_transaction_ids = brokerage_transaction_ids
+            .iter()
+            .filter(|id| {
+                !self
+                    .preloaded_cash_transaction_ids
+                    .contains(&(brokerage_account_id, id.to_string()))
+            })
+            .copied()
+            .collect::<HashSet<&str>>();
+        if brokerage_transaction_ids.is_empty() {
+            return Ok(());
+        }
+
+        let existing_ids = Self::existing_ids(
+            db,
+            session,
+            CashTransaction::COLLECTION_NAME,
+            "brokerage_transaction_id",
+            brokerage_account_id,
+            &brokerage_transaction_ids,
+        )
+        .await?;
+        self.cash_transaction_ids.extend(
+            existing_ids
+                .into_iter()
+                .map(|id| (brokerage_account_id, id)),
+        );
+        self.preloaded_cash_transaction_ids.extend(
+            brokerage_transaction_ids
+                .into_iter()
+                .map(|id| (brokerage_account_id, id.to_owned())),
+        );
+        Ok(())
+    }
+
+    /// Returns the id of the brokerage account, adding it if it doesn't exist.
+    pub async fn account(
+        &mut self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        brokerage_id: &str,
+        account_id: &str,
+    ) -> Result<ObjectId> {
+        let key = (brokerage_id.to_owned(), account_id.to_owned());
+        if !self.preloaded_accounts.contains(&key) {
+            self.preload_accounts(db, session, brokerage_id, &[account_id])
+                .await?;
+        }
+        if let Some(id) = self.accounts.get(&key) {
+            debug!(
+                "Brokerage account already exists: {} at {}",
+                account_id, brokerage_id
+            );
+            return Ok(*id);
+        }
+
+        let account = BrokerageAccount::new(brokerage_id, account_id);
+        info!(
+            "Adding new brokerage account: {} at {}",
+            account_id, brokerage_id
+        );
+        self.accounts.insert(key, account.id());
+        let id = account.id();
+        self.new_accounts.push(account);
+        Ok(id)
+    }
+
+    /// Returns the id of the security, adding it if the exchange doesn't list it yet.
+    pub async fn security(
+        &mut self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        ticker: &str,
+        listing_exchange: &str,
+        ibkr_conid: Option<u32>,
+    ) -> Result<ObjectId> {
+        if !self.preloaded_tickers.contains(ticker) {
+            self.preload_securities(db, session, &[ticker]).await?;
+        }
+        let key = (ticker.to_owned(), listing_exchange.to_owned());
+        if let Some(id) = self.securities.get(&key) {
+            return Ok(*id);
+        }
+
+        let security = Security::new(SecurityType::Stock, ticker, listing_exchange, ibkr_conid);
+        info!("Adding security: {} on {}", ticker, listing_exchange);
+        let id = security.id();
+        self.securities.insert(key, id);
+        self.securities_by_ticker
+            .entry(ticker.to_owned())
+            .or_default()
+            .push(id);
+        self.new_securities.push(security);
+        Ok(id)
+    }
+
+    /// Returns the id of the security with the ticker, for statements that don't name an exchange.
+    ///
+    /// This is the ticker's security if exactly one exchange lists it, and otherwise the one on
+    /// an unknown exchange, which is added if needed.
+    pub async fn security_by_ticker(
+        &mut self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        ticker: &str,
+    ) -> Result<ObjectId> {
+        if !self.preloaded_tickers.contains(ticker) {
+            self.preload_securities(db, session.clone(), &[ticker])
+                .await?;
+        }
+        match self.securities_by_ticker.get(ticker).map(Vec::as_slice) {
+            Some([id]) => Ok(*id),
+            _ => {
+                self.security(db, session, ticker, UNKNOWN_LISTING_EXCHANGE, None)
+                    .await
+            }
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.trades.len() + self.cash_transactions.len() >= self.batch_size
+    }
+
+    /// Adds the trade unless its account already has one with the same execution id.
+    pub async fn add_trade(
+        &mut self,
+        trade: TradeExecution,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+    ) -> Result<()> {
+        let brokerage_account_id = trade.brokerage_account_id();
+        let key = (
+            brokerage_account_id,
+            trade.brokerage_execution_id().to_owned(),
+        );
+        if !self.preloaded_trade_execution_ids.contains(&key) {
+            self.preload_trade_executions(
+                db,
+                session.clone(),
+                brokerage_account_id,
+                &[trade.brokerage_execution_id()],
+            )
+            .await?;
+        }
+        if !self.trade_execution_ids.insert(key) {
+            debug!(
+                "trade execution {} already exists, skipping db insert",
+                trade.brokerage_execution_id()
+            );
+            return Ok(());
+        }
+
+        self.trades.push(trade);
+        if self.is_full() {
+            self.flush(db, session).await?;
+        }
+        Ok(())
+    }
+
+    /// Adds the cash transaction unless its account already has one with the same id.
+    pub async fn add_cash_transaction(
+        &mut self,
+        cash_transaction: CashTransaction,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+    ) -> Result<()> {
+        let brokerage_account_id = cash_transaction.brokerage_account_id();
+        let key = (
+            brokerage_account_id,
+            cash_transaction.brokerage_transaction_id().to_owned(),
+        );
+        if !self.preloaded_cash_transaction_ids.contains(&key) {
+            self.preload_cash_transactions(
+                db,
+                session.clone(),
+                brokerage_account_id,
+                &[cash_transaction.brokerage_transaction_id()],
+            )
+            .await?;
+        }
+        if !self.cash_transaction_ids.insert(key) {
+            debug!(
+                "cash transaction {} already exists, skipping db insert",
+                cash_transaction.brokerage_transaction_id()
+            );
+            return Ok(());
+        }
+
+        self.cash_transactions.push(cash_transaction);
+        if self.is_full() {
+            self.flush(db, session).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered documents.
+    pub async fn flush(
+        &mut self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+    ) -> Result<()> {
+        db_util::insert_many(
+            &std::mem::take(&mut self.new_accounts),
+            db,
+            BrokerageAccount::COLLECTION_NAME,
+            session.clone(),
+        )
+        .await?;
+        db_util::insert_many(
+            &std::mem::take(&mut self.new_securities),
+            db,
+            Security::COLLECTION_NAME,
+            session.clone(),
+        )
+        .await?;
+        db_util::insert_many(
+            &std::mem::take(&mut self.trades),
+            db,
+            TradeExecution::COLLECTION_NAME,
+            session.clone(),
+        )
+        .await?;
+        db_util::insert_many(
+            &std::mem::take(&mut self.cash_transactions),
+            db,
+            CashTransaction::COLLECTION_NAME,
+            session,
+        )
+        .await
+    }
+}
@@ -0,0 +1,346 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate};
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use roxmltree::{Document, Node};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    parse_util::{self, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+const CAMT053_NAMESPACE_PREFIX: &str = "urn:iso:std:iso:20022:tech:xsd:camt.053";
+
+/// The cash statements of one camt.053 file, along with the brokerage they belong to.
+#[derive(Debug, PartialEq)]
+pub struct Camt053Statement {
+    pub brokerage_id: String,
+    pub accounts: Vec<AccountActivities>,
+}
+
+/// Returns the first child element with the given local name.
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+/// Returns the text of the element at the path of local names below the node.
+fn text<'a>(node: Node<'a, '_>, path: &[&str]) -> Option<&'a str> {
+    path.iter()
+        .try_fold(node, |node, name| child(node, name))
+        .and_then(|node| node.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+/// Maps an ISO 20022 bank transaction code to the kind of cash transaction it records.
+///
+/// Returns `None` for codes that aren't imported, such as the cash legs of securities trades.
+pub fn bank_transaction_kind(
+    domain: &str,
+    family: &str,
+    sub_family: &str,
+) -> Option<CashTransactionKind> {
+    let kind = match (domain, family, sub_family) {
+        (_, _, "DVCA" | "DVOP") => CashTransactionKind::Dividend,
+        (_, _, "CAPG") => CashTransactionKind::CapitalGainDistribution,
+        (_, _, "INTR") | (_, "CORP", "INTE") => CashTransactionKind::Interest,
+        (_, _, "CHRG" | "FEES" | "COMM" | "COMT") => CashTransactionKind::Fee,
+        (_, _, "TAXE" | "WITH" | "NRTX") => CashTransactionKind::Tax,
+        ("FORX", _, _) | (_, "FORX", _) => CashTransactionKind::ForeignExchange,
+        ("PMNT", _, _) => CashTransactionKind::Transfer,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Imports ISO 20022 camt.053 bank-to-customer cash statements.
+///
+/// Each statement's account IBAN, or its other account identifier, becomes the brokerage
+/// account id. The brokerage id is the BIC of the account servicer, or failing that the id
+/// configured with `brokerage_id`.
+///
+/// Only booked entries are imported, classified by their bank transaction code as dividends,
+/// interest, fees, taxes, foreign exchange or transfers. Entries with other codes, such as the
+/// cash legs of securities trades, are skipped. Dividends are linked to their security by the
+/// ISIN of the transaction details, or failing that one found in the remittance information,
+/// which is recorded as the security's ticker. Booking dates are recorded at midnight UTC.
+pub struct Camt053Importer {
+    brokerage_id: Option<String>,
+}
+
+impl Default for Camt053Importer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Camt053Importer {
+    pub fn new() -> Self {
+        Self { brokerage_id: None }
+    }
+
+    /// Sets the brokerage id used when the statement doesn't name its account servicer.
+    pub fn brokerage_id(mut self, brokerage_id: &str) -> Self {
+        self.brokerage_id = Some(brokerage_id.to_owned());
+        self
+    }
+
+    fn is_camt053(document: &Document) -> bool {
+        let root = document.root_element();
+        root.tag_name().name() == "Document"
+            && root
+                .tag_name()
+                .namespace()
+                .is_some_and(|ns| ns.starts_with(CAMT053_NAMESPACE_PREFIX))
+            && child(root, "BkToCstmrStmt").is_some()
+    }
+
+    fn parse_date_ms(value: &str) -> Result<i64> {
+        match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            Ok(date) => parse_util::date_timestamp_ms(date, chrono_tz::UTC),
+            Err(_) => DateTime::parse_from_rfc3339(value)
+                .map(|dt| dt.timestamp_millis())
+                .map_err(|e| anyhow!("invalid camt.053 date '{}': {}", value, e)),
+        }
+    }
+
+    fn statement_brokerage_id(&self, statement: Node) -> Result<String> {
+        let servicer = ["Acct", "Svcr", "FinInstnId"];
+        ["BICFI", "BIC"]
+            .iter()
+            .find_map(|bic| text(statement, &[&servicer[..], &[bic]].concat()))
+            .map(str::to_owned)
+            .or_else(|| self.brokerage_id.clone())
+            .ok_or_else(|| anyhow!("camt.053 statement has no servicer BIC and no brokerage id"))
+    }
+
+    fn account_id(statement: Node) -> Result<String> {
+        text(statement, &["Acct", "Id", "IBAN"])
+            .or_else(|| text(statement, &["Acct", "Id", "Othr", "Id"]))
+            .map(str::to_owned)
+            .ok_or_else(|| anyhow!("camt.053 statement has no account identifier"))
+    }
+
+    fn is_booked(entry: Node) -> bool {
+        // camt.053.001.02 has the status code as text; later versions wrap it in <Cd>.
+        matches!(
+            text(entry, &["Sts"]).or_else(|| text(entry, &["Sts", "Cd"])),
+            Some("BOOK")
+        )
+    }
+
+    fn entry_isin(entry: Node) -> Option<String> {
+        entry
+            .descendants()
+            .filter(|node| node.is_element())
+            .find_map(|node| match node.tag_name().name() {
+                "ISIN" => node
+                    .text()
+                    .map(str::trim)
+                    .filter(|t| parse_util::is_isin(t)),
+                "Ustrd" | "Ref" | "AddtlRmtInf" => node.text().and_then(parse_util::find_isin),
+                _ => None,
+            })
+            .map(str::to_owned)
+    }
+
+    fn entry_description(entry: Node) -> String {
+        let remittance = entry
+            .descendants()
+            .filter(|node| node.is_element() && node.tag_name().name() == "Ustrd")
+            .filter_map(|node| node.text())
+            .map(str::trim)
+            .collect::<Vec<&str>>();
+        if !remittance.is_empty() {
+            return remittance.join(" ");
+        }
+        text(entry, &["AddtlNtryInf"])
+            .unwrap_or_default()
+            .to_owned()
+    }
+
+    fn parse_entry(
+        entry: Node,
+        account_id: &str,
+        ids: &mut SyntheticIdGenerator,
+    ) -> Result<Option<Activity>> {
+        if !Self::is_booked(entry) {
+            return Ok(None);
+        }
+
+        let code = |path: &[&str]| {
+            text(entry, &[&["BkTxCd", "Domn"][..], path].concat()).unwrap_or_default()
+        };
+        let domain = code(&["Cd"]);
+        let family = code(&["Fmly", "Cd"]);
+        let sub_family = code(&["Fmly", "SubFmlyCd"]);
+        let Some(kind) = bank_transaction_kind(domain, family, sub_family) else {
+            debug!(
+                "skipping camt.053 entry with bank transaction code {}/{}/{}",
+                domain, family, sub_family
+            );
+            return Ok(None);
+        };
+
+        let amount_node =
+            child(entry, "Amt").ok_or_else(|| anyhow!("camt.053 entry has no amount"))?;
+        let amount = parse_util::parse_amount_or_zero(amount_node.text().unwrap_or_default())?;
+        let amount = match text(entry, &["CdtDbtInd"]) {
+            Some("DBIT") => -amount,
+            _ => amount,
+        };
+        let currency = amount_node
+            .attribute("Ccy")
+            .ok_or_else(|| anyhow!("camt.053 entry amount has no currency"))?;
+
+        let booking_date = text(entry, &["BookgDt", "Dt"])
+            .or_else(|| text(entry, &["BookgDt", "DtTm"]))
+            .or_else(|| text(entry, &["ValDt", "Dt"]))
+            .ok_or_else(|| anyhow!("camt.053 entry has no booking date"))?;
+
+        let id = text(entry, &["AcctSvcrRef"])
+            .or_else(|| text(entry, &["NtryRef"]))
+            .map(str::to_owned)
+            .unwrap_or_else(|| {
+                let fields = [account_id, booking_date, &amount.to_string(), currency];
+                ids.id("camt053", &fields)
+            });
+        let ticker = match kind {
+            CashTransactionKind::Dividend | CashTransactionKind::Tax => Self::entry_isin(entry),
+            _ => None,
+        };
+
+        Ok(Some(Activity::Cash(CashActivity {
+            brokerage_transaction_id: id,
+            kind,
+            amount,
+            currency: currency.to_owned(),
+            timestamp_ms: Self::parse_date_ms(booking_date)?,
+            ticker,
+            listing_exchange: None,
+            description: Self::entry_description(entry),
+        })))
+    }
+
+    /// Parses the camt.053 document into the booked cash entries of each statement's account.
+    pub fn parse(&self, content: &str) -> Result<Camt053Statement> {
+        let document = Document::parse(content)?;
+        if !Self::is_camt053(&document) {
+            return Err(anyhow!("not a camt.053 document"));
+        }
+        let statements = child(document.root_element(), "BkToCstmrStmt")
+            .into_iter()
+            .flat_map(|message| children(message, "Stmt"))
+            .collect::<Vec<Node>>();
+        let first = statements
+            .first()
+            .ok_or_else(|| anyhow!("camt.053 document has no statements"))?;
+        let brokerage_id = self.statement_brokerage_id(*first)?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut accounts = Vec::<AccountActivities>::new();
+        for statement in statements {
+            let account_id = Self::account_id(statement)?;
+            let mut activities = Vec::new();
+            for entry in children(statement, "Ntry") {
+                activities.extend(Self::parse_entry(entry, &account_id, &mut ids)?);
+            }
+
+            match accounts.iter_mut().find(|a| a.account_id == account_id) {
+                Some(account) => account.activities.extend(activities),
+                None => accounts.push(AccountActivities {
+                    account_id,
+                    activities,
+                }),
+            }
+        }
+
+        Ok(Camt053Statement {
+            brokerage_id,
+            accounts,
+        })
+    }
+}
+
+#[async_trait]
+impl StatementImporter for Camt053Importer {
+    fn importer_name(&self) -> &'static str {
+        "camt053"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path.extension().is_some_and(|ext| ext == "xml") {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if !content.contains(CAMT053_NAMESPACE_PREFIX) {
+            return ContentMatch::no("no camt.053 namespace");
+        }
+        match Document::parse(content) {
+            Ok(document) if Self::is_camt053(&document) => {
+                ContentMatch::definite("camt.053 namespace and BkToCstmrStmt element")
+            }
+            _ => ContentMatch::no("not a camt.053 document"),
+        }
+    }
+
+    fn statement_date(&self, content: &str) -> Option<NaiveDate> {
+        let document = Document::parse(content).ok()?;
+        let message = child(document.root_element(), "BkToCstmrStmt")?;
+        let statement = child(message, "Stmt")?;
+        let value = text(statement, &["FrToDt", "ToDtTm"])
+            .or_else(|| text(statement, &["CreDtTm"]))
+            .or_else(|| text(message, &["GrpHdr", "CreDtTm"]))?;
+        NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing camt.053 statement with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let statement = self.parse(content)?;
+        for account_activities in &statement.accounts {
+            writers::write_account_activities(
+                db,
+                session.clone(),
+                &statement.brokerage_id,
+                account_activities,
+                write_batch_size,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
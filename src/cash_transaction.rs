@@ -0,0 +1,140 @@
+use anyhow::Result;
+use futures::stream::TryStreamExt;
+use mongodb::{
+    ClientSession, Database,
+    bson::{doc, oid::ObjectId},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_util;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum CashTransactionKind {
+    CapitalGainDistribution,
+    Deposit,
+    Dividend,
+    Fee,
+    ForeignExchange,
+    Income,
+    Interest,
+    Journal,
+    Tax,
+    Transfer,
+    Withdrawal,
+}
+
+/// A cash movement in a brokerage account that is not part of a trade execution.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct CashTransaction {
+    _id: ObjectId,
+    brokerage_account_id: ObjectId,
+    brokerage_transaction_id: String,
+    kind: CashTransactionKind,
+    amount: f64,
+    currency: String,
+    timestamp_ms: i64,
+    security_id: Option<ObjectId>,
+    description: String,
+}
+
+impl CashTransaction {
+    pub const COLLECTION_NAME: &'static str = "cash_transactions";
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        brokerage_account_id: ObjectId,
+        brokerage_transaction_id: &str,
+        kind: CashTransactionKind,
+        amount: f64,
+        currency: &str,
+        timestamp_ms: i64,
+        security_id: Option<ObjectId>,
+        description: &str,
+    ) -> Self {
+        Self {
+            _id: ObjectId::new(),
+            brokerage_account_id,
+            brokerage_transaction_id: brokerage_transaction_id.to_owned(),
+            kind,
+            amount,
+            currency: currency.to_owned(),
+            timestamp_ms,
+            security_id,
+            description: description.to_owned(),
+        }
+    }
+
+    pub fn id(&self) -> ObjectId {
+        self._id
+    }
+
+    pub fn brokerage_account_id(&self) -> ObjectId {
+        self.brokerage_account_id
+    }
+
+    pub fn brokerage_transaction_id(&self) -> &str {
+        &self.brokerage_transaction_id
+    }
+
+    pub fn kind(&self) -> &CashTransactionKind {
+        &self.kind
+    }
+
+    pub fn amount(&self) -> f64 {
+        self.amount
+    }
+
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    pub fn timestamp_ms(&self) -> i64 {
+        self.timestamp_ms
+    }
+
+    pub fn security_id(&self) -> Option<ObjectId> {
+        self.security_id
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub async fn insert(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+    ) -> Result<()> {
+        db_util::insert(self, db, Self::COLLECTION_NAME, session).await
+    }
+
+    pub async fn find_by_brokerage_transaction_id(
+        db: &Database,
+        brokerage_account_id: ObjectId,
+        brokerage_transaction_id: &str,
+    ) -> Result<Option<Self>> {
+        let result = db
+            .collection::<Self>(Self::COLLECTION_NAME)
+            .find_one(doc! {
+                "brokerage_account_id": brokerage_account_id,
+                "brokerage_transaction_id": brokerage_transaction_id,
+            })
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn find_by_brokerage_account_id(
+        db: &Database,
+        brokerage_account_id: ObjectId,
+    ) -> Result<Vec<Self>> {
+        let result = db
+            .collection::<Self>(Self::COLLECTION_NAME)
+            .find(doc! { "brokerage_account_id": brokerage_account_id })
+            .await?;
+
+        Ok(result.try_collect().await?)
+    }
+}
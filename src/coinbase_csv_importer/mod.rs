@@ -0,0 +1,297 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::{DateTime, NaiveDateTime};
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    crypto_asset::{self, CRYPTO_LISTING_EXCHANGE},
+    parse_util::{self, CsvRow, CsvTable, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const COINBASE_BROKERAGE_ID: &str = "coinbase";
+
+const COINBASE_HEADERS: [&[&str]; 2] = [
+    &["ID", "Timestamp", "Transaction Type", "Asset"],
+    &["Timestamp", "Transaction Type", "Asset"],
+];
+
+/// Imports the Coinbase transaction report CSV.
+///
+/// Crypto assets are recorded as securities listed on `CRYPTO_LISTING_EXCHANGE`. Buys and sells
+/// are priced in the report's price currency, whose fees become the trade commission. A convert
+/// is recorded as a sell of one asset and a buy of the other, both valued at the subtotal.
+///
+/// Staking and other rewards are recorded as income, and sends and receives as transfers, in
+/// units of the crypto asset. The account id comes from the report's `User` line, or failing
+/// that from the id configured with `account_id`.
+pub struct CoinbaseCsvImporter {
+    account_id: Option<String>,
+}
+
+impl Default for CoinbaseCsvImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CoinbaseCsvImporter {
+    pub fn new() -> Self {
+        Self { account_id: None }
+    }
+
+    /// Sets the account id used when the report has no `User` line.
+    pub fn account_id(mut self, account_id: &str) -> Self {
+        self.account_id = Some(account_id.to_owned());
+        self
+    }
+
+    fn find_header(content: &str) -> Option<usize> {
+        COINBASE_HEADERS
+            .iter()
+            .find_map(|header| parse_util::find_csv_header(content, header))
+    }
+
+    fn statement_account_id(&self, preamble: &str) -> Result<String> {
+        let from_preamble = preamble.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix("User,")?
+                .rsplit(',')
+                .next()
+                .map(|id| id.trim().trim_matches('"'))
+        });
+
+        from_preamble
+            .filter(|account_id| !account_id.is_empty())
+            .map(str::to_owned)
+            .or_else(|| self.account_id.clone())
+            .ok_or_else(|| anyhow!("Coinbase CSV does not name its user and no account is set"))
+    }
+
+    fn parse_timestamp_ms(value: &str) -> Result<i64> {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S UTC")
+            .map(|dt| dt.and_utc().timestamp_millis())
+            .or_else(|_| DateTime::parse_from_rfc3339(value).map(|dt| dt.timestamp_millis()))
+            .map_err(|e| anyhow!("invalid Coinbase timestamp '{}': {}", value, e))
+    }
+
+    /// Parses an amount that may be prefixed with a currency symbol, e.g. `€1,234.56`.
+    fn amount(value: &str) -> Result<f64> {
+        let cleaned = value
+            .chars()
+            .filter(|c| c.is_ascii_digit() || matches!(c, '-' | '.' | ',' | '(' | ')'))
+            .collect::<String>();
+        parse_util::parse_amount_or_zero(&cleaned)
+    }
+
+    /// Parses a convert's notes, e.g. `Converted 0.01 BTC to 0.2 ETH`, into the asset received
+    /// and its quantity.
+    fn converted_to(notes: &str) -> Option<(String, f64)> {
+        let (_, to) = notes.split_once(" to ")?;
+        let mut tokens = to.split_whitespace();
+        let quantity = parse_util::parse_amount(tokens.next()?).ok()??;
+        let asset = tokens.next()?;
+        Some((asset.to_owned(), quantity))
+    }
+
+    fn trade(
+        id: String,
+        asset: &str,
+        side: TradeSide,
+        quantity: f64,
+        price: f64,
+        commission: f64,
+        timestamp_ms: i64,
+    ) -> Activity {
+        Activity::Trade(TradeActivity {
+            brokerage_execution_id: id,
+            ticker: asset.to_owned(),
+            listing_exchange: Some(CRYPTO_LISTING_EXCHANGE.to_owned()),
+            side,
+            quantity,
+            price,
+            commission,
+            execution_timestamp_ms: timestamp_ms,
+        })
+    }
+
+    fn parse_row(row: &CsvRow, id: String, activities: &mut Vec<Activity>) -> Result<()> {
+        let transaction_type = row.get("Transaction Type");
+        let timestamp_ms = Self::parse_timestamp_ms(row.get("Timestamp"))?;
+        let asset = row.get("Asset");
+        let quantity = Self::amount(row.get("Quantity Transacted"))?.abs();
+        let price =
+            Self::amount(row.get_any(&["Price at Transaction", "Spot Price at Transaction"]))?;
+        let subtotal = Self::amount(row.get("Subtotal"))?.abs();
+        let fees = Self::amount(row.get("Fees and/or Spread"))?.abs();
+        let notes = row.get("Notes");
+
+        let cash = |kind: CashTransactionKind, amount: f64| {
+            Activity::Cash(CashActivity {
+                brokerage_transaction_id: id.clone(),
+                kind,
+                amount,
+                currency: asset.to_owned(),
+                timestamp_ms,
+                ticker: (!crypto_asset::is_fiat(asset)).then(|| asset.to_owned()),
+                listing_exchange: (!crypto_asset::is_fiat(asset))
+                    .then(|| CRYPTO_LISTING_EXCHANGE.to_owned()),
+                description: match notes {
+                    "" => transaction_type.to_owned(),
+                    notes => notes.to_owned(),
+                },
+            })
+        };
+
+        let activity = match transaction_type {
+            "Buy" | "Advanced Trade Buy" => Self::trade(
+                id.clone(),
+                asset,
+                TradeSide::Buy,
+                quantity,
+                price,
+                fees,
+                timestamp_ms,
+            ),
+            "Sell" | "Advanced Trade Sell" => Self::trade(
+                id.clone(),
+                asset,
+                TradeSide::Sell,
+                quantity,
+                price,
+                fees,
+                timestamp_ms,
+            ),
+            "Convert" => {
+                let (to_asset, to_quantity) = Self::converted_to(notes)
+                    .ok_or_else(|| anyhow!("unrecognized Coinbase convert notes '{}'", notes))?;
+                if quantity == 0.0 || to_quantity == 0.0 {
+                    return Err(anyhow!(
+                        "Coinbase convert '{}' has a zero quantity, so its prices are undefined",
+                        notes
+                    ));
+                }
+                activities.push(Self::trade(
+                    id.clone(),
+                    asset,
+                    TradeSide::Sell,
+                    quantity,
+                    subtotal / quantity,
+                    fees,
+                    timestamp_ms,
+                ));
+                Self::trade(
+                    format!("{}:to", id),
+                    &to_asset,
+                    TradeSide::Buy,
+                    to_quantity,
+                    subtotal / to_quantity,
+                    0.0,
+                    timestamp_ms,
+                )
+            }
+            "Staking Income" | "Rewards Income" | "Inflation Reward" | "Learning Reward"
+            | "Coinbase Earn" => cash(CashTransactionKind::Income, quantity),
+            "Deposit" if crypto_asset::is_fiat(asset) => {
+                cash(CashTransactionKind::Deposit, quantity)
+            }
+            "Withdrawal" if crypto_asset::is_fiat(asset) => {
+                cash(CashTransactionKind::Withdrawal, -quantity)
+            }
+            "Receive" | "Deposit" => cash(CashTransactionKind::Transfer, quantity),
+            "Send" | "Withdrawal" => cash(CashTransactionKind::Transfer, -quantity),
+            _ => {
+                debug!(
+                    "skipping unsupported Coinbase transaction type '{}'",
+                    transaction_type
+                );
+                return Ok(());
+            }
+        };
+        activities.push(activity);
+        Ok(())
+    }
+
+    /// Parses the transaction report into account activities.
+    pub fn parse(&self, content: &str) -> Result<AccountActivities> {
+        let header_offset =
+            Self::find_header(content).ok_or_else(|| anyhow!("Coinbase CSV header not found"))?;
+        let account_id = self.statement_account_id(&content[..header_offset])?;
+        let table = CsvTable::parse(&content[header_offset..])?;
+        table.require_columns(&["Quantity Transacted", "Subtotal", "Fees and/or Spread"])?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut activities = Vec::new();
+
+        for row in table.rows() {
+            let id = match row.get("ID") {
+                "" => ids.id(COINBASE_BROKERAGE_ID, &row.fields()),
+                id => id.to_owned(),
+            };
+            Self::parse_row(&row, id, &mut activities)?;
+        }
+
+        Ok(AccountActivities {
+            account_id,
+            activities,
+        })
+    }
+}
+
+#[async_trait]
+impl StatementImporter for CoinbaseCsvImporter {
+    fn importer_name(&self) -> &'static str {
+        "coinbase-csv"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if Self::find_header(content).is_some() {
+            ContentMatch::likely("Coinbase transactions CSV header")
+        } else {
+            ContentMatch::no("no Coinbase transactions CSV header")
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing Coinbase CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let account_activities = self.parse(content)?;
+        writers::write_account_activities(
+            db,
+            session,
+            COINBASE_BROKERAGE_ID,
+            &account_activities,
+            write_batch_size,
+        )
+        .await?;
+        Ok(())
+    }
+}
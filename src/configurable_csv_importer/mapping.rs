@@ -0,0 +1,201 @@
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::cash_transaction::CashTransactionKind;
+
+/// A mapping file describing one broker's CSV export.
+///
+/// Mapping files are TOML or YAML, chosen by the file extension:
+///
+/// ```toml
+/// name = "acme-csv"
+/// brokerage_id = "acme"
+/// account_id = "ACME-001"
+/// filename_glob = "acme_*.csv"
+/// date_formats = ["%d.%m.%Y %H:%M", "%d.%m.%Y"]
+/// decimal_separator = ","
+///
+/// [header]
+/// required_columns = ["Datum", "Aktion", "Symbol"]
+///
+/// [columns]
+/// date = "Datum"
+/// action = "Aktion"
+/// symbol = "Symbol"
+/// quantity = "Anzahl"
+/// price = "Kurs"
+/// amount = "Betrag"
+///
+/// [actions]
+/// buy = ["Kauf"]
+/// sell = ["Verkauf"]
+///
+/// [actions.cash]
+/// Dividende = "Dividend"
+/// ```
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CsvMapping {
+    /// The importer name the mapping registers under. It must be unique within a registry.
+    pub name: String,
+    pub brokerage_id: String,
+    /// The account id for exports that don't name the account in a column.
+    #[serde(default)]
+    pub account_id: Option<String>,
+    /// A glob matched against the file name, e.g. `acme_*.csv`. Defaults to `*.csv`.
+    #[serde(default = "default_filename_glob")]
+    pub filename_glob: String,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: char,
+    /// `chrono` formats tried in order on the date column, joined with the time column if
+    /// there is one. Formats without a time give the start of the day.
+    pub date_formats: Vec<String>,
+    /// The IANA timezone of the export's dates. Defaults to UTC.
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// The currency of exports without a currency column.
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default)]
+    pub header: CsvHeaderRules,
+    pub columns: CsvColumns,
+    pub actions: CsvActions,
+}
+
+/// How to find the header row of the export.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CsvHeaderRules {
+    /// The header row is the first line with all of these columns. Defaults to the mapped
+    /// date and action columns.
+    #[serde(default)]
+    pub required_columns: Vec<String>,
+    /// Text the content must contain before the header row, such as a report title.
+    #[serde(default)]
+    pub preamble_contains: Option<String>,
+}
+
+/// The export's column names for each field.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CsvColumns {
+    pub date: String,
+    #[serde(default)]
+    pub time: Option<String>,
+    pub action: String,
+    #[serde(default)]
+    pub account: Option<String>,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub quantity: Option<String>,
+    #[serde(default)]
+    pub price: Option<String>,
+    #[serde(default)]
+    pub commission: Option<String>,
+    #[serde(default)]
+    pub amount: Option<String>,
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// The action column's vocabulary. Values are matched case-insensitively; rows with other
+/// actions are skipped.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CsvActions {
+    #[serde(default)]
+    pub buy: Vec<String>,
+    #[serde(default)]
+    pub sell: Vec<String>,
+    /// Maps actions to the kind of cash transaction they record.
+    #[serde(default)]
+    pub cash: HashMap<String, CashTransactionKind>,
+}
+
+fn default_filename_glob() -> String {
+    "*.csv".to_owned()
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_decimal_separator() -> char {
+    '.'
+}
+
+fn default_currency() -> String {
+    "USD".to_owned()
+}
+
+impl CsvMapping {
+    pub fn from_toml(text: &str) -> Result<Self> {
+        toml::from_str(text).map_err(|e| anyhow!("invalid CSV mapping: {}", e))
+    }
+
+    pub fn from_yaml(text: &str) -> Result<Self> {
+        serde_yaml::from_str(text).map_err(|e| anyhow!("invalid CSV mapping: {}", e))
+    }
+
+    /// Loads a mapping file, parsing `.toml` files as TOML and `.yaml` or `.yml` files as YAML.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("toml") => Self::from_toml(&text),
+            Some("yaml" | "yml") => Self::from_yaml(&text),
+            _ => Err(anyhow!(
+                "CSV mapping file {:?} is neither .toml nor .yaml",
+                path
+            )),
+        }
+        .map_err(|e| anyhow!("{:?}: {}", path, e))
+    }
+
+    /// The columns the mapping reads, all of which the export must have.
+    pub fn mapped_columns(&self) -> Vec<&str> {
+        let columns = &self.columns;
+        [
+            Some(&columns.date),
+            columns.time.as_ref(),
+            Some(&columns.action),
+            columns.account.as_ref(),
+            columns.id.as_ref(),
+            columns.symbol.as_ref(),
+            columns.quantity.as_ref(),
+            columns.price.as_ref(),
+            columns.commission.as_ref(),
+            columns.amount.as_ref(),
+            columns.currency.as_ref(),
+            columns.description.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect()
+    }
+
+    /// The columns that identify the header row.
+    pub fn required_columns(&self) -> Vec<&str> {
+        if self.header.required_columns.is_empty() {
+            vec![self.columns.date.as_str(), self.columns.action.as_str()]
+        } else {
+            self.header
+                .required_columns
+                .iter()
+                .map(String::as_str)
+                .collect()
+        }
+    }
+}
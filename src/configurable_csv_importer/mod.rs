@@ -0,0 +1,336 @@
+pub mod mapping;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::{NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+use glob::{MatchOptions, Pattern};
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    content_match::ContentMatch,
+    parse_util::{self, CsvRow, CsvTable, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+use mapping::CsvMapping;
+
+/// Imports CSV exports described by a mapping file, for brokers without a dedicated importer.
+///
+/// The mapping names the export's columns, date formats, decimal separator, the action values
+/// that mean buys, sells and each kind of cash transaction, how to find the header row and
+/// which file names to consider. Each mapping registers under its own importer name, so one
+/// registry can hold importers for several brokers' exports.
+///
+/// Trades are recorded with the absolute quantity and commission. Cash amounts are recorded as
+/// exported, so the export must sign debits. Rows without an id column value get synthetic ids.
+pub struct ConfigurableCsvImporter {
+    mapping: CsvMapping,
+    filename_pattern: Pattern,
+    timezone: Tz,
+}
+
+impl ConfigurableCsvImporter {
+    /// Creates an importer for the mapping, checking that its settings are usable.
+    pub fn new(mapping: CsvMapping) -> Result<Self> {
+        let invalid = |reason: String| anyhow!("CSV mapping '{}': {}", mapping.name, reason);
+        if !mapping.delimiter.is_ascii() {
+            return Err(invalid(format!(
+                "delimiter '{}' is not ASCII",
+                mapping.delimiter
+            )));
+        }
+        if !matches!(mapping.decimal_separator, '.' | ',') {
+            return Err(invalid(format!(
+                "decimal separator '{}' is neither '.' nor ','",
+                mapping.decimal_separator
+            )));
+        }
+        if mapping.decimal_separator == mapping.delimiter {
+            return Err(invalid(format!(
+                "decimal separator '{}' is also the delimiter",
+                mapping.decimal_separator
+            )));
+        }
+        if mapping.date_formats.is_empty() {
+            return Err(invalid("no date formats".to_owned()));
+        }
+        if mapping.account_id.is_none() && mapping.columns.account.is_none() {
+            return Err(invalid(
+                "neither an account id nor an account column".to_owned(),
+            ));
+        }
+        let filename_pattern = Pattern::new(&mapping.filename_glob)
+            .map_err(|e| invalid(format!("invalid filename glob: {}", e)))?;
+        let timezone = match &mapping.timezone {
+            Some(timezone) => timezone
+                .parse::<Tz>()
+                .map_err(|e| invalid(format!("invalid timezone: {}", e)))?,
+            None => chrono_tz::UTC,
+        };
+
+        Ok(Self {
+            mapping,
+            filename_pattern,
+            timezone,
+        })
+    }
+
+    /// Creates an importer from a TOML or YAML mapping file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Self::new(CsvMapping::from_file(path)?)
+    }
+
+    pub fn mapping(&self) -> &CsvMapping {
+        &self.mapping
+    }
+
+    /// Returns the byte offset of the header row, if the content has one.
+    fn find_header(&self, content: &str) -> Option<usize> {
+        let required = self.mapping.required_columns();
+        let mut offset = 0;
+        for line in content.split_inclusive('\n') {
+            let fields = line
+                .trim_end()
+                .split(self.mapping.delimiter)
+                .map(|f| f.trim().trim_matches('"').trim())
+                .collect::<Vec<&str>>();
+            if required.iter().all(|column| fields.contains(column)) {
+                let preamble_matches = self
+                    .mapping
+                    .header
+                    .preamble_contains
+                    .as_ref()
+                    .is_none_or(|text| content[..offset].contains(text.as_str()));
+                return preamble_matches.then_some(offset);
+            }
+            offset += line.len();
+        }
+        None
+    }
+
+    fn parse_amount(&self, value: &str) -> Result<Option<f64>> {
+        parse_util::parse_amount_with_separator(value, self.mapping.decimal_separator)
+    }
+
+    fn column_amount(&self, row: &CsvRow, column: &Option<String>) -> Result<f64> {
+        match column {
+            Some(column) => Ok(self.parse_amount(row.get(column))?.unwrap_or(0.0)),
+            None => Ok(0.0),
+        }
+    }
+
+    fn column<'a>(row: &CsvRow<'a>, column: &Option<String>) -> &'a str {
+        column.as_deref().map_or("", |column| row.get(column))
+    }
+
+    fn parse_timestamp_ms(&self, row: &CsvRow) -> Result<i64> {
+        let date = row.get(&self.mapping.columns.date);
+        let value = match Self::column(row, &self.mapping.columns.time) {
+            "" => date.to_owned(),
+            time => format!("{} {}", date, time),
+        };
+        for format in &self.mapping.date_formats {
+            if let Ok(date_time) = NaiveDateTime::parse_from_str(&value, format) {
+                return parse_util::local_timestamp_ms(date_time, self.timezone);
+            }
+            if let Ok(date) = NaiveDate::parse_from_str(&value, format) {
+                return parse_util::date_timestamp_ms(date, self.timezone);
+            }
+        }
+        Err(anyhow!(
+            "CSV mapping '{}': date '{}' matches none of the date formats",
+            self.mapping.name,
+            value
+        ))
+    }
+
+    fn trade_side(&self, action: &str) -> Option<TradeSide> {
+        let actions = &self.mapping.actions;
+        if actions.buy.iter().any(|a| a.eq_ignore_ascii_case(action)) {
+            Some(TradeSide::Buy)
+        } else if actions.sell.iter().any(|a| a.eq_ignore_ascii_case(action)) {
+            Some(TradeSide::Sell)
+        } else {
+            None
+        }
+    }
+
+    fn parse_row(&self, row: &CsvRow, id: String) -> Result<Option<Activity>> {
+        let columns = &self.mapping.columns;
+        let action = row.get(&columns.action);
+        let symbol = Self::column(row, &columns.symbol);
+
+        if let Some(side) = self.trade_side(action) {
+            if symbol.is_empty() {
+                return Err(anyhow!(
+                    "CSV mapping '{}': {} row {} has no symbol",
+                    self.mapping.name,
+                    action,
+                    id
+                ));
+            }
+            return Ok(Some(Activity::Trade(TradeActivity {
+                brokerage_execution_id: id,
+                ticker: symbol.to_owned(),
+                listing_exchange: None,
+                side,
+                quantity: self.column_amount(row, &columns.quantity)?.abs(),
+                price: self.column_amount(row, &columns.price)?,
+                commission: self.column_amount(row, &columns.commission)?.abs(),
+                execution_timestamp_ms: self.parse_timestamp_ms(row)?,
+            })));
+        }
+
+        let Some(kind) = self
+            .mapping
+            .actions
+            .cash
+            .iter()
+            .find(|(a, _)| a.eq_ignore_ascii_case(action))
+            .map(|(_, kind)| kind.clone())
+        else {
+            debug!(
+                "CSV mapping '{}': skipping row with action '{}'",
+                self.mapping.name, action
+            );
+            return Ok(None);
+        };
+        let currency = match Self::column(row, &columns.currency) {
+            "" => self.mapping.currency.as_str(),
+            currency => currency,
+        };
+        Ok(Some(Activity::Cash(CashActivity {
+            brokerage_transaction_id: id,
+            kind,
+            amount: self.column_amount(row, &columns.amount)?,
+            currency: currency.to_owned(),
+            timestamp_ms: self.parse_timestamp_ms(row)?,
+            ticker: (!symbol.is_empty()).then(|| symbol.to_owned()),
+            listing_exchange: None,
+            description: Self::column(row, &columns.description).to_owned(),
+        })))
+    }
+
+    /// Parses the export into the activities of each account.
+    pub fn parse(&self, content: &str) -> Result<Vec<AccountActivities>> {
+        let header_offset = self
+            .find_header(content)
+            .ok_or_else(|| anyhow!("CSV mapping '{}': header row not found", self.mapping.name))?;
+        let table = CsvTable::parse_with_delimiter(
+            &content[header_offset..],
+            self.mapping.delimiter as u8,
+        )?;
+        table.require_columns(&self.mapping.required_columns())?;
+        // A misspelt column would otherwise read as blank, e.g. as a zero quantity.
+        table.require_columns(&self.mapping.mapped_columns())?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut accounts = Vec::<AccountActivities>::new();
+        for row in table.rows() {
+            if row.get(&self.mapping.columns.date).is_empty() {
+                // Totals and disclaimer lines.
+                continue;
+            }
+            let account_id = match Self::column(&row, &self.mapping.columns.account) {
+                "" => self.mapping.account_id.as_deref().ok_or_else(|| {
+                    anyhow!(
+                        "CSV mapping '{}': row has no account and no account id is set",
+                        self.mapping.name
+                    )
+                })?,
+                account_id => account_id,
+            };
+            let id = match Self::column(&row, &self.mapping.columns.id) {
+                "" => ids.id(&self.mapping.brokerage_id, &row.fields()),
+                id => id.to_owned(),
+            };
+
+            let Some(activity) = self.parse_row(&row, id)? else {
+                continue;
+            };
+            match accounts.iter_mut().find(|a| a.account_id == account_id) {
+                Some(account) => account.activities.push(activity),
+                None => accounts.push(AccountActivities {
+                    account_id: account_id.to_owned(),
+                    activities: vec![activity],
+                }),
+            }
+        }
+
+        Ok(accounts)
+    }
+}
+
+#[async_trait]
+impl StatementImporter for ConfigurableCsvImporter {
+    fn importer_name(&self) -> &str {
+        &self.mapping.name
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        let file_name_matches = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| self.filename_pattern.matches_with(name, options));
+        if file_name_matches {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    /// Mappings with a `preamble_contains` text are likely matches, and mappings that only
+    /// require columns, which generic CSV exports may share, are possible matches.
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        let name = &self.mapping.name;
+        match (
+            self.find_header(content),
+            &self.mapping.header.preamble_contains,
+        ) {
+            (Some(_), Some(_)) => {
+                ContentMatch::likely(format!("mapping {} preamble and required columns", name))
+            }
+            (Some(_), None) => ContentMatch::possible(format!("mapping {} required columns", name)),
+            (None, _) => ContentMatch::no(format!("no mapping {} header", name)),
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        for account_activities in self.parse(content)? {
+            writers::write_account_activities(
+                db,
+                session.clone(),
+                &self.mapping.brokerage_id,
+                &account_activities,
+                write_batch_size,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+/// How confident an importer is that it can import some content, from least to most confident.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MatchConfidence {
+    /// The content is not in the importer's format.
+    No,
+    /// The content has a few features of the format, such as generic CSV columns.
+    Possible,
+    /// The content has the distinctive features of the format, such as a broker's full CSV
+    /// header.
+    Likely,
+    /// The content identifies itself as the format, such as an XML root element or a file
+    /// header.
+    Definite,
+}
+
+/// The result of an importer's `content_matches`: its confidence and the reason for it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContentMatch {
+    confidence: MatchConfidence,
+    reason: String,
+}
+
+impl ContentMatch {
+    pub fn new(confidence: MatchConfidence, reason: impl Into<String>) -> Self {
+        Self {
+            confidence,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn definite(reason: impl Into<String>) -> Self {
+        Self::new(MatchConfidence::Definite, reason)
+    }
+
+    pub fn likely(reason: impl Into<String>) -> Self {
+        Self::new(MatchConfidence::Likely, reason)
+    }
+
+    pub fn possible(reason: impl Into<String>) -> Self {
+        Self::new(MatchConfidence::Possible, reason)
+    }
+
+    pub fn no(reason: impl Into<String>) -> Self {
+        Self::new(MatchConfidence::No, reason)
+    }
+
+    pub fn confidence(&self) -> MatchConfidence {
+        self.confidence
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// Whether the importer can import the content at all.
+    pub fn is_match(&self) -> bool {
+        self.confidence > MatchConfidence::No
+    }
+}
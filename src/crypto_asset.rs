@@ -0,0 +1,17 @@
+/// Listing exchange recorded for crypto-asset securities.
+///
+/// `brokerage-db` only stores stock securities, so a crypto asset is recorded as a security
+/// whose ticker is the asset symbol, e.g. `BTC`, listed on this exchange. Exchange-specific
+/// venues aren't used, so holdings of the same asset at different exchanges share a security.
+pub const CRYPTO_LISTING_EXCHANGE: &str = "CRYPTO";
+
+const FIAT_CURRENCIES: [&str; 10] = [
+    "USD", "EUR", "GBP", "CAD", "AUD", "CHF", "JPY", "USDC", "USDT", "DAI",
+];
+
+/// Whether the asset is a fiat currency or a stablecoin pegged to one.
+///
+/// Trades against these assets are priced in them, and balances in them are treated as cash.
+pub fn is_fiat(asset: &str) -> bool {
+    FIAT_CURRENCIES.contains(&asset)
+}
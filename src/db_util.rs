@@ -0,0 +1,95 @@
+use anyhow::Result;
+use futures::stream::TryStreamExt;
+use mongodb::{ClientSession, Database, bson::Document, options::FindOptions};
+use serde::{Serialize, de::DeserializeOwned};
+use std::{any::type_name, fmt::Debug, sync::Arc};
+use tokio::sync::Mutex;
+
+pub async fn insert<T>(
+    t: &T,
+    db: &Database,
+    collection_name: &str,
+    session: Option<Arc<Mutex<ClientSession>>>,
+) -> Result<()>
+where
+    T: Serialize + Send + Sync + Debug,
+{
+    let collection = db.collection::<T>(collection_name);
+
+    let result = if let Some(session) = session {
+        collection
+            .insert_one(t)
+            .session(&mut *session.lock().await)
+            .await?
+    } else {
+        collection.insert_one(t).await?
+    };
+
+    tracing::info!(
+        "inserted {} {:?}, _id: {}",
+        type_name::<T>(),
+        t,
+        result.inserted_id
+    );
+    Ok(())
+}
+
+/// Inserts the documents with one ordered bulk write, which stops at the first failure.
+pub async fn insert_many<T>(
+    ts: &[T],
+    db: &Database,
+    collection_name: &str,
+    session: Option<Arc<Mutex<ClientSession>>>,
+) -> Result<()>
+where
+    T: Serialize + Send + Sync + Debug,
+{
+    if ts.is_empty() {
+        return Ok(());
+    }
+    let collection = db.collection::<T>(collection_name);
+
+    let result = if let Some(session) = session {
+        collection
+            .insert_many(ts)
+            .session(&mut *session.lock().await)
+            .await?
+    } else {
+        collection.insert_many(ts).await?
+    };
+
+    tracing::info!(
+        "inserted {} {} documents",
+        result.inserted_ids.len(),
+        type_name::<T>()
+    );
+    Ok(())
+}
+
+/// Finds the documents matching the filter, in the session if there is one so that documents
+/// written earlier in its transaction are found. A projection limits the fields returned.
+pub async fn find<T>(
+    filter: Document,
+    projection: Option<Document>,
+    db: &Database,
+    collection_name: &str,
+    session: Option<Arc<Mutex<ClientSession>>>,
+) -> Result<Vec<T>>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    let collection = db.collection::<T>(collection_name);
+    let find = collection
+        .find(filter)
+        .with_options(FindOptions::builder().projection(projection).build());
+
+    let result = if let Some(session) = session {
+        let mut session = session.lock().await;
+        let mut cursor = find.session(&mut *session).await?;
+        cursor.stream(&mut session).try_collect().await?
+    } else {
+        find.await?.try_collect().await?
+    };
+
+    Ok(result)
+}
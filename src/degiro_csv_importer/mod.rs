@@ -0,0 +1,320 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    parse_util::{self, CsvRow, CsvTable, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const DEGIRO_BROKERAGE_ID: &str = "degiro";
+
+/// Listing exchange recorded for DEGIRO securities, whose ticker is their ISIN.
+///
+/// The Account Statement CSV doesn't name an exchange, so trades are recorded on this exchange
+/// too rather than on their reference exchange, and every row of an ISIN shares a security
+/// whichever export is imported first.
+pub const ISIN_LISTING_EXCHANGE: &str = "ISIN";
+
+const AMSTERDAM_TZ: Tz = chrono_tz::Europe::Amsterdam;
+
+// Column names in the English, Dutch and German exports.
+const DATE: &[&str] = &["Date", "Datum"];
+const TIME: &[&str] = &["Time", "Tijd", "Uhrzeit"];
+const PRODUCT: &[&str] = &["Product", "Produkt"];
+const ISIN: &[&str] = &["ISIN"];
+const QUANTITY: &[&str] = &["Quantity", "Aantal", "Anzahl"];
+const PRICE: &[&str] = &["Price", "Koers", "Kurs"];
+const VALUE: &[&str] = &["Value", "Waarde", "Wert"];
+const AUTOFX_FEE: &[&str] = &["AutoFX Fee", "AutoFX Kosten", "AutoFX-Gebühr"];
+const TRANSACTION_FEES: &[&str] = &[
+    "Transaction and/or third party fees",
+    "Transaction and/or third party costs",
+    "Transactiekosten en/of kosten van derden",
+    "Transaktionsgebühren und/oder Gebühren Dritter",
+];
+const DESCRIPTION: &[&str] = &["Description", "Omschrijving", "Beschreibung"];
+/// The account statement's change column holds the currency, followed by an unnamed amount.
+const CHANGE: &[&str] = &["Change", "Mutatie", "Änderung"];
+
+/// Lowercase description keywords for account statement rows, checked in order.
+const ACCOUNT_STATEMENT_KINDS: [(&[&str], CashTransactionKind); 7] = [
+    (
+        &["dividend tax", "dividendbelasting", "dividendensteuer"],
+        CashTransactionKind::Tax,
+    ),
+    (&["dividend"], CashTransactionKind::Dividend),
+    (
+        &["connection fee", "aansluitingskosten", "börsenzugang"],
+        CashTransactionKind::Fee,
+    ),
+    (
+        &[
+            "fx credit",
+            "fx debit",
+            "valuta creditering",
+            "valuta debitering",
+            "währungswechsel",
+        ],
+        CashTransactionKind::ForeignExchange,
+    ),
+    (
+        &["interest", "rente", "zinsen"],
+        CashTransactionKind::Interest,
+    ),
+    (
+        &["withdrawal", "terugstorting", "auszahlung"],
+        CashTransactionKind::Withdrawal,
+    ),
+    (
+        &["deposit", "storting", "einzahlung"],
+        CashTransactionKind::Deposit,
+    ),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DegiroCsvKind {
+    Transactions,
+    AccountStatement,
+}
+
+/// Imports the DEGIRO Transactions CSV and Account Statement CSV.
+///
+/// The exports don't name the account, so the account id is given when constructing the
+/// importer. Securities are keyed by ISIN, listed on `ISIN_LISTING_EXCHANGE`. Exports in
+/// English, Dutch and German are supported. Dutch and German exports use a decimal comma, while
+/// English exports may use either decimal separator.
+///
+/// Trades come from the Transactions CSV, with the transaction fees as their commission and any
+/// AutoFX fee recorded as a separate fee cash transaction. The Account Statement CSV supplies
+/// dividends, dividend tax, connection fees, FX conversions, interest, deposits and withdrawals;
+/// its trade and transaction fee rows repeat the Transactions CSV and are skipped.
+pub struct DegiroCsvImporter {
+    account_id: String,
+}
+
+impl DegiroCsvImporter {
+    pub fn new(account_id: &str) -> Self {
+        Self {
+            account_id: account_id.to_owned(),
+        }
+    }
+
+    /// Returns which DEGIRO export the content is, judging by its header row.
+    pub fn detect(content: &str) -> Option<DegiroCsvKind> {
+        let header = content.trim_start_matches('\u{feff}').lines().next()?;
+        let columns = header
+            .split(',')
+            .map(|c| c.trim().trim_matches('"'))
+            .collect::<Vec<&str>>();
+        let has_any = |names: &[&str]| columns.iter().any(|c| names.contains(c));
+
+        if !DATE.contains(columns.first()?) || !has_any(ISIN) {
+            None
+        } else if has_any(QUANTITY) && has_any(PRICE) {
+            Some(DegiroCsvKind::Transactions)
+        } else if has_any(CHANGE) {
+            Some(DegiroCsvKind::AccountStatement)
+        } else {
+            None
+        }
+    }
+
+    fn parse_timestamp_ms(row: &CsvRow) -> Result<i64> {
+        let date_value = row.get_any(DATE);
+        let date = NaiveDate::parse_from_str(date_value, "%d-%m-%Y")
+            .map_err(|e| anyhow!("invalid DEGIRO date '{}': {}", date_value, e))?;
+        let time = NaiveTime::parse_from_str(row.get_any(TIME), "%H:%M").unwrap_or_default();
+        parse_util::local_timestamp_ms(NaiveDateTime::new(date, time), AMSTERDAM_TZ)
+    }
+
+    /// Returns the decimal separator of exports with Dutch or German headers, which always use a
+    /// comma. English exports may use either, so their amounts are told apart by their format.
+    fn decimal_separator(content: &str) -> Option<char> {
+        let header = content.trim_start_matches('\u{feff}').lines().next()?;
+        header
+            .split(',')
+            .any(|c| c.trim().trim_matches('"') == "Datum")
+            .then_some(',')
+    }
+
+    fn parse_amount(value: &str, decimal_separator: Option<char>) -> Result<Option<f64>> {
+        match decimal_separator {
+            Some(decimal_separator) => {
+                parse_util::parse_amount_with_separator(value, decimal_separator)
+            }
+            None => parse_util::parse_localized_amount(value),
+        }
+    }
+
+    fn amount(value: &str, decimal_separator: Option<char>) -> Result<f64> {
+        Ok(Self::parse_amount(value, decimal_separator)?.unwrap_or(0.0))
+    }
+
+    fn parse_transaction(
+        row: &CsvRow,
+        id: String,
+        decimal_separator: Option<char>,
+        activities: &mut Vec<Activity>,
+    ) -> Result<()> {
+        let isin = row.get_any(ISIN);
+        if isin.is_empty() {
+            return Ok(());
+        }
+        let timestamp_ms = Self::parse_timestamp_ms(row)?;
+        let quantity = Self::amount(row.get_any(QUANTITY), decimal_separator)?;
+
+        activities.push(Activity::Trade(TradeActivity {
+            brokerage_execution_id: id.clone(),
+            ticker: isin.to_owned(),
+            listing_exchange: Some(ISIN_LISTING_EXCHANGE.to_owned()),
+            side: if quantity < 0.0 {
+                TradeSide::Sell
+            } else {
+                TradeSide::Buy
+            },
+            quantity: quantity.abs(),
+            price: Self::amount(row.get_any(PRICE), decimal_separator)?,
+            commission: Self::amount(row.get_any(TRANSACTION_FEES), decimal_separator)?.abs(),
+            execution_timestamp_ms: timestamp_ms,
+        }));
+
+        let autofx_fee = Self::amount(row.get_any(AUTOFX_FEE), decimal_separator)?;
+        if autofx_fee != 0.0 {
+            activities.push(Activity::Cash(CashActivity {
+                brokerage_transaction_id: format!("{}:autofx", id),
+                kind: CashTransactionKind::Fee,
+                amount: -autofx_fee.abs(),
+                currency: match row.get_any_after(VALUE) {
+                    "" => "EUR".to_owned(),
+                    currency => currency.to_owned(),
+                },
+                timestamp_ms,
+                ticker: Some(isin.to_owned()),
+                listing_exchange: Some(ISIN_LISTING_EXCHANGE.to_owned()),
+                description: format!("AutoFX fee: {}", row.get_any(PRODUCT)),
+            }));
+        }
+        Ok(())
+    }
+
+    fn parse_account_statement_row(
+        row: &CsvRow,
+        id: String,
+        decimal_separator: Option<char>,
+        activities: &mut Vec<Activity>,
+    ) -> Result<()> {
+        let description = row.get_any(DESCRIPTION);
+        let lowercase = description.to_lowercase();
+        let Some(kind) = ACCOUNT_STATEMENT_KINDS
+            .iter()
+            .find(|(keywords, _)| keywords.iter().any(|k| lowercase.contains(k)))
+            .map(|(_, kind)| kind.clone())
+        else {
+            debug!("skipping DEGIRO account statement row '{}'", description);
+            return Ok(());
+        };
+        let Some(amount) = Self::parse_amount(row.get_any_after(CHANGE), decimal_separator)? else {
+            return Ok(());
+        };
+
+        let isin = row.get_any(ISIN);
+        activities.push(Activity::Cash(CashActivity {
+            brokerage_transaction_id: id,
+            kind,
+            amount,
+            currency: row.get_any(CHANGE).to_owned(),
+            timestamp_ms: Self::parse_timestamp_ms(row)?,
+            ticker: (!isin.is_empty()).then(|| isin.to_owned()),
+            listing_exchange: (!isin.is_empty()).then(|| ISIN_LISTING_EXCHANGE.to_owned()),
+            description: description.to_owned(),
+        }));
+        Ok(())
+    }
+
+    /// Parses either DEGIRO export into account activities.
+    pub fn parse(&self, content: &str) -> Result<AccountActivities> {
+        let kind = Self::detect(content).ok_or_else(|| anyhow!("DEGIRO CSV header not found"))?;
+        let table = CsvTable::parse(content.trim_start_matches('\u{feff}'))?;
+        let decimal_separator = Self::decimal_separator(content);
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut activities = Vec::new();
+
+        for row in table.rows() {
+            let id = ids.id(DEGIRO_BROKERAGE_ID, &row.fields());
+            match kind {
+                DegiroCsvKind::Transactions => {
+                    Self::parse_transaction(&row, id, decimal_separator, &mut activities)?
+                }
+                DegiroCsvKind::AccountStatement => {
+                    Self::parse_account_statement_row(&row, id, decimal_separator, &mut activities)?
+                }
+            }
+        }
+
+        Ok(AccountActivities {
+            account_id: self.account_id.clone(),
+            activities,
+        })
+    }
+}
+
+#[async_trait]
+impl StatementImporter for DegiroCsvImporter {
+    fn importer_name(&self) -> &'static str {
+        "degiro-csv"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        match Self::detect(content) {
+            Some(kind) => ContentMatch::likely(format!("DEGIRO {:?} CSV header", kind)),
+            None => ContentMatch::no("no DEGIRO CSV header"),
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing DEGIRO CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let account_activities = self.parse(content)?;
+        writers::write_account_activities(
+            db,
+            session,
+            DEGIRO_BROKERAGE_ID,
+            &account_activities,
+            write_batch_size,
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+pub mod processed_email;
+
+use anyhow::{Result, anyhow};
+use mail_parser::{MessageParser, MimeHeaders, mailbox};
+use mongodb::{ClientSession, Database};
+use std::{
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::{
+    imported_file::ImportedFile, importer_registry::ImporterRegistry,
+    parse_util::SyntheticIdGenerator,
+};
+use processed_email::ProcessedEmail;
+
+/// A local store of emails.
+#[derive(Debug, PartialEq)]
+pub enum Mailbox {
+    /// A single RFC 5322 message file.
+    Eml(PathBuf),
+    /// An mbox file of concatenated messages.
+    Mbox(PathBuf),
+    /// A Maildir directory with `cur` and `new` subdirectories.
+    Maildir(PathBuf),
+}
+
+impl Mailbox {
+    /// Returns the mailbox at the path: a Maildir for directories, a single message for `.eml`
+    /// files, and an mbox otherwise.
+    pub fn at(path: &Path) -> Self {
+        if path.is_dir() {
+            Mailbox::Maildir(path.to_owned())
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("eml"))
+        {
+            Mailbox::Eml(path.to_owned())
+        } else {
+            Mailbox::Mbox(path.to_owned())
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Mailbox::Eml(path) | Mailbox::Mbox(path) | Mailbox::Maildir(path) => path,
+        }
+    }
+
+    /// Reads the raw messages of the mailbox.
+    pub fn messages(&self) -> Result<Vec<Vec<u8>>> {
+        let messages = match self {
+            Mailbox::Eml(path) => vec![fs::read(path)?],
+            Mailbox::Mbox(path) => {
+                mailbox::mbox::MessageIterator::new(BufReader::new(fs::File::open(path)?))
+                    .map(|message| message.map(|m| m.unwrap_contents()))
+                    .collect::<std::io::Result<Vec<Vec<u8>>>>()?
+            }
+            Mailbox::Maildir(path) => {
+                let mut messages = mailbox::maildir::MessageIterator::new(path)?
+                    .collect::<std::io::Result<Vec<mailbox::maildir::Message>>>()?;
+                // Directory order is arbitrary; process messages in delivery order.
+                messages.sort_by_key(|m| (m.internal_date(), m.path().to_owned()));
+                messages.into_iter().map(|m| m.unwrap_contents()).collect()
+            }
+        };
+        Ok(messages)
+    }
+}
+
+/// An attachment or inline body of an email, named so it can be routed like a statement file.
+#[derive(Debug, PartialEq)]
+pub struct EmailDocument {
+    /// `<mailbox>!/<message id>/<attachment number>/<file name>` for attachments, numbered from 1
+    /// so that attachments with the same name have different paths, and
+    /// `<mailbox>!/<message id>/body.txt` for inline bodies, with a number for messages with more
+    /// than one.
+    pub path: PathBuf,
+    pub bytes: Vec<u8>,
+}
+
+/// A parsed email and the documents that may hold statements.
+#[derive(Debug, PartialEq)]
+pub struct EmailMessage {
+    /// The `Message-ID` header without its angle brackets, or a hash of the message for
+    /// messages without one.
+    pub message_id: String,
+    pub subject: String,
+    pub documents: Vec<EmailDocument>,
+}
+
+/// Parses a raw email into its named attachments and inline text bodies.
+///
+/// HTML-only bodies are converted to text.
+pub fn parse_email(raw: &[u8], mailbox: &Path) -> Result<EmailMessage> {
+    let message = MessageParser::default()
+        .parse(raw)
+        .ok_or_else(|| anyhow!("invalid email in {:?}", mailbox))?;
+    let message_id = match message.message_id() {
+        Some(message_id) => message_id.to_owned(),
+        None => SyntheticIdGenerator::new().id("email", &[&String::from_utf8_lossy(raw)]),
+    };
+    let document_path = |name: &str| {
+        // Message ids may contain '/', which would add path components.
+        PathBuf::from(format!(
+            "{}!/{}/{}",
+            mailbox.display(),
+            message_id.replace('/', "_"),
+            name
+        ))
+    };
+
+    let mut documents = Vec::new();
+    for (index, attachment) in message.attachments().enumerate() {
+        let Some(name) = attachment.attachment_name() else {
+            debug!("skipping unnamed attachment of email {}", message_id);
+            continue;
+        };
+        documents.push(EmailDocument {
+            path: document_path(&format!("{}/{}", index + 1, name)),
+            bytes: attachment.contents().to_vec(),
+        });
+    }
+    for index in 0..message.text_body_count() {
+        let Some(text) = message.body_text(index).filter(|t| !t.trim().is_empty()) else {
+            continue;
+        };
+        let name = match index {
+            0 => "body.txt".to_owned(),
+            index => format!("body-{}.txt", index + 1),
+        };
+        documents.push(EmailDocument {
+            path: document_path(&name),
+            bytes: text.into_owned().into_bytes(),
+        });
+    }
+
+    Ok(EmailMessage {
+        message_id: message_id.clone(),
+        subject: message.subject().unwrap_or_default().to_owned(),
+        documents,
+    })
+}
+
+/// Counts of what `ingest_mailbox` did.
+#[derive(Debug, Default, PartialEq)]
+pub struct EmailIngestSummary {
+    pub messages_processed: u32,
+    /// Messages skipped because their message id was processed before.
+    pub messages_skipped: u32,
+    pub documents_imported: u32,
+    /// Documents skipped because the import ledger already records their path.
+    pub documents_skipped: u32,
+}
+
+/// Imports the statements attached to or inlined in the emails of a mailbox.
+///
+/// Each attachment and inline body is routed through the registry as if it were a file, by
+/// its document path. Documents that no importer recognizes, such as logos and signatures,
+/// are skipped, as are documents whose path is already in the import ledger, e.g. when an
+/// earlier ingestion failed after importing some of a message's documents. Once all of a
+/// message's documents are imported, its message id is recorded, and messages with recorded
+/// ids are skipped, so each email is processed once.
+pub async fn ingest_mailbox(
+    registry: &ImporterRegistry,
+    db: &Database,
+    session: Option<Arc<Mutex<ClientSession>>>,
+    mailbox: &Mailbox,
+) -> Result<EmailIngestSummary> {
+    ProcessedEmail::create_indexes(db).await?;
+    let mut summary = EmailIngestSummary::default();
+    for raw in mailbox.messages()? {
+        let message = parse_email(&raw, mailbox.path())?;
+        if ProcessedEmail::find_by_message_id(db, session.clone(), &message.message_id)
+            .await?
+            .is_some()
+        {
+            debug!("skipping already processed email {}", message.message_id);
+            summary.messages_skipped += 1;
+            continue;
+        }
+
+        info!(
+            "ingesting email {} '{}' with {} documents",
+            message.message_id,
+            message.subject,
+            message.documents.len()
+        );
+        let mut imported_documents = 0;
+        for document in &message.documents {
+            if ImportedFile::is_imported(db, session.clone(), &document.path.to_string_lossy())
+                .await?
+            {
+                debug!("skipping already imported {:?}", document.path);
+                summary.documents_skipped += 1;
+                continue;
+            }
+            if registry
+                .import_statement_bytes(db, session.clone(), &document.path, &document.bytes)
+                .await?
+            {
+                imported_documents += 1;
+            } else {
+                debug!("no importer recognizes {:?}", document.path);
+            }
+        }
+
+        ProcessedEmail::new(&message.message_id, &message.subject, imported_documents)
+            .insert(db, session.clone())
+            .await?;
+        summary.messages_processed += 1;
+        summary.documents_imported += imported_documents;
+    }
+
+    Ok(summary)
+}
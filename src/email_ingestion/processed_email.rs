@@ -0,0 +1,88 @@
+use anyhow::Result;
+use mongodb::{
+    ClientSession, Database, IndexModel,
+    bson::{doc, oid::ObjectId},
+    options::IndexOptions,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_util;
+
+/// An email whose statements have been ingested, keyed by its message id so that the same
+/// email is never processed twice, even when it is in several mailboxes.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ProcessedEmail {
+    _id: ObjectId,
+    message_id: String,
+    subject: String,
+    imported_documents: u32,
+}
+
+impl ProcessedEmail {
+    pub const COLLECTION_NAME: &'static str = "processed_emails";
+
+    pub fn new(message_id: &str, subject: &str, imported_documents: u32) -> Self {
+        Self {
+            _id: ObjectId::new(),
+            message_id: message_id.to_owned(),
+            subject: subject.to_owned(),
+            imported_documents,
+        }
+    }
+
+    pub fn id(&self) -> ObjectId {
+        self._id
+    }
+
+    pub fn message_id(&self) -> &str {
+        &self.message_id
+    }
+
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// The number of attachments and bodies that an importer recognized.
+    pub fn imported_documents(&self) -> u32 {
+        self.imported_documents
+    }
+
+    pub async fn insert(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+    ) -> Result<()> {
+        db_util::insert(self, db, Self::COLLECTION_NAME, session).await
+    }
+
+    /// Creates the unique index on the message id, so that an email recorded concurrently by
+    /// two ingestions fails the second one instead of being recorded twice.
+    pub async fn create_indexes(db: &Database) -> Result<()> {
+        let index = IndexModel::builder()
+            .keys(doc! { "message_id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        db.collection::<Self>(Self::COLLECTION_NAME)
+            .create_index(index)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn find_by_message_id(
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        message_id: &str,
+    ) -> Result<Option<Self>> {
+        let collection = db.collection::<Self>(Self::COLLECTION_NAME);
+        let find = collection.find_one(doc! { "message_id": message_id });
+        let result = if let Some(session) = session {
+            find.session(&mut *session.lock().await).await?
+        } else {
+            find.await?
+        };
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,270 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::NaiveDate;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{collections::HashMap, path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    parse_util::{self, CsvRow, CsvTable, NEW_YORK_TZ, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const ETRADE_BROKERAGE_ID: &str = "etrade";
+
+const ETRADE_HEADERS: [&[&str]; 2] = [
+    &["Activity/Trade Date", "Transaction Date", "Settlement Date"],
+    &["TransactionDate", "TransactionType", "SecurityType"],
+];
+
+/// Description keywords identifying share deposits from equity compensation plans.
+const EQUITY_COMPENSATION_KEYWORDS: [&str; 5] = ["RSU", "ESPP", "RESTRICTED", "VEST", "RELEASE"];
+
+/// Imports the E*TRADE (Morgan Stanley) transaction history CSV.
+///
+/// Share deposits from RSU vesting and ESPP purchases are imported as buys at the vest-date fair
+/// market value, so they carry a cost basis. The value comes from a `FMV` amount in the
+/// description or a value configured with `vest_fair_market_value`. The row's price, which for
+/// ESPP purchases is the discounted purchase price, is used only failing those.
+pub struct EtradeCsvImporter {
+    account_id: Option<String>,
+    vest_fair_market_values: HashMap<(String, NaiveDate), f64>,
+}
+
+impl Default for EtradeCsvImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EtradeCsvImporter {
+    pub fn new() -> Self {
+        Self {
+            account_id: None,
+            vest_fair_market_values: HashMap::new(),
+        }
+    }
+
+    /// Sets the account id used when the export doesn't start with a `For Account:` line.
+    pub fn account_id(mut self, account_id: &str) -> Self {
+        self.account_id = Some(account_id.to_owned());
+        self
+    }
+
+    /// Sets the per-share fair market value of shares of `symbol` deposited on `date`.
+    pub fn vest_fair_market_value(mut self, symbol: &str, date: NaiveDate, value: f64) -> Self {
+        self.vest_fair_market_values
+            .insert((symbol.to_owned(), date), value);
+        self
+    }
+
+    fn find_header(content: &str) -> Option<usize> {
+        ETRADE_HEADERS
+            .iter()
+            .find_map(|header| parse_util::find_csv_header(content, header))
+    }
+
+    fn statement_account_id(&self, preamble: &str) -> Result<String> {
+        let from_preamble = preamble.lines().find_map(|line| {
+            let rest = line.trim().strip_prefix("For Account:")?;
+            Some(rest.trim_matches(|c: char| c == ',' || c == '"' || c.is_whitespace()))
+        });
+
+        from_preamble
+            .filter(|account_id| !account_id.is_empty())
+            .map(str::to_owned)
+            .or_else(|| self.account_id.clone())
+            .ok_or_else(|| anyhow!("E*TRADE CSV does not name its account and none is configured"))
+    }
+
+    fn parse_date(value: &str) -> Result<NaiveDate> {
+        NaiveDate::parse_from_str(value, "%m/%d/%y")
+            .or_else(|_| parse_util::parse_us_date(value))
+            .map_err(|e| anyhow!("invalid E*TRADE date '{}': {}", value, e))
+    }
+
+    fn row_fair_market_value(&self, row: &CsvRow, symbol: &str, date: NaiveDate) -> Option<f64> {
+        let price = parse_util::parse_amount(row.get_any(&["Price $", "Price"]))
+            .ok()
+            .flatten()
+            .filter(|price| *price > 0.0);
+
+        let description = row.get("Description").to_uppercase();
+        let from_description = description.split_once("FMV").and_then(|(_, rest)| {
+            let value = rest
+                .trim_start_matches(|c: char| c == ':' || c == '$' || c == '@' || c.is_whitespace())
+                .split(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+                .next()?;
+            parse_util::parse_amount(value).ok().flatten()
+        });
+
+        from_description
+            .or_else(|| {
+                self.vest_fair_market_values
+                    .get(&(symbol.to_owned(), date))
+                    .copied()
+            })
+            .or(price)
+    }
+
+    /// Parses the transaction history CSV into account activities.
+    pub fn parse(&self, content: &str) -> Result<AccountActivities> {
+        let header_offset =
+            Self::find_header(content).ok_or_else(|| anyhow!("E*TRADE CSV header not found"))?;
+        let account_id = self.statement_account_id(&content[..header_offset])?;
+        let table = CsvTable::parse(&content[header_offset..])?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut activities = Vec::new();
+
+        for row in table.rows() {
+            let Ok(date) =
+                Self::parse_date(row.get_any(&["Activity/Trade Date", "TransactionDate"]))
+            else {
+                continue;
+            };
+            let timestamp_ms = parse_util::date_timestamp_ms(date, NEW_YORK_TZ)?;
+
+            let activity_type = row.get_any(&["Activity Type", "TransactionType"]);
+            let symbol = row.get("Symbol");
+            let description = row.get("Description");
+            let quantity =
+                parse_util::parse_amount_or_zero(row.get_any(&["Quantity #", "Quantity"]))?;
+            let amount = parse_util::parse_amount_or_zero(row.get_any(&["Amount $", "Amount"]))?;
+            let commission = parse_util::parse_amount_or_zero(row.get("Commission"))?.abs();
+            let id = ids.id(ETRADE_BROKERAGE_ID, &row.fields());
+
+            let is_share_deposit = matches!(activity_type, "Receive" | "Transfer" | "Deposit")
+                && quantity > 0.0
+                && !symbol.is_empty();
+            let is_equity_compensation = EQUITY_COMPENSATION_KEYWORDS
+                .iter()
+                .any(|keyword| description.to_uppercase().contains(keyword));
+
+            let trade = match activity_type {
+                "Bought" | "Reinvest" => Some((
+                    TradeSide::Buy,
+                    parse_util::parse_amount_or_zero(row.get_any(&["Price $", "Price"]))?,
+                )),
+                "Sold" => Some((
+                    TradeSide::Sell,
+                    parse_util::parse_amount_or_zero(row.get_any(&["Price $", "Price"]))?,
+                )),
+                _ if is_share_deposit && is_equity_compensation => {
+                    let fair_market_value = self
+                        .row_fair_market_value(&row, symbol, date)
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "no fair market value for {} shares of {} deposited on {}",
+                                quantity,
+                                symbol,
+                                date
+                            )
+                        })?;
+                    Some((TradeSide::Buy, fair_market_value))
+                }
+                _ => None,
+            };
+
+            if let Some((side, price)) = trade {
+                activities.push(Activity::Trade(TradeActivity {
+                    brokerage_execution_id: id,
+                    ticker: symbol.to_owned(),
+                    listing_exchange: None,
+                    side,
+                    quantity: quantity.abs(),
+                    price,
+                    commission,
+                    execution_timestamp_ms: timestamp_ms,
+                }));
+                continue;
+            }
+
+            let kind = match activity_type {
+                "Dividend" | "Qualified Dividend" => CashTransactionKind::Dividend,
+                "Interest" | "Interest Income" => CashTransactionKind::Interest,
+                "Fee" | "Service Fee" => CashTransactionKind::Fee,
+                "Tax Withholding" | "Foreign Tax" => CashTransactionKind::Tax,
+                "Deposit" | "Online Transfer" if symbol.is_empty() => CashTransactionKind::Deposit,
+                "Withdrawal" => CashTransactionKind::Withdrawal,
+                "Transfer" if symbol.is_empty() => CashTransactionKind::Transfer,
+                _ => {
+                    debug!("skipping unsupported E*TRADE activity '{}'", activity_type);
+                    continue;
+                }
+            };
+
+            activities.push(Activity::Cash(CashActivity {
+                brokerage_transaction_id: id,
+                kind,
+                amount,
+                currency: "USD".to_owned(),
+                timestamp_ms,
+                ticker: (!symbol.is_empty()).then(|| symbol.to_owned()),
+                listing_exchange: None,
+                description: description.to_owned(),
+            }));
+        }
+
+        Ok(AccountActivities {
+            account_id,
+            activities,
+        })
+    }
+}
+
+#[async_trait]
+impl StatementImporter for EtradeCsvImporter {
+    fn importer_name(&self) -> &'static str {
+        "etrade-csv"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if Self::find_header(content).is_some() {
+            ContentMatch::likely("E*TRADE transactions CSV header")
+        } else {
+            ContentMatch::no("no E*TRADE transactions CSV header")
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing E*TRADE CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let account_activities = self.parse(content)?;
+        writers::write_account_activities(
+            db,
+            session,
+            ETRADE_BROKERAGE_ID,
+            &account_activities,
+            write_batch_size,
+        )
+        .await?;
+        Ok(())
+    }
+}
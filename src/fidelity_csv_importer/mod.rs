@@ -0,0 +1,237 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    parse_util::{self, CsvRow, CsvTable, NEW_YORK_TZ, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const FIDELITY_BROKERAGE_ID: &str = "fidelity";
+
+/// Money market funds Fidelity uses as the core (cash sweep) position of an account.
+const CORE_MONEY_MARKET_SYMBOLS: [&str; 5] = ["SPAXX", "FDRXX", "FZFXX", "SPRXX", "FCASH"];
+
+/// Imports the "Accounts History" CSV download from Fidelity.
+///
+/// The download starts with blank preamble lines and ends with disclaimer text, both of which are
+/// skipped. Multi-account downloads name the account on each row; single-account downloads
+/// (`History_for_Account_<number>.csv`) don't, so the account id must then be configured with
+/// `account_id`.
+pub struct FidelityCsvImporter {
+    account_id: Option<String>,
+}
+
+impl Default for FidelityCsvImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FidelityCsvImporter {
+    pub fn new() -> Self {
+        Self { account_id: None }
+    }
+
+    /// Sets the account id used when the download doesn't name the account on each row.
+    pub fn account_id(mut self, account_id: &str) -> Self {
+        self.account_id = Some(account_id.to_owned());
+        self
+    }
+
+    fn row_account_id(&self, row: &CsvRow) -> Result<String> {
+        let account_number = row.get("Account Number");
+        if !account_number.is_empty() {
+            return Ok(account_number.to_owned());
+        }
+
+        // Older downloads combine the account name and number, e.g. "Individual X12345678", but
+        // may also name the account alone, e.g. "ROTH IRA".
+        if let Some(account_number) = row
+            .get("Account")
+            .split_whitespace()
+            .last()
+            .filter(|token| token.chars().any(|c| c.is_ascii_digit()))
+        {
+            return Ok(account_number.to_owned());
+        }
+
+        self.account_id
+            .clone()
+            .ok_or_else(|| anyhow!("Fidelity CSV does not name its account and none is configured"))
+    }
+
+    /// Parses the history CSV into activities, grouped by account in order of appearance.
+    pub fn parse(&self, content: &str) -> Result<Vec<AccountActivities>> {
+        let header_offset = parse_util::find_csv_header(content, &["Run Date"])
+            .ok_or_else(|| anyhow!("Fidelity CSV header not found"))?;
+        let table = CsvTable::parse(&content[header_offset..])?;
+        table.require_columns(&["Run Date", "Action", "Symbol"])?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut accounts = Vec::<AccountActivities>::new();
+
+        for row in table.rows() {
+            // Disclaimer text at the end of the download has no run date.
+            let Ok(date) = parse_util::parse_us_date(row.get("Run Date")) else {
+                continue;
+            };
+            let timestamp_ms = parse_util::date_timestamp_ms(date, NEW_YORK_TZ)?;
+
+            let action = row.get("Action");
+            let upper_action = action.to_uppercase();
+            let symbol = row.get("Symbol");
+            let is_core_position = CORE_MONEY_MARKET_SYMBOLS.contains(&symbol);
+            let amount = parse_util::parse_amount_or_zero(row.get_any(&["Amount ($)", "Amount"]))?;
+            let currency = match row.get("Currency") {
+                "" => "USD",
+                currency => currency,
+            };
+            let id = ids.id(FIDELITY_BROKERAGE_ID, &row.fields());
+
+            let side = if upper_action.starts_with("YOU BOUGHT")
+                || (upper_action.starts_with("REINVESTMENT") && !is_core_position)
+            {
+                Some(TradeSide::Buy)
+            } else if upper_action.starts_with("YOU SOLD") {
+                Some(TradeSide::Sell)
+            } else {
+                None
+            };
+
+            let activity = if let Some(side) = side {
+                let commission = parse_util::parse_amount_or_zero(
+                    row.get_any(&["Commission ($)", "Commission"]),
+                )?;
+                let fees = parse_util::parse_amount_or_zero(row.get_any(&["Fees ($)", "Fees"]))?;
+
+                Activity::Trade(TradeActivity {
+                    brokerage_execution_id: id,
+                    ticker: symbol.to_owned(),
+                    listing_exchange: None,
+                    side,
+                    quantity: parse_util::parse_amount_or_zero(row.get("Quantity"))?.abs(),
+                    price: parse_util::parse_amount_or_zero(row.get_any(&["Price ($)", "Price"]))?,
+                    commission: commission.abs() + fees.abs(),
+                    execution_timestamp_ms: timestamp_ms,
+                })
+            } else {
+                let kind = if upper_action.starts_with("DIVIDEND RECEIVED") {
+                    CashTransactionKind::Dividend
+                } else if upper_action.starts_with("INTEREST EARNED") {
+                    CashTransactionKind::Interest
+                } else if upper_action.contains("CAP GAIN") {
+                    CashTransactionKind::CapitalGainDistribution
+                } else if upper_action.starts_with("FOREIGN TAX") {
+                    CashTransactionKind::Tax
+                } else if upper_action.contains("FEE") {
+                    CashTransactionKind::Fee
+                } else if upper_action.contains("CONTRIBUTION")
+                    || upper_action.contains("DIRECT DEPOSIT")
+                    || upper_action.contains("FUNDS TRANSFER RECEIVED")
+                {
+                    CashTransactionKind::Deposit
+                } else if upper_action.contains("TRANSFER") || upper_action.contains("JOURNAL") {
+                    CashTransactionKind::Transfer
+                } else if upper_action.contains("DIRECT DEBIT")
+                    || upper_action.contains("DISTRIBUTION")
+                {
+                    CashTransactionKind::Withdrawal
+                } else {
+                    // Includes sweeps into and out of the core money market position, which
+                    // only move cash within the account.
+                    debug!("skipping unsupported Fidelity action '{}'", action);
+                    continue;
+                };
+
+                Activity::Cash(CashActivity {
+                    brokerage_transaction_id: id,
+                    kind,
+                    amount,
+                    currency: currency.to_owned(),
+                    timestamp_ms,
+                    ticker: (!symbol.is_empty() && !is_core_position).then(|| symbol.to_owned()),
+                    listing_exchange: None,
+                    description: action.to_owned(),
+                })
+            };
+
+            let account_id = self.row_account_id(&row)?;
+            match accounts.iter_mut().find(|a| a.account_id == account_id) {
+                Some(account) => account.activities.push(activity),
+                None => accounts.push(AccountActivities {
+                    account_id,
+                    activities: vec![activity],
+                }),
+            }
+        }
+
+        Ok(accounts)
+    }
+}
+
+#[async_trait]
+impl StatementImporter for FidelityCsvImporter {
+    fn importer_name(&self) -> &'static str {
+        "fidelity-csv"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        let file_name = path.file_name().and_then(|f| f.to_str()).unwrap_or("");
+        if (file_name.starts_with("History_for_Account")
+            || file_name.starts_with("Accounts_History"))
+            && path.extension().is_some_and(|ext| ext == "csv")
+        {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        let header = parse_util::find_csv_header(content, &["Run Date"])
+            .and_then(|offset| content[offset..].lines().next());
+        if header.is_some_and(|h| h.contains("Action") && h.contains("Symbol")) {
+            ContentMatch::likely("Fidelity account history CSV header")
+        } else {
+            ContentMatch::no("no Fidelity account history CSV header")
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing Fidelity CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        for account_activities in self.parse(content)? {
+            writers::write_account_activities(
+                db,
+                session.clone(),
+                FIDELITY_BROKERAGE_ID,
+                &account_activities,
+                write_batch_size,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
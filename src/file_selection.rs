@@ -0,0 +1,174 @@
+use anyhow::{Result, anyhow};
+use glob::{MatchOptions, Pattern};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::{debug, warn};
+
+/// Suffixes of editor backups, partial downloads and other temporary files.
+const TEMP_FILE_SUFFIXES: [&str; 7] = [
+    "~",
+    ".tmp",
+    ".temp",
+    ".part",
+    ".partial",
+    ".crdownload",
+    ".swp",
+];
+
+/// Directories, files and glob patterns of statement files to import.
+///
+/// Directories are walked recursively, following symbolic links to directories, each of which
+/// is walked once so that links back up the tree don't loop. Broken links are skipped with a
+/// warning. Files found by walking or by a glob pattern are skipped if they or a directory below
+/// the root are hidden, or they look like temporary files such as editor backups and partial
+/// downloads. Explicitly named files are always selected.
+///
+/// Include and exclude patterns apply to every selected file. Patterns containing `/` are
+/// matched against the whole path, others against the file name, e.g. `*.xml` or
+/// `**/drafts/**`. With include patterns, only files matching one of them are selected.
+#[derive(Debug, Default)]
+pub struct FileSelection {
+    roots: Vec<String>,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+fn is_glob(root: &str) -> bool {
+    root.contains(['*', '?', '['])
+}
+
+fn is_hidden_or_temp(name: &str) -> bool {
+    let lowercase = name.to_lowercase();
+    name.starts_with('.')
+        || name.starts_with("~$")
+        || name.starts_with('#')
+        || TEMP_FILE_SUFFIXES
+            .iter()
+            .any(|suffix| lowercase.ends_with(suffix))
+}
+
+impl FileSelection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, a directory to walk, or a glob pattern such as `statements/**/*.xml`.
+    pub fn path(mut self, path: &str) -> Self {
+        self.roots.push(path.to_owned());
+        self
+    }
+
+    pub fn include(mut self, pattern: &str) -> Result<Self> {
+        self.include.push(Self::pattern(pattern)?);
+        Ok(self)
+    }
+
+    pub fn exclude(mut self, pattern: &str) -> Result<Self> {
+        self.exclude.push(Self::pattern(pattern)?);
+        Ok(self)
+    }
+
+    fn pattern(pattern: &str) -> Result<Pattern> {
+        Pattern::new(pattern).map_err(|e| anyhow!("invalid file pattern '{}': {}", pattern, e))
+    }
+
+    fn pattern_matches(pattern: &Pattern, path: &Path) -> bool {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        if pattern.as_str().contains('/') {
+            pattern.matches_path_with(path, options)
+        } else {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| pattern.matches_with(name, options))
+        }
+    }
+
+    fn is_selected(&self, path: &Path) -> bool {
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| Self::pattern_matches(pattern, path));
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| Self::pattern_matches(pattern, path))
+    }
+
+    fn walk(
+        dir: &Path,
+        files: &mut BTreeSet<PathBuf>,
+        walked_dirs: &mut HashSet<PathBuf>,
+    ) -> Result<()> {
+        if !walked_dirs.insert(fs::canonicalize(dir)?) {
+            debug!("skipping already walked directory {:?}", dir);
+            return Ok(());
+        }
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name();
+            if is_hidden_or_temp(&name.to_string_lossy()) {
+                debug!("skipping hidden or temporary file {:?}", path);
+                continue;
+            }
+            match fs::metadata(&path) {
+                Ok(metadata) if metadata.is_dir() => Self::walk(&path, files, walked_dirs)?,
+                Ok(_) => {
+                    files.insert(path);
+                }
+                Err(e) => warn!("skipping {:?}, which can't be read: {}", path, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the selected files, sorted by path.
+    pub fn files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = BTreeSet::new();
+        let mut walked_dirs = HashSet::new();
+        for root in &self.roots {
+            if is_glob(root) {
+                // Wildcards don't match hidden files and directories.
+                let options = MatchOptions {
+                    require_literal_leading_dot: true,
+                    ..MatchOptions::new()
+                };
+                let matches = glob::glob_with(root, options)
+                    .map_err(|e| anyhow!("invalid glob pattern '{}': {}", root, e))?;
+                for path in matches {
+                    let path = path?;
+                    if path.is_dir() {
+                        Self::walk(&path, &mut files, &mut walked_dirs)?;
+                    } else if path
+                        .file_name()
+                        .is_some_and(|name| !is_hidden_or_temp(&name.to_string_lossy()))
+                    {
+                        files.insert(path);
+                    }
+                }
+            } else {
+                let path = PathBuf::from(root);
+                if path.is_dir() {
+                    Self::walk(&path, &mut files, &mut walked_dirs)?;
+                } else if path.is_file() {
+                    files.insert(path);
+                } else {
+                    return Err(anyhow!("statement path {:?} does not exist", path));
+                }
+            }
+        }
+
+        Ok(files
+            .into_iter()
+            .filter(|path| self.is_selected(path))
+            .collect())
+    }
+}
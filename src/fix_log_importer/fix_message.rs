@@ -0,0 +1,105 @@
+use anyhow::{Result, anyhow};
+use chrono::NaiveDateTime;
+
+pub const TAG_ACCOUNT: u32 = 1;
+pub const TAG_COMMISSION: u32 = 12;
+pub const TAG_COMM_TYPE: u32 = 13;
+pub const TAG_EXEC_ID: u32 = 17;
+pub const TAG_EXEC_REF_ID: u32 = 19;
+pub const TAG_EXEC_TRANS_TYPE: u32 = 20;
+pub const TAG_LAST_PX: u32 = 31;
+pub const TAG_LAST_QTY: u32 = 32;
+pub const TAG_MSG_TYPE: u32 = 35;
+pub const TAG_SENDING_TIME: u32 = 52;
+pub const TAG_SIDE: u32 = 54;
+pub const TAG_SYMBOL: u32 = 55;
+pub const TAG_TRANSACT_TIME: u32 = 60;
+pub const TAG_EXEC_TYPE: u32 = 150;
+pub const TAG_SECURITY_TYPE: u32 = 167;
+pub const TAG_MATURITY_MONTH_YEAR: u32 = 200;
+pub const TAG_PUT_OR_CALL: u32 = 201;
+pub const TAG_STRIKE_PRICE: u32 = 202;
+pub const TAG_MATURITY_DAY: u32 = 205;
+pub const TAG_MATURITY_DATE: u32 = 541;
+
+/// The `MsgType` of an ExecutionReport.
+pub const MSG_TYPE_EXECUTION_REPORT: &str = "8";
+
+/// The start of every FIX message, the `BeginString` field.
+const BEGIN_STRING: &str = "8=FIX";
+
+/// A FIX message as an ordered list of tag/value fields.
+#[derive(Debug, PartialEq)]
+pub struct FixMessage {
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    /// Parses the FIX message in a log line, skipping any prefix the logger wrote before the
+    /// `BeginString` field. Returns `None` for lines without a message.
+    ///
+    /// Fields may be separated by SOH, by a printed `^A`, or, in logs without either, by `|`.
+    pub fn from_log_line(line: &str) -> Option<Result<Self>> {
+        let start = line.find(BEGIN_STRING)?;
+        Some(Self::parse(&line[start..]))
+    }
+
+    fn parse(message: &str) -> Result<Self> {
+        let message = message.replace("^A", "\x01");
+        let delimiter = if message.contains('\x01') {
+            '\x01'
+        } else {
+            '|'
+        };
+        let fields = message
+            .split(delimiter)
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(|field| {
+                let (tag, value) = field
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("invalid FIX field '{}'", field))?;
+                let tag = tag
+                    .parse::<u32>()
+                    .map_err(|e| anyhow!("invalid FIX tag '{}': {}", tag, e))?;
+                Ok((tag, value.to_owned()))
+            })
+            .collect::<Result<Vec<(u32, String)>>>()?;
+
+        Ok(Self { fields })
+    }
+
+    /// Returns the value of the first field with the tag.
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field_tag, _)| *field_tag == tag)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn require(&self, tag: u32) -> Result<&str> {
+        self.get(tag)
+            .ok_or_else(|| anyhow!("FIX message is missing tag {}", tag))
+    }
+
+    pub fn get_f64(&self, tag: u32) -> Result<Option<f64>> {
+        self.get(tag)
+            .map(|value| {
+                value
+                    .parse::<f64>()
+                    .map_err(|e| anyhow!("invalid FIX tag {} value '{}': {}", tag, value, e))
+            })
+            .transpose()
+    }
+
+    pub fn msg_type(&self) -> Option<&str> {
+        self.get(TAG_MSG_TYPE)
+    }
+}
+
+/// Parses a FIX `UTCTimestamp`, e.g. `20250103-14:31:12.345`, to milliseconds since the epoch.
+pub fn parse_utc_timestamp_ms(value: &str) -> Result<i64> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%d-%H:%M:%S%.f")
+        .map(|dt| dt.and_utc().timestamp_millis())
+        .map_err(|e| anyhow!("invalid FIX timestamp '{}': {}", value, e))
+}
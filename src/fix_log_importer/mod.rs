@@ -0,0 +1,312 @@
+pub mod fix_message;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::NaiveDate;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{collections::HashMap, path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::{
+    activity::{AccountActivities, Activity, TradeActivity},
+    content_match::ContentMatch,
+    option_contract::{OptionContract, OptionRight},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+use fix_message::*;
+
+const FIX_LOG_EXTENSIONS: [&str; 3] = ["log", "fix", "txt"];
+
+/// What an ExecutionReport does to the executions of the log.
+#[derive(Debug, PartialEq)]
+enum ExecEvent {
+    Fill,
+    Correct,
+    Bust,
+    Other,
+}
+
+impl ExecEvent {
+    fn of(message: &FixMessage) -> Self {
+        // FIX 4.2 signals corrections and busts with ExecTransType; FIX 4.4 with ExecType.
+        match message.get(TAG_EXEC_TRANS_TYPE) {
+            Some("1") => return ExecEvent::Bust,
+            Some("2") => return ExecEvent::Correct,
+            Some("3") => return ExecEvent::Other,
+            _ => {}
+        }
+        match message.get(TAG_EXEC_TYPE) {
+            Some("1" | "2" | "F") => ExecEvent::Fill,
+            Some("G") => ExecEvent::Correct,
+            Some("H") => ExecEvent::Bust,
+            _ => ExecEvent::Other,
+        }
+    }
+}
+
+/// The executions of a log in the order they were reported, as corrections and busts apply.
+#[derive(Default)]
+struct ExecutionBook {
+    executions: Vec<Option<(String, TradeActivity)>>,
+    index_by_exec_id: HashMap<String, usize>,
+}
+
+impl ExecutionBook {
+    fn fill(&mut self, account_id: String, trade: TradeActivity) {
+        if self
+            .index_by_exec_id
+            .contains_key(&trade.brokerage_execution_id)
+        {
+            debug!(
+                "skipping repeated FIX execution {}",
+                trade.brokerage_execution_id
+            );
+            return;
+        }
+        self.index_by_exec_id
+            .insert(trade.brokerage_execution_id.clone(), self.executions.len());
+        self.executions.push(Some((account_id, trade)));
+    }
+
+    fn correct(&mut self, exec_ref_id: &str, account_id: String, trade: TradeActivity) {
+        match self.index_by_exec_id.get(exec_ref_id).copied() {
+            Some(index) => {
+                self.index_by_exec_id
+                    .insert(trade.brokerage_execution_id.clone(), index);
+                self.executions[index] = Some((account_id, trade));
+            }
+            None => warn!(
+                "skipping FIX correction {} of execution {}, which is not in this log",
+                trade.brokerage_execution_id, exec_ref_id
+            ),
+        }
+    }
+
+    fn bust(&mut self, exec_ref_id: &str) {
+        match self.index_by_exec_id.get(exec_ref_id) {
+            Some(index) => self.executions[*index] = None,
+            None => warn!(
+                "skipping FIX bust of execution {}, which is not in this log",
+                exec_ref_id
+            ),
+        }
+    }
+
+    fn into_accounts(self) -> Vec<AccountActivities> {
+        let mut accounts = Vec::<AccountActivities>::new();
+        for (account_id, trade) in self.executions.into_iter().flatten() {
+            match accounts.iter_mut().find(|a| a.account_id == account_id) {
+                Some(account) => account.activities.push(Activity::Trade(trade)),
+                None => accounts.push(AccountActivities {
+                    account_id,
+                    activities: vec![Activity::Trade(trade)],
+                }),
+            }
+        }
+        accounts
+    }
+}
+
+/// Imports the ExecutionReport (`35=8`) messages of a FIX 4.2 or 4.4 drop-copy log.
+///
+/// Each fill becomes a trade execution under its ExecID (17), sized and priced by LastQty (32)
+/// and LastPx (31) at its TransactTime (60). Trade corrections replace the execution they refer
+/// to through ExecRefID (19), and busts remove it. Corrections and busts of executions that
+/// aren't in the same log can't be applied and are skipped with a warning, leaving the broker
+/// statement to settle them. Resent messages with an ExecID already seen are skipped.
+///
+/// Drop copies don't identify the broker, so the brokerage id is given when constructing the
+/// importer. The account comes from the Account (1) field, or failing that from the id
+/// configured with `account_id`. Options (`167=OPT`) are recorded under their OCC symbol.
+pub struct FixLogImporter {
+    brokerage_id: String,
+    account_id: Option<String>,
+}
+
+impl FixLogImporter {
+    pub fn new(brokerage_id: &str) -> Self {
+        Self {
+            brokerage_id: brokerage_id.to_owned(),
+            account_id: None,
+        }
+    }
+
+    /// Sets the account id used for messages without an Account field.
+    pub fn account_id(mut self, account_id: &str) -> Self {
+        self.account_id = Some(account_id.to_owned());
+        self
+    }
+
+    fn messages(content: &str) -> impl Iterator<Item = Result<FixMessage>> {
+        content.lines().filter_map(FixMessage::from_log_line)
+    }
+
+    fn ticker(message: &FixMessage) -> Result<String> {
+        let symbol = message.require(TAG_SYMBOL)?;
+        if message.get(TAG_SECURITY_TYPE) != Some("OPT") {
+            return Ok(symbol.to_owned());
+        }
+
+        let expiration = match (
+            message.get(TAG_MATURITY_DATE),
+            message.get(TAG_MATURITY_MONTH_YEAR),
+            message.get(TAG_MATURITY_DAY),
+        ) {
+            (Some(date), _, _) => date.to_owned(),
+            (None, Some(month_year), Some(day)) => format!("{}{:0>2}", month_year, day),
+            (None, Some(month_year), None) => month_year.to_owned(),
+            _ => return Err(anyhow!("FIX option {} has no maturity", symbol)),
+        };
+        let contract = OptionContract {
+            underlying: symbol.to_owned(),
+            expiration: NaiveDate::parse_from_str(&expiration, "%Y%m%d")
+                .map_err(|e| anyhow!("invalid FIX option maturity '{}': {}", expiration, e))?,
+            strike: message
+                .get_f64(TAG_STRIKE_PRICE)?
+                .ok_or_else(|| anyhow!("FIX option {} has no strike price", symbol))?,
+            right: match message.require(TAG_PUT_OR_CALL)? {
+                "0" => OptionRight::Put,
+                "1" => OptionRight::Call,
+                other => return Err(anyhow!("invalid FIX PutOrCall '{}'", other)),
+            },
+        };
+        Ok(contract.occ_symbol())
+    }
+
+    fn commission(message: &FixMessage, quantity: f64, price: f64) -> Result<f64> {
+        let Some(commission) = message.get_f64(TAG_COMMISSION)? else {
+            return Ok(0.0);
+        };
+        let commission = match message.get(TAG_COMM_TYPE) {
+            Some("1") => commission * quantity,
+            Some("2") => commission / 100.0 * quantity * price,
+            _ => commission,
+        };
+        Ok(commission.abs())
+    }
+
+    fn trade(message: &FixMessage) -> Result<TradeActivity> {
+        let side = match message.require(TAG_SIDE)? {
+            "1" | "3" => TradeSide::Buy,
+            "2" | "4" | "5" | "6" => TradeSide::Sell,
+            other => return Err(anyhow!("unsupported FIX side '{}'", other)),
+        };
+        let quantity = message
+            .get_f64(TAG_LAST_QTY)?
+            .ok_or_else(|| anyhow!("FIX execution report has no LastQty"))?;
+        let price = message
+            .get_f64(TAG_LAST_PX)?
+            .ok_or_else(|| anyhow!("FIX execution report has no LastPx"))?;
+        let transact_time = message
+            .get(TAG_TRANSACT_TIME)
+            .or_else(|| message.get(TAG_SENDING_TIME))
+            .ok_or_else(|| anyhow!("FIX execution report has no TransactTime"))?;
+
+        Ok(TradeActivity {
+            brokerage_execution_id: message.require(TAG_EXEC_ID)?.to_owned(),
+            ticker: Self::ticker(message)?,
+            listing_exchange: None,
+            side,
+            quantity,
+            price,
+            commission: Self::commission(message, quantity, price)?,
+            execution_timestamp_ms: parse_utc_timestamp_ms(transact_time)?,
+        })
+    }
+
+    fn message_account_id(&self, message: &FixMessage) -> Result<String> {
+        message
+            .get(TAG_ACCOUNT)
+            .map(str::to_owned)
+            .or_else(|| self.account_id.clone())
+            .ok_or_else(|| anyhow!("FIX execution report has no Account and no account is set"))
+    }
+
+    /// Parses the log's execution reports into account activities, one entry per account.
+    pub fn parse(&self, content: &str) -> Result<Vec<AccountActivities>> {
+        let mut book = ExecutionBook::default();
+
+        for message in Self::messages(content) {
+            let message = message?;
+            if message.msg_type() != Some(MSG_TYPE_EXECUTION_REPORT) {
+                continue;
+            }
+
+            match ExecEvent::of(&message) {
+                ExecEvent::Fill => {
+                    book.fill(self.message_account_id(&message)?, Self::trade(&message)?)
+                }
+                ExecEvent::Correct => book.correct(
+                    message.require(TAG_EXEC_REF_ID)?,
+                    self.message_account_id(&message)?,
+                    Self::trade(&message)?,
+                ),
+                ExecEvent::Bust => book.bust(message.require(TAG_EXEC_REF_ID)?),
+                ExecEvent::Other => {}
+            }
+        }
+
+        Ok(book.into_accounts())
+    }
+}
+
+#[async_trait]
+impl StatementImporter for FixLogImporter {
+    fn importer_name(&self) -> &'static str {
+        "fix-log"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path
+            .extension()
+            .is_some_and(|ext| FIX_LOG_EXTENSIONS.iter().any(|e| ext == *e))
+        {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        let has_execution_report = Self::messages(content).any(|message| {
+            message.is_ok_and(|message| message.msg_type() == Some(MSG_TYPE_EXECUTION_REPORT))
+        });
+        if has_execution_report {
+            ContentMatch::definite("FIX execution report messages")
+        } else {
+            ContentMatch::no("no FIX execution report messages")
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing FIX log with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        for account_activities in self.parse(content)? {
+            writers::write_account_activities(
+                db,
+                session.clone(),
+                &self.brokerage_id,
+                &account_activities,
+                write_batch_size,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::{Result, anyhow};
+use ibkr_flex_statement::{Parser, trade::Trade};
+use quick_xml::{Reader, events::Event};
+use tokio::io::AsyncBufRead;
+
+/// Trades of one Flex statement, read by `FlexTradeReader`.
+#[derive(Debug, PartialEq)]
+pub struct FlexTradeBatch {
+    pub account_id: String,
+    pub trades: Vec<Trade>,
+}
+
+/// Reads the trades of a Flex query response incrementally, in batches of a bounded size.
+///
+/// Only the elements of the current batch are held in memory, so statements of any size can be
+/// read. Each batch is parsed with the same parser as whole statements, along with its
+/// statement's `AccountInformation` element, which Flex writes before the trades.
+pub struct FlexTradeReader<R> {
+    xml: Reader<R>,
+    parser: Parser,
+    batch_size: usize,
+    account_information: Option<String>,
+    pending_trades: Vec<String>,
+}
+
+impl<R: AsyncBufRead + Unpin> FlexTradeReader<R> {
+    pub fn new(reader: R, batch_size: usize) -> Result<Self> {
+        if batch_size == 0 {
+            return Err(anyhow!("batch size must be at least 1"));
+        }
+        Ok(Self {
+            xml: Reader::from_reader(reader),
+            parser: Parser::new()?,
+            batch_size,
+            account_information: None,
+            pending_trades: Vec::new(),
+        })
+    }
+
+    /// Parses the pending trades.
+    fn take_batch(&mut self) -> Result<FlexTradeBatch> {
+        let account_information = self
+            .account_information
+            .as_deref()
+            .ok_or_else(|| anyhow!("Flex statement has no AccountInformation before its trades"))?;
+        let statement = format!(
+            "<FlexStatement>{}{}</FlexStatement>",
+            account_information,
+            self.pending_trades.concat()
+        );
+        self.pending_trades.clear();
+
+        let mut statements = self.parser.parse_flex_query_response(&statement)?;
+        let statement = statements.remove(0);
+        Ok(FlexTradeBatch {
+            account_id: statement.account_info.account_id,
+            trades: statement.trades,
+        })
+    }
+
+    /// Returns the next batch of trades, or `None` at the end of the response.
+    ///
+    /// A batch never spans two statements, so the last batch of each statement may be smaller
+    /// than the batch size.
+    pub async fn next_batch(&mut self) -> Result<Option<FlexTradeBatch>> {
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match self.xml.read_event_into_async(&mut buf).await? {
+                Event::Start(element) | Event::Empty(element) => match element.name().as_ref() {
+                    "FlexStatement" => self.account_information = None,
+                    "AccountInformation" => {
+                        self.account_information = Some(format!("<{}/>", &*element));
+                    }
+                    "Trade" => {
+                        self.pending_trades.push(format!("<{}/>", &*element));
+                        if self.pending_trades.len() == self.batch_size {
+                            return self.take_batch().map(Some);
+                        }
+                    }
+                    _ => {}
+                },
+                Event::End(element)
+                    if element.name().as_ref() == "FlexStatement"
+                        && !self.pending_trades.is_empty() =>
+                {
+                    return self.take_batch().map(Some);
+                }
+                Event::Eof if self.pending_trades.is_empty() => return Ok(None),
+                Event::Eof => return self.take_batch().map(Some),
+                _ => {}
+            }
+        }
+    }
+}
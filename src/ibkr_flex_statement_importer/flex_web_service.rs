@@ -0,0 +1,233 @@
+use anyhow::{Result, anyhow};
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use roxmltree::Document;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::{importer_registry::ImporterRegistry, text_encoding};
+
+pub const DEFAULT_FLEX_WEB_SERVICE_BASE_URL: &str =
+    "https://ndcdyn.interactivebrokers.com/AccountManagement/FlexWebService";
+
+const FLEX_WEB_SERVICE_VERSION: &str = "3";
+
+/// Flex Web Service error codes that mean the request should be retried later.
+///
+/// - 1009: the server is under heavy load.
+/// - 1018: too many requests have been made from this token.
+/// - 1019: statement generation is in progress.
+/// - 1021: the statement could not be retrieved at this time.
+const RETRYABLE_ERROR_CODES: [&str; 4] = ["1009", "1018", "1019", "1021"];
+
+/// Fetches Flex query statements from the IBKR Flex Web Service.
+///
+/// The fetcher calls `SendRequest` with the configured token and query id, then polls
+/// `GetStatement` with the returned reference code until the report has been generated.
+pub struct FlexWebServiceFetcher {
+    base_url: String,
+    token: String,
+    query_id: String,
+    poll_interval: Duration,
+    max_poll_attempts: u32,
+    download_dir: PathBuf,
+    client: reqwest::Client,
+}
+
+impl FlexWebServiceFetcher {
+    pub fn new(token: &str, query_id: &str) -> Self {
+        Self {
+            base_url: DEFAULT_FLEX_WEB_SERVICE_BASE_URL.to_owned(),
+            token: token.to_owned(),
+            query_id: query_id.to_owned(),
+            poll_interval: Duration::from_secs(5),
+            max_poll_attempts: 24,
+            download_dir: PathBuf::from("."),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Overrides the Flex Web Service base URL, e.g. to point at a local stub server.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn max_poll_attempts(mut self, max_poll_attempts: u32) -> Self {
+        self.max_poll_attempts = max_poll_attempts;
+        self
+    }
+
+    /// Sets the directory where a copy of each fetched statement is saved.
+    pub fn download_dir(mut self, download_dir: &Path) -> Self {
+        self.download_dir = download_dir.to_owned();
+        self
+    }
+
+    async fn get(&self, endpoint: &str, code: &str) -> Result<String> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let response = self
+            .client
+            .get(&url)
+            .header(
+                reqwest::header::USER_AGENT,
+                concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")),
+            )
+            .query(&[
+                ("t", self.token.as_str()),
+                ("q", code),
+                ("v", FLEX_WEB_SERVICE_VERSION),
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response.text().await?)
+    }
+
+    /// Requests generation of the Flex query and returns the reference code for the report.
+    pub async fn send_request(&self) -> Result<String> {
+        let body = self.get("SendRequest", &self.query_id).await?;
+        let response = FlexStatementResponse::parse(&body)?;
+
+        match (response.status.as_str(), &response.reference_code) {
+            ("Success", Some(reference_code)) => Ok(reference_code.clone()),
+            _ => Err(anyhow!(
+                "Flex Web Service SendRequest failed: {}",
+                response.describe_error()
+            )),
+        }
+    }
+
+    /// Retrieves the statement for the given reference code.
+    ///
+    /// Returns `None` if the statement is not ready yet and the request should be retried, and
+    /// an error if the response is neither a statement nor a Flex Web Service status.
+    pub async fn get_statement(&self, reference_code: &str) -> Result<Option<String>> {
+        let body = self.get("GetStatement", reference_code).await?;
+        let root = text_encoding::skip_xml_prolog(&body);
+        if root.starts_with("<FlexQueryResponse") {
+            return Ok(Some(body));
+        }
+        if !root.starts_with("<FlexStatementResponse") {
+            return Err(anyhow!(
+                "Flex Web Service GetStatement returned neither a FlexQueryResponse nor a FlexStatementResponse for {}",
+                reference_code
+            ));
+        }
+
+        let response = FlexStatementResponse::parse(&body)?;
+        if response
+            .error_code
+            .as_deref()
+            .is_some_and(|code| RETRYABLE_ERROR_CODES.contains(&code))
+        {
+            debug!(
+                "Flex statement {} not ready: {}",
+                reference_code,
+                response.describe_error()
+            );
+            Ok(None)
+        } else {
+            Err(anyhow!(
+                "Flex Web Service GetStatement failed: {}",
+                response.describe_error()
+            ))
+        }
+    }
+
+    /// Requests the Flex query and polls until the statement XML is available.
+    ///
+    /// Returns the report reference code along with the statement XML.
+    pub async fn fetch_statement(&self) -> Result<(String, String)> {
+        let reference_code = self.send_request().await?;
+        info!(
+            "Flex query {} accepted with reference code {}",
+            self.query_id, reference_code
+        );
+
+        for _ in 0..self.max_poll_attempts {
+            if let Some(statement) = self.get_statement(&reference_code).await? {
+                return Ok((reference_code, statement));
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+
+        Err(anyhow!(
+            "Flex statement {} was not ready after {} attempts",
+            reference_code,
+            self.max_poll_attempts
+        ))
+    }
+
+    /// Fetches the statement, saves a copy to the download directory and imports it.
+    ///
+    /// Returns the path of the saved statement copy.
+    pub async fn fetch_and_import(
+        &self,
+        registry: &ImporterRegistry,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+    ) -> Result<PathBuf> {
+        let (reference_code, statement) = self.fetch_statement().await?;
+
+        fs::create_dir_all(&self.download_dir)?;
+        let path = self
+            .download_dir
+            .join(format!("{}_{}.xml", self.query_id, reference_code));
+        fs::write(&path, &statement)?;
+        info!("saved Flex statement to {:?}", path);
+
+        registry
+            .import_statement_content(&statement, db, session, ObjectId::new())
+            .await?;
+
+        Ok(path)
+    }
+}
+
+struct FlexStatementResponse {
+    status: String,
+    reference_code: Option<String>,
+    error_code: Option<String>,
+    error_message: Option<String>,
+}
+
+impl FlexStatementResponse {
+    fn parse(body: &str) -> Result<Self> {
+        let doc = Document::parse(body)?;
+        let child_text = |name: &str| {
+            doc.descendants()
+                .find(|n| n.tag_name().name() == name)
+                .and_then(|n| n.text())
+                .map(|t| t.trim().to_owned())
+        };
+
+        Ok(Self {
+            status: child_text("Status")
+                .ok_or_else(|| anyhow!("Flex Web Service response has no Status"))?,
+            reference_code: child_text("ReferenceCode"),
+            error_code: child_text("ErrorCode"),
+            error_message: child_text("ErrorMessage"),
+        })
+    }
+
+    fn describe_error(&self) -> String {
+        format!(
+            "status {}, error code {}: {}",
+            self.status,
+            self.error_code.as_deref().unwrap_or("none"),
+            self.error_message.as_deref().unwrap_or("no message")
+        )
+    }
+}
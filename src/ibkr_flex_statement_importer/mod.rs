@@ -1,20 +1,22 @@
+pub mod flex_stream;
+pub mod flex_web_service;
+
 use anyhow::Result;
 use async_trait::async_trait;
-use ibkr_flex_statement::{Parser, Statement};
+use brokerage_db::trade_execution::TradeExecution;
+use chrono::NaiveDate;
+use ibkr_flex_statement::{Parser, trade::Trade};
 use mongodb::{ClientSession, Database, bson::oid::ObjectId};
-use std::{
-    collections::{HashMap, HashSet},
-    path::Path,
-    sync::Arc,
-};
-use tokio::sync::Mutex;
+use std::{path::Path, sync::Arc};
+use tokio::{io::AsyncBufRead, sync::Mutex};
 use tracing::info;
 
 use crate::{
-    path_match::PathMatch,
+    batch_writer::BatchWriter, content_match::ContentMatch, path_match::PathMatch,
     statement_importer::StatementImporter,
-    writers::{self, TradeWriter},
+    streaming_statement_importer::StreamingStatementImporter, text_encoding,
 };
+use flex_stream::FlexTradeReader;
 
 pub const IBKR_BROKERAGE_ID: &str = "ibkr";
 
@@ -31,76 +33,43 @@ impl IbkrFlexStatementImporter {
         Self {}
     }
 
-    async fn import_securities(
+    /// Adds the trades of the brokerage account to the writer, along with their securities.
+    ///
+    /// Trades whose execution id the account already has are skipped, so a statement that
+    /// overlaps an earlier import adds only its new trades.
+    async fn import_trades(
         &self,
-        statement: &Statement,
+        writer: &mut BatchWriter,
+        brokerage_account_id: ObjectId,
+        trades: &[Trade],
         db: &Database,
         session: Option<Arc<Mutex<ClientSession>>>,
-    ) -> Result<HashMap<u32, ObjectId>> {
-        let mut conid_map = HashMap::<u32, ObjectId>::new();
-
-        let securities = statement
-            .trades
+    ) -> Result<()> {
+        let tickers = trades
             .iter()
-            .map(|trade| {
-                (
-                    trade.ticker.clone(),
-                    trade.listing_exchange.clone(),
-                    trade.conid,
-                )
-            })
-            .collect::<HashSet<(String, String, u32)>>();
-
-        for (ticker, listing_exchange, conid) in securities {
-            let security = writers::maybe_add_security(
-                db,
-                session.clone(),
-                &ticker,
-                &listing_exchange,
-                Some(conid),
-            )
+            .map(|trade| trade.ticker.as_str())
+            .collect::<Vec<&str>>();
+        writer
+            .preload_securities(db, session.clone(), &tickers)
             .await?;
-
-            conid_map.insert(conid, security.id());
-
-            info!(
-                "Parsed IBKR Flex statement for security: {}",
-                security.ticker()
-            );
-        }
-
-        Ok(conid_map)
-    }
-
-    async fn import_flex_statement(
-        &self,
-        statement: &Statement,
-        db: &Database,
-        session: Option<Arc<Mutex<ClientSession>>>,
-        _source_id: ObjectId,
-    ) -> Result<()> {
-        let brokerage_account = writers::maybe_add_brokerage_account(
-            db,
-            session.clone(),
-            IBKR_BROKERAGE_ID,
-            &statement.account_info.account_id,
-        )
-        .await?;
-
-        info!(
-            "Importing IBKR Flex statement for brokerage account: {}",
-            brokerage_account.account_id(),
-        );
-
-        let conid_security_map = self
-            .import_securities(statement, db, session.clone())
+        let execution_ids = trades
+            .iter()
+            .map(|trade| trade.execution_id.as_str())
+            .collect::<Vec<&str>>();
+        writer
+            .preload_trade_executions(db, session.clone(), brokerage_account_id, &execution_ids)
             .await?;
 
-        // Import the trades.
-        for trade in &statement.trades {
-            let security_id = conid_security_map
-                .get(&trade.conid)
-                .ok_or_else(|| anyhow::anyhow!("Security not found for conid {}", trade.conid))?;
+        for trade in trades {
+            let security_id = writer
+                .security(
+                    db,
+                    session.clone(),
+                    &trade.ticker,
+                    &trade.listing_exchange,
+                    Some(trade.conid),
+                )
+                .await?;
 
             let trade_side = match trade.side {
                 ibkr_flex_statement::trade::TradeSide::Buy => {
@@ -111,16 +80,18 @@ impl IbkrFlexStatementImporter {
                 }
             };
 
-            TradeWriter::new()
-                .brokerage_account_id(brokerage_account.id())
+            let trade_execution = TradeExecution::builder()
+                .brokerage_account_id(brokerage_account_id)
                 .brokerage_execution_id(&trade.execution_id)
                 .commission(trade.commission)
                 .execution_timestamp_ms(trade.execution_timestamp_ms)
                 .quantity(trade.quantity)
                 .price(trade.price)
-                .security_id(*security_id)
+                .security_id(security_id)
                 .side(trade_side)
-                .insert(db, session.clone())
+                .build()?;
+            writer
+                .add_trade(trade_execution, db, session.clone())
                 .await?;
         }
 
@@ -142,20 +113,32 @@ impl StatementImporter for IbkrFlexStatementImporter {
         }
     }
 
-    async fn content_matches(&self, content: &str) -> PathMatch {
-        if content.starts_with("<FlexQueryResponse") {
-            PathMatch::Match
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if text_encoding::skip_xml_prolog(content).starts_with("<FlexQueryResponse") {
+            ContentMatch::definite("FlexQueryResponse root element")
         } else {
-            PathMatch::NoMatch
+            ContentMatch::no("no FlexQueryResponse root element")
         }
     }
 
+    fn statement_date(&self, content: &str) -> Option<NaiveDate> {
+        // The period end of the first statement, e.g. `toDate="2025-04-25"`, or
+        // `toDate="20250425"` for queries configured without date separators.
+        let start = content.find("<FlexStatement ")?;
+        let tag = &content[start..start + content[start..].find('>')?];
+        let value = tag.split(" toDate=\"").nth(1)?.split('"').next()?;
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(value, "%Y%m%d"))
+            .ok()
+    }
+
     async fn import(
         &self,
         content: &str,
         db: &Database,
         session: Option<Arc<Mutex<ClientSession>>>,
         source_id: ObjectId,
+        write_batch_size: usize,
     ) -> Result<()> {
         tracing::debug!(
             "Importing IBKR Flex with importer {}, source_id {}, string content {}",
@@ -169,11 +152,86 @@ impl StatementImporter for IbkrFlexStatementImporter {
         let flex_statements = parser.parse_flex_query_response(content)?;
 
         // Add each flex statement content to the database.
-        for flex_statement in flex_statements {
-            self.import_flex_statement(&flex_statement, db, session.clone(), source_id)
+        let mut writer = BatchWriter::new().batch_size(write_batch_size)?;
+        let account_ids = flex_statements
+            .iter()
+            .map(|statement| statement.account_info.account_id.as_str())
+            .collect::<Vec<&str>>();
+        writer
+            .preload_accounts(db, session.clone(), IBKR_BROKERAGE_ID, &account_ids)
+            .await?;
+        for flex_statement in &flex_statements {
+            let account_id = &flex_statement.account_info.account_id;
+            info!(
+                "Importing IBKR Flex statement for brokerage account: {}",
+                account_id
+            );
+            let brokerage_account_id = writer
+                .account(db, session.clone(), IBKR_BROKERAGE_ID, account_id)
                 .await?;
+            self.import_trades(
+                &mut writer,
+                brokerage_account_id,
+                &flex_statement.trades,
+                db,
+                session.clone(),
+            )
+            .await?;
         }
 
-        Ok(())
+        writer.flush(db, session).await
+    }
+
+    fn streaming(&self) -> Option<&dyn StreamingStatementImporter> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl StreamingStatementImporter for IbkrFlexStatementImporter {
+    async fn import_stream(
+        &self,
+        reader: &mut (dyn AsyncBufRead + Send + Unpin),
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        batch_size: usize,
+    ) -> Result<()> {
+        tracing::debug!(
+            "Streaming IBKR Flex with importer {}, source_id {}, batch size {}",
+            self.importer_name(),
+            source_id,
+            batch_size
+        );
+
+        let mut trade_reader = FlexTradeReader::new(reader, batch_size)?;
+        let mut writer = BatchWriter::new().batch_size(batch_size)?;
+        let mut brokerage_account: Option<(String, ObjectId)> = None;
+        while let Some(batch) = trade_reader.next_batch().await? {
+            let brokerage_account_id = match &brokerage_account {
+                Some((account_id, id)) if *account_id == batch.account_id => *id,
+                _ => {
+                    info!(
+                        "Importing IBKR Flex statement for brokerage account: {}",
+                        batch.account_id
+                    );
+                    let id = writer
+                        .account(db, session.clone(), IBKR_BROKERAGE_ID, &batch.account_id)
+                        .await?;
+                    brokerage_account = Some((batch.account_id.clone(), id));
+                    id
+                }
+            };
+            self.import_trades(
+                &mut writer,
+                brokerage_account_id,
+                &batch.trades,
+                db,
+                session.clone(),
+            )
+            .await?;
+        }
+
+        writer.flush(db, session).await
     }
 }
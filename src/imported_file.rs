@@ -0,0 +1,82 @@
+use anyhow::Result;
+use futures::stream::TryStreamExt;
+use mongodb::{
+    ClientSession, Database,
+    bson::{doc, oid::ObjectId},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::db_util;
+
+/// The import ledger's record of a statement file imported by the registry.
+///
+/// Entries of archives and email attachments are recorded by their qualified paths, e.g.
+/// `2024.zip!/jan.xml`. The record's id is the source id the import was run with.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ImportedFile {
+    _id: ObjectId,
+    path: String,
+    importer_name: String,
+}
+
+impl ImportedFile {
+    pub const COLLECTION_NAME: &'static str = "imported_files";
+
+    pub fn new(source_id: ObjectId, path: &str, importer_name: &str) -> Self {
+        Self {
+            _id: source_id,
+            path: path.to_owned(),
+            importer_name: importer_name.to_owned(),
+        }
+    }
+
+    /// The source id of the import.
+    pub fn id(&self) -> ObjectId {
+        self._id
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn importer_name(&self) -> &str {
+        &self.importer_name
+    }
+
+    pub async fn insert(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+    ) -> Result<()> {
+        db_util::insert(self, db, Self::COLLECTION_NAME, session).await
+    }
+
+    /// Returns the imports of the path, which has more than one if it was imported again.
+    pub async fn find_by_path(db: &Database, path: &str) -> Result<Vec<Self>> {
+        let result = db
+            .collection::<Self>(Self::COLLECTION_NAME)
+            .find(doc! { "path": path })
+            .await?;
+
+        Ok(result.try_collect().await?)
+    }
+
+    /// Returns whether the path was imported, seeing the imports of the session's transaction.
+    pub async fn is_imported(
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        path: &str,
+    ) -> Result<bool> {
+        let collection = db.collection::<Self>(Self::COLLECTION_NAME);
+        let find = collection.find_one(doc! { "path": path });
+        let result = if let Some(session) = session {
+            find.session(&mut *session.lock().await).await?
+        } else {
+            find.await?
+        };
+
+        Ok(result.is_some())
+    }
+}
@@ -1,14 +1,65 @@
-use std::{fs, path::PathBuf, sync::Arc};
+use std::{
+    borrow::Cow,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
+use crate::archive;
+use crate::batch_writer;
+use crate::configurable_csv_importer::ConfigurableCsvImporter;
+use crate::content_match::ContentMatch;
+use crate::file_selection::FileSelection;
+use crate::imported_file::ImportedFile;
+use crate::parse_util;
 use crate::path_match::PathMatch;
 use crate::statement_importer::StatementImporter;
-use anyhow::Result;
+use crate::streaming_statement_importer::StreamingStatementImporter;
+use crate::text_encoding;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, NaiveDate, Utc};
 use mongodb::{ClientSession, Database, bson::oid::ObjectId};
-use tokio::sync::Mutex;
-use tracing::info;
+use tokio::{io::AsyncBufRead, sync::Mutex};
+use tracing::{debug, info, warn};
+
+pub use crate::batch_writer::DEFAULT_WRITE_BATCH_SIZE;
+
+/// Files at least this large are streamed to importers that can import them incrementally.
+pub const DEFAULT_STREAMING_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// The number of records streaming importers parse and write at a time.
+pub const DEFAULT_STREAM_BATCH_SIZE: usize = 1000;
+
+/// How much of a streamed file importers are matched against.
+const MATCH_PREFIX_LEN: u64 = 64 * 1024;
+
+/// Reads the start of a file, without any partial UTF-8 character at its end.
+fn read_prefix(path: &Path) -> Result<Vec<u8>> {
+    let mut prefix = Vec::new();
+    fs::File::open(path)?
+        .take(MATCH_PREFIX_LEN)
+        .read_to_end(&mut prefix)?;
+    let len = text_encoding::trim_partial_utf8(&prefix).len();
+    prefix.truncate(len);
+    Ok(prefix)
+}
+
+/// The outcome of importing a file or archive entry.
+#[derive(Debug, PartialEq)]
+enum EntryImport {
+    Imported,
+    /// No importer accepts the path.
+    NoViableImporter,
+    /// Importers accept the path, but none accepts the content.
+    NoMatchingContent,
+}
 
 pub struct ImporterRegistry {
     importers: Vec<Box<dyn StatementImporter>>,
+    streaming_threshold: u64,
+    stream_batch_size: usize,
+    write_batch_size: usize,
 }
 
 impl Default for ImporterRegistry {
@@ -21,6 +72,9 @@ impl ImporterRegistry {
     pub fn new() -> Self {
         Self {
             importers: Vec::new(),
+            streaming_threshold: DEFAULT_STREAMING_THRESHOLD,
+            stream_batch_size: DEFAULT_STREAM_BATCH_SIZE,
+            write_batch_size: DEFAULT_WRITE_BATCH_SIZE,
         }
     }
 
@@ -28,6 +82,31 @@ impl ImporterRegistry {
         self.importers.push(importer);
     }
 
+    /// Registers a `ConfigurableCsvImporter` for each TOML or YAML mapping file.
+    ///
+    /// Nothing is registered if a mapping fails to load or its name is already taken.
+    pub fn register_csv_mapping_files(&mut self, paths: &[PathBuf]) -> Result<()> {
+        let mut importers = Vec::<ConfigurableCsvImporter>::new();
+        for path in paths {
+            let importer = ConfigurableCsvImporter::from_file(path)?;
+            let name = importer.importer_name();
+            if self.importer(name).is_some() || importers.iter().any(|i| i.importer_name() == name)
+            {
+                return Err(anyhow!(
+                    "CSV mapping {:?}: importer name '{}' is already registered",
+                    path,
+                    name
+                ));
+            }
+            importers.push(importer);
+        }
+        for importer in importers {
+            debug!("registering CSV mapping {}", importer.importer_name());
+            self.register_importer(Box::new(importer));
+        }
+        Ok(())
+    }
+
     pub fn importer(&self, name: &str) -> Option<&dyn StatementImporter> {
         self.importers
             .iter()
@@ -35,6 +114,78 @@ impl ImporterRegistry {
             .map(|v| &**v)
     }
 
+    /// Returns the importer named to be forced, failing if none is registered under the name.
+    fn forced_importer(&self, forced: Option<&str>) -> Result<Option<&dyn StatementImporter>> {
+        forced
+            .map(|name| {
+                self.importer(name)
+                    .ok_or_else(|| anyhow!("No importer named '{}' is registered", name))
+            })
+            .transpose()
+    }
+
+    /// Sets the size in bytes from which statement files are streamed to importers that can
+    /// import them incrementally, instead of being read into memory.
+    ///
+    /// Importers are matched against the first 64 KiB of streamed files.
+    pub fn set_streaming_threshold(&mut self, bytes: u64) {
+        self.streaming_threshold = bytes;
+    }
+
+    /// Sets the number of records streaming importers parse and write at a time.
+    pub fn set_stream_batch_size(&mut self, batch_size: usize) -> Result<()> {
+        if batch_size == 0 {
+            return Err(anyhow!("Stream batch size must be at least 1"));
+        }
+        self.stream_batch_size = batch_size;
+        Ok(())
+    }
+
+    /// Sets the number of trades and cash transactions importers write to the database at a
+    /// time.
+    pub fn set_write_batch_size(&mut self, batch_size: usize) -> Result<()> {
+        self.write_batch_size = batch_writer::validate_batch_size(batch_size)?;
+        Ok(())
+    }
+
+    /// Picks the importer with the highest confidence among those that match `source`,
+    /// failing if several share it.
+    fn most_confident<'a, T>(
+        source: &str,
+        matches: Vec<(&'a dyn StatementImporter, ContentMatch, T)>,
+    ) -> Result<Option<(&'a dyn StatementImporter, T)>> {
+        let Some(confidence) = matches.iter().map(|(_, m, _)| m.confidence()).max() else {
+            return Ok(None);
+        };
+        let mut best = matches
+            .into_iter()
+            .filter(|(_, m, _)| m.confidence() == confidence)
+            .collect::<Vec<_>>();
+        if best.len() > 1 {
+            let candidates = best
+                .iter()
+                .map(|(importer, m, _)| format!("{} ({})", importer.importer_name(), m.reason()))
+                .collect::<Vec<String>>()
+                .join(", ");
+            return Err(anyhow!(
+                "Ambiguous importers for {} with {:?} confidence: {}",
+                source,
+                confidence,
+                candidates
+            ));
+        }
+
+        let (importer, content_match, value) = best.remove(0);
+        debug!(
+            "importer {} matches {} with {:?} confidence: {}",
+            importer.importer_name(),
+            source,
+            confidence,
+            content_match.reason()
+        );
+        Ok(Some((importer, value)))
+    }
+
     async fn import_with_importers(
         &self,
         importers: Vec<&dyn StatementImporter>,
@@ -43,16 +194,21 @@ impl ImporterRegistry {
         session: Option<Arc<Mutex<ClientSession>>>,
         source_id: ObjectId,
     ) -> Result<()> {
+        let mut matches = Vec::new();
         for importer in importers {
-            if importer.content_matches(content).await == PathMatch::Match {
-                // Run the importer.
-                let result = importer
-                    .import(content, db, session.clone(), source_id)
-                    .await;
-                return result;
+            let content_match = importer.content_matches(content).await;
+            if content_match.is_match() {
+                matches.push((importer, content_match, ()));
+            }
+        }
+        match Self::most_confident("statement content", matches)? {
+            Some((importer, ())) => {
+                importer
+                    .import(content, db, session, source_id, self.write_batch_size)
+                    .await
             }
+            None => Err(anyhow!("No matching importer found")),
         }
-        Err(anyhow::anyhow!("No matching importer found"))
     }
 
     pub async fn import_statement_content(
@@ -67,31 +223,490 @@ impl ImporterRegistry {
             .iter()
             .map(|i| i.as_ref())
             .collect::<Vec<&dyn StatementImporter>>();
+        let content = content.trim_start_matches('\u{feff}');
         self.import_with_importers(importers, content, db, session, source_id)
             .await
     }
 
+    /// Returns the importer most confident that it can import the bytes, with the content it
+    /// decoded them to, or the forced importer if there is one.
+    ///
+    /// Returns `Ok(None)` if no importer decodes the bytes and matches the content.
+    async fn matching_importer<'a>(
+        &'a self,
+        importers: Vec<&'a dyn StatementImporter>,
+        forced: Option<&'a dyn StatementImporter>,
+        path: &Path,
+        bytes: &'a [u8],
+    ) -> Result<Option<(&'a dyn StatementImporter, Cow<'a, str>)>> {
+        if let Some(importer) = forced {
+            let content = importer.decode_content(bytes).map_err(|e| {
+                anyhow!(
+                    "forced importer {} cannot decode {:?}: {}",
+                    importer.importer_name(),
+                    path,
+                    e
+                )
+            })?;
+            return Ok(Some((importer, content)));
+        }
+
+        let mut matches = Vec::new();
+        for importer in importers {
+            let content = match importer.decode_content(bytes) {
+                Ok(content) => content,
+                Err(e) => {
+                    debug!(
+                        "importer {} cannot decode the content: {}",
+                        importer.importer_name(),
+                        e
+                    );
+                    continue;
+                }
+            };
+            let content_match = importer.content_matches(&content).await;
+            if content_match.is_match() {
+                matches.push((importer, content_match, content));
+            }
+        }
+        Self::most_confident(&format!("{:?}", path), matches)
+    }
+
+    /// Imports the bytes with the importer most confident that it can import them, recording
+    /// the import in the import ledger under `path`.
+    ///
+    /// Returns `Ok(false)` if no importer matches.
+    async fn import_bytes_with_importers(
+        &self,
+        importers: Vec<&dyn StatementImporter>,
+        forced: Option<&dyn StatementImporter>,
+        path: &Path,
+        bytes: &[u8],
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+    ) -> Result<bool> {
+        let Some((importer, content)) = self
+            .matching_importer(importers, forced, path, bytes)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        // Construct a new statement source ID for each file.
+        let source_id = ObjectId::new();
+        importer
+            .import(
+                &content,
+                db,
+                session.clone(),
+                source_id,
+                self.write_batch_size,
+            )
+            .await?;
+        ImportedFile::new(source_id, &path.to_string_lossy(), importer.importer_name())
+            .insert(db, session)
+            .await?;
+        Ok(true)
+    }
+
+    /// Returns the importers that accept the path, or just the forced importer if there is
+    /// one.
+    async fn viable_importers<'a>(
+        &'a self,
+        path: &Path,
+        forced: Option<&'a dyn StatementImporter>,
+    ) -> Vec<&'a dyn StatementImporter> {
+        if let Some(importer) = forced {
+            return vec![importer];
+        }
+
+        let mut viable_importers = Vec::<&dyn StatementImporter>::new();
+        for importer in self.importers.iter() {
+            if importer.path_may_match(path).await == PathMatch::Match {
+                viable_importers.push(importer.as_ref());
+            }
+        }
+        viable_importers
+    }
+
+    /// Imports the bytes of a file or archive entry with the importers whose path and content
+    /// match.
+    async fn import_entry(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        path: &Path,
+        bytes: &[u8],
+        forced: Option<&dyn StatementImporter>,
+    ) -> Result<EntryImport> {
+        let viable_importers = self.viable_importers(path, forced).await;
+        if viable_importers.is_empty() {
+            return Ok(EntryImport::NoViableImporter);
+        }
+
+        let imported = self
+            .import_bytes_with_importers(viable_importers, forced, path, bytes, db, session)
+            .await?;
+        Ok(if imported {
+            EntryImport::Imported
+        } else {
+            EntryImport::NoMatchingContent
+        })
+    }
+
+    /// Imports statement content that doesn't come from a file of its own, such as an email
+    /// attachment, as if it were a file at `path`. Archives are expanded as they are for files.
+    ///
+    /// Returns `Ok(false)` if no importer matches the path and content, or for archives, any
+    /// of their entries.
+    pub async fn import_statement_bytes(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        path: &Path,
+        bytes: &[u8],
+    ) -> Result<bool> {
+        if !archive::is_archive(path) {
+            let result = self.import_entry(db, session, path, bytes, None).await?;
+            if result != EntryImport::Imported {
+                debug!("No importer matches {:?}", path);
+            }
+            return Ok(result == EntryImport::Imported);
+        }
+
+        let mut imported = false;
+        for entry in archive::read_entries(path, bytes)? {
+            let result = self
+                .import_entry(db, session.clone(), &entry.path, &entry.bytes, None)
+                .await?;
+            imported |= result == EntryImport::Imported;
+        }
+        Ok(imported)
+    }
+
+    /// Imports a statement file, failing if an importer accepts the path but none accepts the
+    /// content.
+    async fn import_file_entry(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        path: &Path,
+        bytes: &[u8],
+        forced: Option<&dyn StatementImporter>,
+    ) -> Result<()> {
+        match self.import_entry(db, session, path, bytes, forced).await? {
+            EntryImport::Imported => Ok(()),
+            EntryImport::NoViableImporter => {
+                info!(
+                    "No viable importer found for file: {:?} based on filename",
+                    path
+                );
+                Ok(())
+            }
+            EntryImport::NoMatchingContent => {
+                Err(anyhow!("No matching importer found for {:?}", path))
+            }
+        }
+    }
+
+    /// Reads the file, or just its start if it's large enough to be streamed.
+    fn read_for_matching(&self, path: &Path) -> Result<Vec<u8>> {
+        if fs::metadata(path)?.len() < self.streaming_threshold {
+            Ok(fs::read(path)?)
+        } else {
+            read_prefix(path)
+        }
+    }
+
+    /// Returns the importer that best matches the start of a large statement file or archive
+    /// entry, if that importer can import incrementally.
+    ///
+    /// Returns `Ok(None)` if the best matching importer can't stream, none matches, or the
+    /// content isn't UTF-8, which streaming importers read without decoding, for the content
+    /// to be read whole instead.
+    async fn streaming_importer<'a>(
+        &'a self,
+        path: &Path,
+        prefix: &'a [u8],
+        forced: Option<&'a dyn StatementImporter>,
+    ) -> Result<Option<&'a dyn StreamingStatementImporter>> {
+        let viable_importers = self.viable_importers(path, forced).await;
+        let Some((importer, _)) = self
+            .matching_importer(viable_importers, forced, path, prefix)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let Some(streaming_importer) = importer.streaming() else {
+            debug!(
+                "importer {} cannot stream {:?}, reading it whole",
+                importer.importer_name(),
+                path
+            );
+            return Ok(None);
+        };
+        if !text_encoding::is_utf8(prefix) {
+            debug!("{:?} is not UTF-8 text, reading it whole", path);
+            return Ok(None);
+        }
+        Ok(Some(streaming_importer))
+    }
+
+    /// Streams the statement to the importer, recording the import in the import ledger.
+    async fn import_stream(
+        &self,
+        importer: &dyn StreamingStatementImporter,
+        reader: &mut (dyn AsyncBufRead + Send + Unpin),
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        path: &Path,
+    ) -> Result<()> {
+        info!(
+            "streaming {:?} to importer {} in batches of {}",
+            path,
+            importer.importer_name(),
+            self.stream_batch_size
+        );
+        let source_id = ObjectId::new();
+        importer
+            .import_stream(
+                reader,
+                db,
+                session.clone(),
+                source_id,
+                self.stream_batch_size,
+            )
+            .await?;
+        ImportedFile::new(source_id, &path.to_string_lossy(), importer.importer_name())
+            .insert(db, session)
+            .await
+    }
+
+    /// Streams a large statement file to the importer that best matches its start, if that
+    /// importer can import incrementally, recording the import in the import ledger.
+    ///
+    /// Returns `Ok(false)` if the best matching importer can't stream, or none matches the
+    /// start of the file, for the file to be read whole instead.
+    async fn import_file_stream(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        path: &Path,
+        forced: Option<&dyn StatementImporter>,
+    ) -> Result<bool> {
+        let prefix = read_prefix(path)?;
+        let Some(importer) = self.streaming_importer(path, &prefix, forced).await? else {
+            return Ok(false);
+        };
+
+        let mut reader = tokio::io::BufReader::new(tokio::fs::File::open(path).await?);
+        self.import_stream(importer, &mut reader, db, session, path)
+            .await?;
+        Ok(true)
+    }
+
+    /// Imports an archive entry, warning instead of failing if no importer matches its
+    /// content, as archives often hold notes and other files besides statements.
+    async fn import_archive_entry(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        path: &Path,
+        bytes: &[u8],
+        forced: Option<&dyn StatementImporter>,
+    ) -> Result<()> {
+        info!("attempting to import archive entry: {:?}", path);
+        let result = self.import_entry(db, session, path, bytes, forced).await?;
+        if result == EntryImport::NoMatchingContent {
+            warn!("No matching importer found for {:?}", path);
+        }
+        Ok(())
+    }
+
+    /// Imports the entries of a large `.tar`, `.tar.gz`, `.tgz` or `.gz` archive as it is
+    /// decompressed, streaming entries to importers that can import them incrementally, and
+    /// reading the others whole.
+    async fn import_archive_stream(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        path: &Path,
+        forced: Option<&dyn StatementImporter>,
+    ) -> Result<()> {
+        let mut entries = archive::stream_entries(path, MATCH_PREFIX_LEN)?;
+        while let Some(entry) = entries.recv().await {
+            let entry = entry?;
+            let entry_path = entry.path.clone();
+            if !archive::is_archive(&entry_path) {
+                if self.viable_importers(&entry_path, forced).await.is_empty() {
+                    debug!("No viable importer found for {:?}", entry_path);
+                    entry.skip()?;
+                    continue;
+                }
+                let prefix = text_encoding::trim_partial_utf8(&entry.prefix).to_vec();
+                if let Some(importer) = self
+                    .streaming_importer(&entry_path, &prefix, forced)
+                    .await?
+                {
+                    let mut stream = entry.stream()?;
+                    self.import_stream(
+                        importer,
+                        &mut stream.reader,
+                        db,
+                        session.clone(),
+                        &entry_path,
+                    )
+                    .await?;
+                    stream.finish().await?;
+                    continue;
+                }
+            }
+
+            let bytes = entry.read().await?;
+            if archive::is_archive(&entry_path) {
+                for nested in archive::read_entries(&entry_path, &bytes)? {
+                    self.import_archive_entry(
+                        db,
+                        session.clone(),
+                        &nested.path,
+                        &nested.bytes,
+                        forced,
+                    )
+                    .await?;
+                }
+            } else {
+                self.import_archive_entry(db, session.clone(), &entry_path, &bytes, forced)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the date of the statement in the file, if an importer recognizes it and can tell.
+    async fn statement_date(
+        &self,
+        path: &Path,
+        forced: Option<&dyn StatementImporter>,
+    ) -> Result<Option<NaiveDate>> {
+        let viable_importers = self.viable_importers(path, forced).await;
+        if viable_importers.is_empty() || archive::is_archive(path) {
+            return Ok(None);
+        }
+
+        let bytes = self.read_for_matching(path)?;
+        Ok(self
+            .matching_importer(viable_importers, forced, path, &bytes)
+            .await?
+            .and_then(|(importer, content)| importer.statement_date(&content)))
+    }
+
+    /// Returns the selected statement files, oldest first.
+    ///
+    /// Files are ordered by their statement date where their importer can tell, and otherwise
+    /// by their modification time, with ties broken by path. Importing in this order applies
+    /// corporate actions and corrections after the statements they affect.
+    pub async fn chronological_statement_files(
+        &self,
+        selection: &FileSelection,
+    ) -> Result<Vec<PathBuf>> {
+        self.chronological_statement_files_with(selection, None)
+            .await
+    }
+
+    /// Returns the selected statement files, oldest first, dating them with the `forced`
+    /// importer if one is named. See `import_statement_files_with`.
+    pub async fn chronological_statement_files_with(
+        &self,
+        selection: &FileSelection,
+        forced: Option<&str>,
+    ) -> Result<Vec<PathBuf>> {
+        let forced = self.forced_importer(forced)?;
+        let mut dated_files = Vec::<(i64, PathBuf)>::new();
+        for path in selection.files()? {
+            let timestamp_ms = match self.statement_date(&path, forced).await? {
+                Some(date) => parse_util::date_timestamp_ms(date, chrono_tz::UTC)?,
+                None => {
+                    let modified = fs::metadata(&path)?.modified()?;
+                    DateTime::<Utc>::from(modified).timestamp_millis()
+                }
+            };
+            dated_files.push((timestamp_ms, path));
+        }
+
+        dated_files.sort();
+        Ok(dated_files.into_iter().map(|(_, path)| path).collect())
+    }
+
+    /// Imports the selected statement files, oldest first.
+    pub async fn import_selected_statement_files(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        selection: &FileSelection,
+    ) -> Result<()> {
+        let paths = self.chronological_statement_files(selection).await?;
+        self.import_statement_files(db, session, paths).await
+    }
+
+    /// Imports statement files.
+    ///
+    /// `.zip`, `.tar`, `.tar.gz`, `.tgz` and `.gz` archives are opened and each of their files
+    /// is imported as if it were a file at its archive-qualified path, e.g. `2024.zip!/jan.xml`.
+    /// Unlike plain files, archive entries that no importer recognizes are skipped.
+    ///
+    /// Files at least as large as the streaming threshold are streamed to their importer if
+    /// it supports streaming and they are UTF-8 text, as are the entries of `.tar`, `.tar.gz`,
+    /// `.tgz` and `.gz` archives that large.
     pub async fn import_statement_files(
         &self,
         db: &Database,
         session: Option<Arc<Mutex<ClientSession>>>,
         paths: Vec<PathBuf>,
     ) -> Result<()> {
-        for path in paths {
-            // Construct a new statement source ID for each file.
-            let source_id = ObjectId::new();
+        self.import_statement_files_with(db, session, paths, None)
+            .await
+    }
 
+    /// Imports statement files like `import_statement_files`, but with the `forced` importer
+    /// if one is named, regardless of path and content.
+    ///
+    /// This resolves ambiguous matches, and imports files an importer doesn't recognize,
+    /// such as statements renamed by hand.
+    pub async fn import_statement_files_with(
+        &self,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        paths: Vec<PathBuf>,
+        forced: Option<&str>,
+    ) -> Result<()> {
+        let forced = self.forced_importer(forced)?;
+        for path in paths {
             info!("attempting to import brokerage statement file: {:?}", path);
 
-            // Find the set of importers that can handle this path.
-            let mut viable_importers = Vec::<&dyn StatementImporter>::new();
-            for importer in self.importers.iter() {
-                if importer.path_may_match(&path).await == PathMatch::Match {
-                    viable_importers.push(importer.as_ref());
+            if archive::is_archive(&path) {
+                if archive::is_streamable(&path)
+                    && fs::metadata(&path)?.len() >= self.streaming_threshold
+                {
+                    self.import_archive_stream(db, session.clone(), &path, forced)
+                        .await?;
+                    continue;
                 }
+                let bytes = fs::read(&path)?;
+                for entry in archive::read_entries(&path, &bytes)? {
+                    self.import_archive_entry(
+                        db,
+                        session.clone(),
+                        &entry.path,
+                        &entry.bytes,
+                        forced,
+                    )
+                    .await?;
+                }
+                continue;
             }
 
-            if viable_importers.is_empty() {
+            // Skip reading files that no importer handles based on the filename.
+            if self.viable_importers(&path, forced).await.is_empty() {
                 info!(
                     "No viable importer found for file: {:?} based on filename",
                     path
@@ -99,11 +714,18 @@ impl ImporterRegistry {
                 continue;
             }
 
-            // Read the file contents.
-            let content = fs::read_to_string(&path)?;
+            // Stream large files to importers that can import them incrementally.
+            if fs::metadata(&path)?.len() >= self.streaming_threshold
+                && self
+                    .import_file_stream(db, session.clone(), &path, forced)
+                    .await?
+            {
+                continue;
+            }
 
-            // Import the file contents using the first hard-match importer based on content.
-            self.import_with_importers(viable_importers, &content, db, session.clone(), source_id)
+            // Import the file contents using the most confident importer based on content.
+            let bytes = fs::read(&path)?;
+            self.import_file_entry(db, session.clone(), &path, &bytes, forced)
                 .await?;
         }
         Ok(())
@@ -0,0 +1,396 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::NaiveDateTime;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{collections::HashMap, path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    crypto_asset::{self, CRYPTO_LISTING_EXCHANGE},
+    parse_util::{self, CsvRow, CsvTable},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const KRAKEN_BROKERAGE_ID: &str = "kraken";
+
+const LEDGERS_HEADER: [&str; 4] = ["txid", "refid", "time", "type"];
+const TRADES_HEADER: [&str; 4] = ["txid", "ordertxid", "pair", "time"];
+
+/// Quote assets that Kraken pair names can end with, longest first.
+const PAIR_QUOTES: [&str; 21] = [
+    "ZUSD", "ZEUR", "ZGBP", "ZCAD", "ZJPY", "ZAUD", "ZCHF", "XXBT", "XETH", "USDT", "USDC", "USD",
+    "EUR", "GBP", "CAD", "JPY", "AUD", "CHF", "DAI", "XBT", "ETH",
+];
+
+/// Kraken asset codes that differ from the common symbol: the legacy codes of the assets listed
+/// before 2018, prefixed with `X` for crypto assets and `Z` for fiat currencies, and a few others.
+/// Assets listed since, such as `ZEUS` or `XCN`, use their common symbol.
+const KRAKEN_ASSET_CODES: [(&str, &str); 26] = [
+    ("XXBT", "BTC"),
+    ("XBT", "BTC"),
+    ("XXDG", "DOGE"),
+    ("XDG", "DOGE"),
+    ("XETH", "ETH"),
+    ("ETH2", "ETH"),
+    ("XETC", "ETC"),
+    ("XLTC", "LTC"),
+    ("XXRP", "XRP"),
+    ("XXLM", "XLM"),
+    ("XXMR", "XMR"),
+    ("XZEC", "ZEC"),
+    ("XREP", "REP"),
+    ("XMLN", "MLN"),
+    ("XICN", "ICN"),
+    ("XNMC", "NMC"),
+    ("XXVN", "XVN"),
+    ("XDAO", "DAO"),
+    ("ZUSD", "USD"),
+    ("ZEUR", "EUR"),
+    ("ZGBP", "GBP"),
+    ("ZCAD", "CAD"),
+    ("ZJPY", "JPY"),
+    ("ZAUD", "AUD"),
+    ("ZCHF", "CHF"),
+    ("ZKRW", "KRW"),
+];
+
+/// Converts a Kraken asset code to its common symbol, e.g. `XXBT` to `BTC`, `ZUSD` to `USD` and
+/// the staked `DOT.S` to `DOT`.
+pub fn normalize_kraken_asset(asset: &str) -> String {
+    let unstaked = asset.split('.').next().unwrap_or(asset);
+    KRAKEN_ASSET_CODES
+        .iter()
+        .find(|(code, _)| *code == unstaked)
+        .map_or(unstaked, |(_, symbol)| symbol)
+        .to_owned()
+}
+
+/// Splits a Kraken pair such as `XXBTZUSD`, `SOLUSD` or `ETH/XBT` into its base and quote assets.
+pub fn split_kraken_pair(pair: &str) -> Option<(String, String)> {
+    if let Some((base, quote)) = pair.split_once('/') {
+        return Some((normalize_kraken_asset(base), normalize_kraken_asset(quote)));
+    }
+
+    PAIR_QUOTES.iter().find_map(|quote| {
+        let base = pair.strip_suffix(quote)?;
+        (!base.is_empty()).then(|| (normalize_kraken_asset(base), normalize_kraken_asset(quote)))
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KrakenCsvKind {
+    Ledgers,
+    Trades,
+}
+
+/// A row of the ledgers export.
+struct LedgerEntry {
+    txid: String,
+    refid: String,
+    timestamp_ms: i64,
+    entry_type: String,
+    subtype: String,
+    asset: String,
+    amount: f64,
+    fee: f64,
+}
+
+/// Imports the Kraken ledgers and trades CSV exports.
+///
+/// The exports don't name the account, so the account id is given when constructing the
+/// importer. Crypto assets are recorded as securities listed on `CRYPTO_LISTING_EXCHANGE`, under
+/// their common symbol.
+///
+/// The ledgers export records each trade as a pair of entries sharing a reference id, which
+/// becomes the execution id. Fees charged in the quote asset become the trade commission, while
+/// fees charged in the base asset are recorded as a fee cash transaction in that asset. Staking
+/// rewards are recorded as income, and crypto deposits, withdrawals and moves between wallets as
+/// transfers. The trades export only has trades, with fees in the quote asset.
+///
+/// Both exports contain the same trades under the same ids, so import trades from only one of
+/// them.
+pub struct KrakenCsvImporter {
+    account_id: String,
+}
+
+impl KrakenCsvImporter {
+    pub fn new(account_id: &str) -> Self {
+        Self {
+            account_id: account_id.to_owned(),
+        }
+    }
+
+    /// Returns which Kraken export the content is, judging by its header row.
+    pub fn detect(content: &str) -> Option<(KrakenCsvKind, usize)> {
+        parse_util::find_csv_header(content, &LEDGERS_HEADER)
+            .map(|offset| (KrakenCsvKind::Ledgers, offset))
+            .or_else(|| {
+                parse_util::find_csv_header(content, &TRADES_HEADER)
+                    .map(|offset| (KrakenCsvKind::Trades, offset))
+            })
+    }
+
+    fn parse_timestamp_ms(value: &str) -> Result<i64> {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+            .map(|dt| dt.and_utc().timestamp_millis())
+            .map_err(|e| anyhow!("invalid Kraken time '{}': {}", value, e))
+    }
+
+    fn ledger_entry(row: &CsvRow) -> Result<LedgerEntry> {
+        Ok(LedgerEntry {
+            txid: row.get("txid").to_owned(),
+            refid: row.get("refid").to_owned(),
+            timestamp_ms: Self::parse_timestamp_ms(row.get("time"))?,
+            entry_type: row.get("type").to_owned(),
+            subtype: row.get("subtype").to_owned(),
+            asset: normalize_kraken_asset(row.get("asset")),
+            amount: parse_util::parse_amount_or_zero(row.get("amount"))?,
+            fee: parse_util::parse_amount_or_zero(row.get("fee"))?,
+        })
+    }
+
+    fn cash(entry: &LedgerEntry, id: String, kind: CashTransactionKind, amount: f64) -> Activity {
+        Activity::Cash(CashActivity {
+            brokerage_transaction_id: id,
+            kind,
+            amount,
+            currency: entry.asset.clone(),
+            timestamp_ms: entry.timestamp_ms,
+            ticker: (!crypto_asset::is_fiat(&entry.asset)).then(|| entry.asset.clone()),
+            listing_exchange: (!crypto_asset::is_fiat(&entry.asset))
+                .then(|| CRYPTO_LISTING_EXCHANGE.to_owned()),
+            description: match entry.subtype.as_str() {
+                "" => entry.entry_type.clone(),
+                subtype => format!("{} ({})", entry.entry_type, subtype),
+            },
+        })
+    }
+
+    fn fee_cash(entry: &LedgerEntry) -> Option<Activity> {
+        (entry.fee != 0.0).then(|| {
+            Self::cash(
+                entry,
+                format!("{}:fee", entry.txid),
+                CashTransactionKind::Fee,
+                -entry.fee.abs(),
+            )
+        })
+    }
+
+    /// Builds the trade for the two ledger entries of a trade, plus any fee in the base asset.
+    fn ledger_trade(first: &LedgerEntry, second: &LedgerEntry) -> Vec<Activity> {
+        // Fiat is always the quote asset. Between two crypto assets, the one spent is.
+        let first_is_quote = match (
+            crypto_asset::is_fiat(&first.asset),
+            crypto_asset::is_fiat(&second.asset),
+        ) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => first.amount < 0.0,
+        };
+        let (base, quote) = if first_is_quote {
+            (second, first)
+        } else {
+            (first, second)
+        };
+
+        let quantity = base.amount.abs();
+        let mut activities = vec![Activity::Trade(TradeActivity {
+            brokerage_execution_id: base.refid.clone(),
+            ticker: base.asset.clone(),
+            listing_exchange: Some(CRYPTO_LISTING_EXCHANGE.to_owned()),
+            side: if base.amount > 0.0 {
+                TradeSide::Buy
+            } else {
+                TradeSide::Sell
+            },
+            quantity,
+            price: if quantity == 0.0 {
+                0.0
+            } else {
+                quote.amount.abs() / quantity
+            },
+            commission: quote.fee.abs(),
+            execution_timestamp_ms: base.timestamp_ms,
+        })];
+        activities.extend(Self::fee_cash(base));
+        activities
+    }
+
+    fn parse_ledgers(table: &CsvTable) -> Result<Vec<Activity>> {
+        let entries = table
+            .rows()
+            .filter(|row| !row.get("txid").is_empty())
+            .map(|row| Self::ledger_entry(&row))
+            .collect::<Result<Vec<LedgerEntry>>>()?;
+
+        // Both entries of a trade share its refid.
+        let mut trade_entries = HashMap::<&str, Vec<&LedgerEntry>>::new();
+        for entry in &entries {
+            if matches!(entry.entry_type.as_str(), "trade" | "spend" | "receive") {
+                trade_entries.entry(&entry.refid).or_default().push(entry);
+            }
+        }
+
+        let mut activities = Vec::new();
+
+        for entry in &entries {
+            match entry.entry_type.as_str() {
+                "trade" | "spend" | "receive" => {
+                    // The trade is built at its first entry, after which its refid is removed.
+                    let Some(refid_entries) = trade_entries.remove(entry.refid.as_str()) else {
+                        continue;
+                    };
+                    let Some(other) = refid_entries.iter().find(|other| other.txid != entry.txid)
+                    else {
+                        debug!(
+                            "skipping Kraken trade entry {} without its pair",
+                            entry.txid
+                        );
+                        continue;
+                    };
+                    activities.extend(Self::ledger_trade(entry, other));
+                }
+                "deposit" | "withdrawal" => {
+                    let kind = match (
+                        crypto_asset::is_fiat(&entry.asset),
+                        entry.entry_type.as_str(),
+                    ) {
+                        (true, "deposit") => CashTransactionKind::Deposit,
+                        (true, _) => CashTransactionKind::Withdrawal,
+                        (false, _) => CashTransactionKind::Transfer,
+                    };
+                    activities.push(Self::cash(entry, entry.txid.clone(), kind, entry.amount));
+                    activities.extend(Self::fee_cash(entry));
+                }
+                "staking" if entry.amount > 0.0 && entry.subtype.is_empty() => {
+                    activities.push(Self::cash(
+                        entry,
+                        entry.txid.clone(),
+                        CashTransactionKind::Income,
+                        entry.amount,
+                    ));
+                }
+                "earn" if entry.subtype == "reward" => {
+                    activities.push(Self::cash(
+                        entry,
+                        entry.txid.clone(),
+                        CashTransactionKind::Income,
+                        entry.amount,
+                    ));
+                }
+                "transfer" | "staking" | "earn" => {
+                    activities.push(Self::cash(
+                        entry,
+                        entry.txid.clone(),
+                        CashTransactionKind::Transfer,
+                        entry.amount,
+                    ));
+                }
+                other => debug!("skipping unsupported Kraken ledger type '{}'", other),
+            }
+        }
+
+        Ok(activities)
+    }
+
+    fn parse_trades(table: &CsvTable) -> Result<Vec<Activity>> {
+        table.require_columns(&["price", "fee", "vol"])?;
+
+        table
+            .rows()
+            .filter(|row| !row.get("txid").is_empty())
+            .map(|row| {
+                let pair = row.get("pair");
+                let (base, _) = split_kraken_pair(pair)
+                    .ok_or_else(|| anyhow!("unrecognized Kraken pair '{}'", pair))?;
+                Ok(Activity::Trade(TradeActivity {
+                    brokerage_execution_id: row.get("txid").to_owned(),
+                    ticker: base,
+                    listing_exchange: Some(CRYPTO_LISTING_EXCHANGE.to_owned()),
+                    side: match row.get("type") {
+                        "buy" => TradeSide::Buy,
+                        "sell" => TradeSide::Sell,
+                        other => return Err(anyhow!("invalid Kraken trade type '{}'", other)),
+                    },
+                    quantity: parse_util::parse_amount_or_zero(row.get("vol"))?,
+                    price: parse_util::parse_amount_or_zero(row.get("price"))?,
+                    commission: parse_util::parse_amount_or_zero(row.get("fee"))?.abs(),
+                    execution_timestamp_ms: Self::parse_timestamp_ms(row.get("time"))?,
+                }))
+            })
+            .collect()
+    }
+
+    /// Parses either Kraken export into account activities.
+    pub fn parse(&self, content: &str) -> Result<AccountActivities> {
+        let (kind, header_offset) =
+            Self::detect(content).ok_or_else(|| anyhow!("Kraken CSV header not found"))?;
+        let table = CsvTable::parse(&content[header_offset..])?;
+
+        let activities = match kind {
+            KrakenCsvKind::Ledgers => Self::parse_ledgers(&table)?,
+            KrakenCsvKind::Trades => Self::parse_trades(&table)?,
+        };
+
+        Ok(AccountActivities {
+            account_id: self.account_id.clone(),
+            activities,
+        })
+    }
+}
+
+#[async_trait]
+impl StatementImporter for KrakenCsvImporter {
+    fn importer_name(&self) -> &'static str {
+        "kraken-csv"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        match Self::detect(content) {
+            Some((kind, _)) => ContentMatch::likely(format!("Kraken {:?} CSV header", kind)),
+            None => ContentMatch::no("no Kraken ledgers or trades CSV header"),
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing Kraken CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let account_activities = self.parse(content)?;
+        writers::write_account_activities(
+            db,
+            session,
+            KRAKEN_BROKERAGE_ID,
+            &account_activities,
+            write_batch_size,
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -1,7 +1,41 @@
+pub mod account_type;
+pub mod activity;
+pub mod alpaca_json_importer;
+pub mod archive;
+mod batch_writer;
+pub mod camt053_importer;
+pub mod cash_transaction;
+pub mod coinbase_csv_importer;
+pub mod configurable_csv_importer;
+pub mod content_match;
+pub mod crypto_asset;
+mod db_util;
+pub mod degiro_csv_importer;
+pub mod email_ingestion;
+pub mod etrade_csv_importer;
+pub mod fidelity_csv_importer;
+pub mod file_selection;
+pub mod fix_log_importer;
 pub mod ibkr_flex_statement_importer;
+pub mod imported_file;
 pub mod importer_registry;
+pub mod kraken_csv_importer;
+pub mod mt940_importer;
+pub mod ofx_importer;
+pub mod option_contract;
+mod parse_util;
 pub mod path_match;
+pub mod pdf_statement_importer;
+pub mod qif_importer;
+pub mod questrade_csv_importer;
+pub mod robinhood_csv_importer;
+pub mod schwab_csv_importer;
 pub mod statement_importer;
+pub mod streaming_statement_importer;
+pub mod tastytrade_csv_importer;
+pub mod text_encoding;
+pub mod trading212_csv_importer;
+pub mod vanguard_csv_importer;
 mod writers;
 
 use anyhow::Result;
@@ -0,0 +1,377 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::{Datelike, NaiveDate};
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    parse_util::{self, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+const MT940_EXTENSIONS: [&str; 4] = ["sta", "mt940", "940", "txt"];
+
+/// The cash statements of one MT940 file, along with the brokerage they belong to.
+#[derive(Debug, PartialEq)]
+pub struct Mt940Statement {
+    pub brokerage_id: String,
+    pub accounts: Vec<AccountActivities>,
+}
+
+/// A `:61:` statement line, with the `:86:` information that follows it, or failing that the
+/// line's supplementary details.
+#[derive(Debug, PartialEq)]
+pub struct Mt940StatementLine {
+    pub value_date: NaiveDate,
+    pub entry_date: Option<NaiveDate>,
+    pub amount: f64,
+    /// The SWIFT transaction type code, e.g. `DIV` from `NDIV`.
+    pub transaction_type: String,
+    pub customer_reference: String,
+    pub bank_reference: Option<String>,
+    pub information: String,
+}
+
+/// Maps a SWIFT transaction type code to the kind of cash transaction it records.
+///
+/// Returns `None` for codes that aren't imported, such as the cash legs of securities trades.
+pub fn transaction_type_kind(transaction_type: &str) -> Option<CashTransactionKind> {
+    let kind = match transaction_type {
+        "DIV" => CashTransactionKind::Dividend,
+        "INT" | "CPN" => CashTransactionKind::Interest,
+        "CHG" | "COM" | "FEE" => CashTransactionKind::Fee,
+        "TAX" => CashTransactionKind::Tax,
+        "FEX" => CashTransactionKind::ForeignExchange,
+        "TRF" | "STO" | "DDT" | "CHK" | "RTI" => CashTransactionKind::Transfer,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+fn parse_yymmdd(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%y%m%d")
+        .map_err(|e| anyhow!("invalid MT940 date '{}': {}", value, e))
+}
+
+/// Parses a `:61:` field, e.g. `2501020102C123,45NDIVNONREF//BANKREF123`.
+pub fn parse_statement_line(value: &str) -> Result<Mt940StatementLine> {
+    let (first_line, supplementary) = value.split_once('\n').unwrap_or((value, ""));
+    let invalid = || anyhow!("invalid MT940 statement line '{}'", first_line);
+
+    let value_date = parse_yymmdd(first_line.get(..6).ok_or_else(invalid)?)?;
+    let mut rest = &first_line[6..];
+
+    // The optional entry date is MMDD in the value date's year, or an adjacent one.
+    let mut entry_date = None;
+    if rest.len() >= 4 && rest.as_bytes()[..4].iter().all(u8::is_ascii_digit) {
+        let (month, day) = (rest[..2].parse::<u32>()?, rest[2..4].parse::<u32>()?);
+        let year = match (value_date.month(), month) {
+            (12, 1) => value_date.year() + 1,
+            (1, 12) => value_date.year() - 1,
+            _ => value_date.year(),
+        };
+        entry_date = Some(NaiveDate::from_ymd_opt(year, month, day).ok_or_else(invalid)?);
+        rest = &rest[4..];
+    }
+
+    let (sign, mark_len) = match rest.get(..2) {
+        Some("RC") => (-1.0, 2),
+        Some("RD") => (1.0, 2),
+        _ => match rest.get(..1) {
+            Some("C") => (1.0, 1),
+            Some("D") => (-1.0, 1),
+            _ => return Err(invalid()),
+        },
+    };
+    rest = &rest[mark_len..];
+    // The optional funds code is the last letter of the currency.
+    if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        rest = &rest[1..];
+    }
+
+    let amount_len = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == ','))
+        .ok_or_else(invalid)?;
+    let amount =
+        parse_util::parse_amount_with_separator(&rest[..amount_len], ',')?.ok_or_else(invalid)?;
+    rest = &rest[amount_len..];
+
+    let transaction_type = rest.get(1..4).ok_or_else(invalid)?.to_owned();
+    rest = &rest[4..];
+    let (customer_reference, bank_reference) = match rest.split_once("//") {
+        Some((customer, bank)) => (customer, Some(bank.trim().to_owned())),
+        None => (rest, None),
+    };
+
+    Ok(Mt940StatementLine {
+        value_date,
+        entry_date,
+        amount: sign * amount,
+        transaction_type,
+        customer_reference: customer_reference.trim().to_owned(),
+        bank_reference: bank_reference.filter(|r| !r.is_empty()),
+        information: supplementary.trim().to_owned(),
+    })
+}
+
+/// Removes the `?NN` subfield codes that German banks use in the `:86:` field.
+fn strip_subfield_codes(information: &str) -> String {
+    let mut stripped = String::new();
+    let mut chars = information.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '?' && chars.peek().is_some_and(char::is_ascii_digit) {
+            chars.next();
+            chars.next_if(char::is_ascii_digit);
+            stripped.push(' ');
+        } else if c != '\n' && c != '\r' {
+            stripped.push(c);
+        }
+    }
+    stripped.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Splits the content of a statement message into its `:tag:` fields, joining continuation
+/// lines to the field they continue.
+fn fields(message: &str) -> Vec<(&str, String)> {
+    let mut fields = Vec::<(&str, String)>::new();
+    for line in message.lines() {
+        let tag = line
+            .strip_prefix(':')
+            .and_then(|rest| rest.split_once(':'))
+            .filter(|(tag, _)| tag.len() <= 3 && tag.starts_with(|c: char| c.is_ascii_digit()));
+        match (tag, fields.last_mut()) {
+            (Some((tag, value)), _) => fields.push((tag, value.to_owned())),
+            (None, Some((_, value))) if line != "-" && !line.starts_with('-') => {
+                value.push('\n');
+                value.push_str(line);
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+/// Imports SWIFT MT940 customer statement messages.
+///
+/// The `:25:` account identification, usually an IBAN, becomes the brokerage account id. The
+/// brokerage id is the BIC of the sending bank from the message's basic header block, or failing
+/// that the id configured with `brokerage_id`.
+///
+/// Statement lines are classified by their SWIFT transaction type as dividends, interest, fees,
+/// taxes, foreign exchange or transfers; others, such as the cash legs of securities trades, are
+/// skipped. Dividends are linked to their security by an ISIN found in the `:86:` information,
+/// which is recorded as the security's ticker. Entry dates, or value dates where there is no
+/// entry date, are recorded at midnight UTC.
+pub struct Mt940Importer {
+    brokerage_id: Option<String>,
+}
+
+impl Default for Mt940Importer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mt940Importer {
+    pub fn new() -> Self {
+        Self { brokerage_id: None }
+    }
+
+    /// Sets the brokerage id used when the file has no basic header block.
+    pub fn brokerage_id(mut self, brokerage_id: &str) -> Self {
+        self.brokerage_id = Some(brokerage_id.to_owned());
+        self
+    }
+
+    /// Returns the BIC in the basic header block, e.g. `DEUTDEFF` from
+    /// `{1:F01DEUTDEFFAXXX0000000000}`.
+    fn sender_bic(content: &str) -> Option<&str> {
+        let block = &content[content.find("{1:")? + 3..];
+        block.get(3..11).filter(|bic| {
+            bic.chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        })
+    }
+
+    fn is_mt940(content: &str) -> bool {
+        let fields = fields(content);
+        let has = |tag: &str| fields.iter().any(|(t, _)| *t == tag);
+        has("20") && has("25") && fields.iter().any(|(t, _)| t.starts_with("60"))
+    }
+
+    fn account_id(value: &str) -> String {
+        // Some banks write the account as `BIC/ACCOUNT`.
+        value.rsplit('/').next().unwrap_or(value).trim().to_owned()
+    }
+
+    fn parse_line(
+        line: &Mt940StatementLine,
+        account_id: &str,
+        currency: &str,
+        raw: &str,
+        ids: &mut SyntheticIdGenerator,
+    ) -> Result<Option<Activity>> {
+        let Some(kind) = transaction_type_kind(&line.transaction_type) else {
+            debug!(
+                "skipping MT940 statement line with transaction type {}",
+                line.transaction_type
+            );
+            return Ok(None);
+        };
+
+        let information = strip_subfield_codes(&line.information);
+        let customer_reference =
+            Some(line.customer_reference.as_str()).filter(|r| !r.is_empty() && *r != "NONREF");
+        let id = line
+            .bank_reference
+            .as_deref()
+            .or(customer_reference)
+            .map(str::to_owned)
+            .unwrap_or_else(|| ids.id("mt940", &[account_id, raw, &information]));
+        let ticker = match kind {
+            CashTransactionKind::Dividend | CashTransactionKind::Tax => {
+                parse_util::find_isin(&information).map(str::to_owned)
+            }
+            _ => None,
+        };
+
+        Ok(Some(Activity::Cash(CashActivity {
+            brokerage_transaction_id: id,
+            kind,
+            amount: line.amount,
+            currency: currency.to_owned(),
+            timestamp_ms: parse_util::date_timestamp_ms(
+                line.entry_date.unwrap_or(line.value_date),
+                chrono_tz::UTC,
+            )?,
+            ticker,
+            listing_exchange: None,
+            description: information,
+        })))
+    }
+
+    /// Parses the statement messages into the cash entries of each statement's account.
+    pub fn parse(&self, content: &str) -> Result<Mt940Statement> {
+        let brokerage_id = Self::sender_bic(content)
+            .map(str::to_owned)
+            .or_else(|| self.brokerage_id.clone())
+            .ok_or_else(|| anyhow!("MT940 file has no sender BIC and no brokerage id is set"))?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut accounts = Vec::<AccountActivities>::new();
+        let mut account_id = None;
+        let mut currency = String::new();
+
+        let fields = fields(content);
+        for (index, (tag, value)) in fields.iter().enumerate() {
+            match *tag {
+                "25" => account_id = Some(Self::account_id(value)),
+                "60F" | "60M" => {
+                    currency = value
+                        .get(7..10)
+                        .ok_or_else(|| anyhow!("invalid MT940 opening balance '{}'", value))?
+                        .to_owned();
+                }
+                "61" => {
+                    let account_id = account_id
+                        .as_deref()
+                        .ok_or_else(|| anyhow!("MT940 statement line before the :25: account"))?;
+                    let mut line = parse_statement_line(value)?;
+                    if let Some(("86", information)) = fields.get(index + 1).map(|(t, v)| (*t, v)) {
+                        line.information = information.clone();
+                    }
+
+                    let Some(activity) =
+                        Self::parse_line(&line, account_id, &currency, value, &mut ids)?
+                    else {
+                        continue;
+                    };
+                    match accounts.iter_mut().find(|a| a.account_id == account_id) {
+                        Some(account) => account.activities.push(activity),
+                        None => accounts.push(AccountActivities {
+                            account_id: account_id.to_owned(),
+                            activities: vec![activity],
+                        }),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Mt940Statement {
+            brokerage_id,
+            accounts,
+        })
+    }
+}
+
+#[async_trait]
+impl StatementImporter for Mt940Importer {
+    fn importer_name(&self) -> &'static str {
+        "mt940"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path
+            .extension()
+            .is_some_and(|ext| MT940_EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+        {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if Self::is_mt940(content) {
+            ContentMatch::likely("MT940 :20:, :25: and :60: fields")
+        } else {
+            ContentMatch::no("no MT940 :20:, :25: and :60: fields")
+        }
+    }
+
+    fn statement_date(&self, content: &str) -> Option<NaiveDate> {
+        // The date of the last closing balance, e.g. `C250331EUR10172,66`.
+        fields(content)
+            .iter()
+            .rev()
+            .find(|(tag, _)| matches!(*tag, "62F" | "62M"))
+            .and_then(|(_, value)| parse_yymmdd(value.get(1..7)?).ok())
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing MT940 statement with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let statement = self.parse(content)?;
+        for account_activities in &statement.accounts {
+            writers::write_account_activities(
+                db,
+                session.clone(),
+                &statement.brokerage_id,
+                account_activities,
+                write_batch_size,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,359 @@
+pub mod ofx_document;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::NaiveDate;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{collections::HashMap, path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    parse_util,
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+use ofx_document::{OfxElement, parse_ofx_timestamp_ms};
+
+/// A security from the `SECLIST` of an OFX statement.
+#[derive(Debug, PartialEq)]
+pub struct OfxSecurity {
+    /// The security id, usually a CUSIP.
+    pub unique_id: String,
+    pub unique_id_type: String,
+    pub ticker: Option<String>,
+    pub name: String,
+}
+
+impl OfxSecurity {
+    /// The ticker used to store the security, falling back to its unique id.
+    pub fn stored_ticker(&self) -> &str {
+        self.ticker.as_deref().unwrap_or(&self.unique_id)
+    }
+}
+
+/// A position from the `INVPOSLIST` of an OFX statement.
+#[derive(Debug, PartialEq)]
+pub struct OfxPosition {
+    pub account_id: String,
+    pub ticker: String,
+    pub units: f64,
+    pub unit_price: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct OfxStatement {
+    pub brokerage_id: String,
+    pub securities: Vec<OfxSecurity>,
+    pub positions: Vec<OfxPosition>,
+    pub accounts: Vec<AccountActivities>,
+}
+
+/// Imports OFX 1.x (SGML) and 2.x (XML) investment statements, including Quicken QFX files.
+///
+/// The brokerage id comes from the statement's `<FI><ORG>` element. Transactions reference
+/// securities by CUSIP, which is resolved to a ticker through the statement's `SECLIST`.
+/// Securities without a ticker are stored under their CUSIP.
+pub struct OfxImporter {
+    brokerage_id: Option<String>,
+}
+
+impl Default for OfxImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OfxImporter {
+    pub fn new() -> Self {
+        Self { brokerage_id: None }
+    }
+
+    /// Sets the brokerage id used when the statement has no `<FI><ORG>` element.
+    pub fn brokerage_id(mut self, brokerage_id: &str) -> Self {
+        self.brokerage_id = Some(brokerage_id.to_owned());
+        self
+    }
+
+    fn statement_brokerage_id(&self, ofx: &OfxElement) -> Result<String> {
+        let org = ofx.text(&["SIGNONMSGSRSV1", "SONRS", "FI", "ORG"]);
+        if !org.is_empty() {
+            return Ok(org.to_owned());
+        }
+
+        self.brokerage_id
+            .clone()
+            .or_else(|| {
+                ofx.descendants("BROKERID")
+                    .first()
+                    .and_then(|e| e.text.clone())
+            })
+            .ok_or_else(|| anyhow!("OFX statement has no <FI><ORG> and no brokerage id is set"))
+    }
+
+    fn parse_securities(ofx: &OfxElement) -> Vec<OfxSecurity> {
+        ofx.descendants("SECINFO")
+            .into_iter()
+            .map(|info| {
+                let ticker = info.text(&["TICKER"]);
+                OfxSecurity {
+                    unique_id: info.text(&["SECID", "UNIQUEID"]).to_owned(),
+                    unique_id_type: info.text(&["SECID", "UNIQUEIDTYPE"]).to_owned(),
+                    ticker: (!ticker.is_empty()).then(|| ticker.to_owned()),
+                    name: info.text(&["SECNAME"]).to_owned(),
+                }
+            })
+            .collect()
+    }
+
+    /// Parses the OFX document into its securities, positions and account activities.
+    pub fn parse(&self, content: &str) -> Result<OfxStatement> {
+        let ofx = OfxElement::parse(content)?;
+        let brokerage_id = self.statement_brokerage_id(&ofx)?;
+        let securities = Self::parse_securities(&ofx);
+        let tickers = securities
+            .iter()
+            .map(|s| (s.unique_id.as_str(), s.stored_ticker()))
+            .collect::<HashMap<&str, &str>>();
+        let ticker = |aggregate: &OfxElement| -> String {
+            let unique_id = aggregate.text(&["SECID", "UNIQUEID"]);
+            tickers
+                .get(unique_id)
+                .copied()
+                .unwrap_or(unique_id)
+                .to_owned()
+        };
+
+        let mut positions = Vec::new();
+        let mut accounts = Vec::new();
+
+        for statement in ofx.descendants("INVSTMTRS") {
+            let account_id = statement.text(&["INVACCTFROM", "ACCTID"]).to_owned();
+            let currency = match statement.text(&["CURDEF"]) {
+                "" => "USD",
+                currency => currency,
+            };
+
+            if let Some(position_list) = statement.child("INVPOSLIST") {
+                for position in &position_list.children {
+                    let Some(invpos) = position.child("INVPOS") else {
+                        continue;
+                    };
+                    positions.push(OfxPosition {
+                        account_id: account_id.clone(),
+                        ticker: ticker(invpos),
+                        units: parse_util::parse_amount_or_zero(invpos.text(&["UNITS"]))?,
+                        unit_price: parse_util::parse_amount_or_zero(invpos.text(&["UNITPRICE"]))?,
+                    });
+                }
+            }
+
+            let mut activities = Vec::new();
+            if let Some(transaction_list) = statement.child("INVTRANLIST") {
+                for transaction in &transaction_list.children {
+                    Self::parse_transaction(transaction, currency, &ticker, &mut activities)?;
+                }
+            }
+
+            accounts.push(AccountActivities {
+                account_id,
+                activities,
+            });
+        }
+
+        Ok(OfxStatement {
+            brokerage_id,
+            securities,
+            positions,
+            accounts,
+        })
+    }
+
+    fn income_kind(income_type: &str) -> CashTransactionKind {
+        match income_type {
+            "DIV" => CashTransactionKind::Dividend,
+            "INTEREST" => CashTransactionKind::Interest,
+            "CGLONG" | "CGSHORT" => CashTransactionKind::CapitalGainDistribution,
+            _ => CashTransactionKind::Income,
+        }
+    }
+
+    fn parse_transaction(
+        transaction: &OfxElement,
+        currency: &str,
+        ticker: &dyn Fn(&OfxElement) -> String,
+        activities: &mut Vec<Activity>,
+    ) -> Result<()> {
+        let name = transaction.name.as_str();
+
+        if name == "INVBANKTRAN" {
+            let Some(bank) = transaction.child("STMTTRN") else {
+                return Ok(());
+            };
+            let amount = parse_util::parse_amount_or_zero(bank.text(&["TRNAMT"]))?;
+            let kind = match bank.text(&["TRNTYPE"]) {
+                "INT" => CashTransactionKind::Interest,
+                "DIV" => CashTransactionKind::Dividend,
+                "FEE" | "SRVCHG" => CashTransactionKind::Fee,
+                "XFER" => CashTransactionKind::Transfer,
+                _ if amount >= 0.0 => CashTransactionKind::Deposit,
+                _ => CashTransactionKind::Withdrawal,
+            };
+            let description = [bank.text(&["NAME"]), bank.text(&["MEMO"])]
+                .into_iter()
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<&str>>()
+                .join(" ");
+            activities.push(Activity::Cash(CashActivity {
+                brokerage_transaction_id: bank.text(&["FITID"]).to_owned(),
+                kind,
+                amount,
+                currency: currency.to_owned(),
+                timestamp_ms: parse_ofx_timestamp_ms(bank.text(&["DTPOSTED"]))?,
+                ticker: None,
+                listing_exchange: None,
+                description,
+            }));
+            return Ok(());
+        }
+
+        // Buys and sells wrap their details in an INVBUY or INVSELL aggregate.
+        let (details, side) = if let Some(buy) = transaction.child("INVBUY") {
+            (buy, Some(TradeSide::Buy))
+        } else if let Some(sell) = transaction.child("INVSELL") {
+            (sell, Some(TradeSide::Sell))
+        } else {
+            (transaction, None)
+        };
+        let Some(invtran) = details.child("INVTRAN") else {
+            debug!("skipping OFX transaction '{}' without INVTRAN", name);
+            return Ok(());
+        };
+        let fitid = invtran.text(&["FITID"]);
+        let timestamp_ms = parse_ofx_timestamp_ms(invtran.text(&["DTTRADE"]))?;
+        let memo = invtran.text(&["MEMO"]);
+        let total = parse_util::parse_amount_or_zero(details.text(&["TOTAL"]))?;
+
+        let trade = |side: TradeSide| -> Result<TradeActivity> {
+            Ok(TradeActivity {
+                brokerage_execution_id: fitid.to_owned(),
+                ticker: ticker(details),
+                listing_exchange: None,
+                side,
+                quantity: parse_util::parse_amount_or_zero(details.text(&["UNITS"]))?.abs(),
+                price: parse_util::parse_amount_or_zero(details.text(&["UNITPRICE"]))?,
+                commission: (parse_util::parse_amount_or_zero(details.text(&["COMMISSION"]))?
+                    + parse_util::parse_amount_or_zero(details.text(&["FEES"]))?)
+                .abs(),
+                execution_timestamp_ms: timestamp_ms,
+            })
+        };
+
+        match (name, side) {
+            (_, Some(side)) => activities.push(Activity::Trade(trade(side)?)),
+            ("INCOME", _) => activities.push(Activity::Cash(CashActivity {
+                brokerage_transaction_id: fitid.to_owned(),
+                kind: Self::income_kind(details.text(&["INCOMETYPE"])),
+                amount: total,
+                currency: currency.to_owned(),
+                timestamp_ms,
+                ticker: Some(ticker(details)),
+                listing_exchange: None,
+                description: memo.to_owned(),
+            })),
+            ("REINVEST", _) => activities.extend(Activity::reinvestment(
+                trade(TradeSide::Buy)?,
+                Self::income_kind(details.text(&["INCOMETYPE"])),
+                total,
+                currency,
+                memo,
+            )),
+            ("INVEXPENSE", _) => activities.push(Activity::Cash(CashActivity {
+                brokerage_transaction_id: fitid.to_owned(),
+                kind: CashTransactionKind::Fee,
+                amount: -total.abs(),
+                currency: currency.to_owned(),
+                timestamp_ms,
+                ticker: Some(ticker(details)),
+                listing_exchange: None,
+                description: memo.to_owned(),
+            })),
+            _ => debug!("skipping unsupported OFX transaction '{}'", name),
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StatementImporter for OfxImporter {
+    fn importer_name(&self) -> &'static str {
+        "ofx"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("ofx") || ext.eq_ignore_ascii_case("qfx"))
+        {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        // OFX 1.x starts with an `OFXHEADER:100` header block, OFX 2.x with an `<?OFX ...?>`
+        // processing instruction.
+        let preamble = &content[..content.find("<OFX>").unwrap_or(content.len())];
+        if preamble.contains("OFXHEADER") || preamble.contains("<?OFX") {
+            ContentMatch::definite("OFX header")
+        } else {
+            ContentMatch::no("no OFX header")
+        }
+    }
+
+    fn statement_date(&self, content: &str) -> Option<NaiveDate> {
+        let ofx = OfxElement::parse(content).ok()?;
+        let end = ofx.descendants("DTEND").first()?.text.clone()?;
+        NaiveDate::parse_from_str(end.get(..8)?, "%Y%m%d").ok()
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing OFX statement with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let statement = self.parse(content)?;
+
+        // Held securities are added even if the statement has no trades for them.
+        let held_tickers = statement
+            .positions
+            .iter()
+            .map(|position| position.ticker.as_str())
+            .collect::<Vec<&str>>();
+        writers::write_statement_activities(
+            db,
+            session,
+            &statement.brokerage_id,
+            &statement.accounts,
+            &held_tickers,
+            write_batch_size,
+        )
+        .await
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::{Result, anyhow};
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
+
+/// An element of an OFX document.
+///
+/// OFX 1.x is SGML where leaf elements usually have no end tag, while OFX 2.x is XML. Both are
+/// read into the same tree: elements with text content are leaves, all others are aggregates.
+#[derive(Debug, PartialEq)]
+pub struct OfxElement {
+    pub name: String,
+    pub text: Option<String>,
+    pub children: Vec<OfxElement>,
+}
+
+impl OfxElement {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            text: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Parses the document body, starting at the `<OFX>` element and ignoring any header.
+    pub fn parse(content: &str) -> Result<Self> {
+        let start = content
+            .find("<OFX>")
+            .ok_or_else(|| anyhow!("OFX document has no <OFX> element"))?;
+
+        let mut stack = vec![OfxElement::new("")];
+        let mut rest = &content[start..];
+
+        while let Some(open) = rest.find('<') {
+            let close = rest[open..]
+                .find('>')
+                .map(|i| open + i)
+                .ok_or_else(|| anyhow!("unterminated OFX tag"))?;
+            let tag = rest[open + 1..close].trim();
+            rest = &rest[close + 1..];
+
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+
+            if let Some(name) = tag.strip_prefix('/') {
+                // An end tag closes its element along with any unclosed elements inside it.
+                let Some(depth) = stack.iter().rposition(|e| e.name == name) else {
+                    continue;
+                };
+                while stack.len() > depth {
+                    let element = stack.pop().unwrap();
+                    stack.last_mut().unwrap().children.push(element);
+                }
+                continue;
+            }
+
+            let (name, self_closing) = match tag.strip_suffix('/') {
+                Some(name) => (name.trim(), true),
+                None => (tag, false),
+            };
+            let text_end = rest.find('<').unwrap_or(rest.len());
+            let text = decode_entities(rest[..text_end].trim());
+            let mut element = OfxElement::new(name);
+
+            if self_closing {
+                stack.last_mut().unwrap().children.push(element);
+            } else if !text.is_empty() {
+                element.text = Some(text);
+                rest = &rest[text_end..];
+                let end_tag = format!("</{}>", name);
+                if rest.starts_with(&end_tag) {
+                    rest = &rest[end_tag.len()..];
+                }
+                stack.last_mut().unwrap().children.push(element);
+            } else {
+                stack.push(element);
+            }
+        }
+
+        while stack.len() > 1 {
+            let element = stack.pop().unwrap();
+            stack.last_mut().unwrap().children.push(element);
+        }
+
+        stack
+            .pop()
+            .unwrap()
+            .children
+            .into_iter()
+            .find(|e| e.name == "OFX")
+            .ok_or_else(|| anyhow!("OFX document has no <OFX> element"))
+    }
+
+    /// Returns the first child with the given name.
+    pub fn child(&self, name: &str) -> Option<&OfxElement> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// Follows a path of child names.
+    pub fn path(&self, names: &[&str]) -> Option<&OfxElement> {
+        names
+            .iter()
+            .try_fold(self, |element, name| element.child(name))
+    }
+
+    /// Returns the text of the element at the given path, or `""` if there is none.
+    pub fn text(&self, names: &[&str]) -> &str {
+        self.path(names)
+            .and_then(|e| e.text.as_deref())
+            .unwrap_or("")
+    }
+
+    /// Returns all descendants with the given name, in document order.
+    pub fn descendants<'a>(&'a self, name: &str) -> Vec<&'a OfxElement> {
+        let mut found = Vec::new();
+        for child in &self.children {
+            if child.name == name {
+                found.push(child);
+            }
+            found.extend(child.descendants(name));
+        }
+        found
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Parses an OFX date-time such as `20250415`, `20250415093000.000` or
+/// `20250415093000.000[-4:EDT]` into milliseconds since the epoch.
+///
+/// Per the OFX specification, times without a timezone are in GMT.
+pub fn parse_ofx_timestamp_ms(value: &str) -> Result<i64> {
+    let invalid = || anyhow!("invalid OFX date-time '{}'", value);
+
+    let (date_time, zone) = match value.split_once('[') {
+        Some((date_time, zone)) => (date_time, Some(zone.trim_end_matches(']'))),
+        None => (value, None),
+    };
+    let digits = date_time.split('.').next().unwrap_or("");
+    if digits.len() < 8 || !digits.is_ascii() {
+        return Err(invalid());
+    }
+
+    let date = NaiveDate::parse_from_str(&digits[..8], "%Y%m%d").map_err(|_| invalid())?;
+    let time = format!("{:0<6}", &digits[8..]);
+    let naive = NaiveDateTime::parse_from_str(
+        &format!("{}{}", date.format("%Y%m%d"), &time[..6]),
+        "%Y%m%d%H%M%S",
+    )
+    .map_err(|_| invalid())?;
+
+    let offset_hours = match zone {
+        Some(zone) => zone
+            .split(':')
+            .next()
+            .unwrap_or("")
+            .parse::<f64>()
+            .map_err(|_| invalid())?,
+        None => 0.0,
+    };
+    let offset =
+        FixedOffset::east_opt((offset_hours * 3600.0).round() as i32).ok_or_else(invalid)?;
+
+    offset
+        .from_local_datetime(&naive)
+        .single()
+        .map(|dt| dt.timestamp_millis())
+        .ok_or_else(invalid)
+}
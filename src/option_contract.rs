@@ -0,0 +1,35 @@
+use chrono::NaiveDate;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionRight {
+    Call,
+    Put,
+}
+
+/// An equity option contract.
+///
+/// `brokerage-db` only stores stock securities, so option trades are recorded against a stock
+/// security whose ticker is the contract's OCC symbol.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptionContract {
+    pub underlying: String,
+    pub expiration: NaiveDate,
+    pub strike: f64,
+    pub right: OptionRight,
+}
+
+impl OptionContract {
+    /// Returns the 21-character OCC option symbol, e.g. `AAPL  250516C00210000`.
+    pub fn occ_symbol(&self) -> String {
+        format!(
+            "{:<6}{}{}{:08}",
+            self.underlying,
+            self.expiration.format("%y%m%d"),
+            match self.right {
+                OptionRight::Call => 'C',
+                OptionRight::Put => 'P',
+            },
+            (self.strike * 1000.0).round() as u64
+        )
+    }
+}
@@ -0,0 +1,291 @@
+use anyhow::{Result, anyhow};
+use chrono::{NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use csv::StringRecord;
+use std::collections::HashMap;
+
+pub const NEW_YORK_TZ: Tz = chrono_tz::America::New_York;
+
+/// Parses a statement amount such as `$1,234.56`, `-$5.00` or `($12.00)`.
+///
+/// Returns `None` for blank amounts.
+pub fn parse_amount(value: &str) -> Result<Option<f64>> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() || trimmed == "--" {
+        return Ok(None);
+    }
+
+    let (negative, unwrapped) = match trimmed.strip_prefix('(') {
+        Some(rest) => (true, rest.strip_suffix(')').unwrap_or(rest)),
+        None => (false, trimmed),
+    };
+    let cleaned = unwrapped
+        .chars()
+        .filter(|c| !matches!(c, '$' | ',' | ' '))
+        .collect::<String>();
+
+    let amount = cleaned
+        .parse::<f64>()
+        .map_err(|e| anyhow!("invalid amount '{}': {}", value, e))?;
+    Ok(Some(if negative { -amount } else { amount }))
+}
+
+/// Parses an amount with the given decimal separator, such as `1.234,56` or `1'234,56` with
+/// `,`, ignoring the other separators used to group thousands.
+///
+/// Returns `None` for blank amounts.
+pub fn parse_amount_with_separator(value: &str, decimal_separator: char) -> Result<Option<f64>> {
+    if decimal_separator == '.' {
+        return parse_amount(value);
+    }
+    let normalized = value
+        .chars()
+        .filter(|c| !matches!(c, '.' | ' ' | '\''))
+        .map(|c| if c == decimal_separator { '.' } else { c })
+        .collect::<String>();
+    parse_amount(&normalized).map_err(|_| anyhow!("invalid amount '{}'", value))
+}
+
+/// Parses an amount that may use a comma as its decimal separator, such as `1.234,56` or
+/// `-12,5`, as well as the `1,234.56` form, for exports whose locale isn't known.
+///
+/// A lone comma followed by exactly three digits, as in `1,234`, groups thousands.
+///
+/// Returns `None` for blank amounts.
+pub fn parse_localized_amount(value: &str) -> Result<Option<f64>> {
+    let trimmed = value.trim();
+    let decimal_comma = match (trimmed.rfind(','), trimmed.rfind('.')) {
+        (Some(comma), Some(dot)) => comma > dot,
+        (Some(comma), None) => {
+            trimmed[comma + 1..]
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .count()
+                != 3
+        }
+        _ => false,
+    };
+    if decimal_comma {
+        parse_amount_with_separator(trimmed, ',')
+    } else {
+        parse_amount(trimmed)
+    }
+}
+
+/// Parses an amount, treating a blank value as zero.
+pub fn parse_amount_or_zero(value: &str) -> Result<f64> {
+    Ok(parse_amount(value)?.unwrap_or(0.0))
+}
+
+/// Parses a `MM/DD/YYYY` date.
+pub fn parse_us_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%m/%d/%Y")
+        .map_err(|e| anyhow!("invalid date '{}': {}", value, e))
+}
+
+/// Converts a local date-time in the given timezone to milliseconds since the epoch.
+pub fn local_timestamp_ms(date_time: NaiveDateTime, tz: Tz) -> Result<i64> {
+    tz.from_local_datetime(&date_time)
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+        .ok_or_else(|| anyhow!("invalid local time {} in {}", date_time, tz))
+}
+
+/// Converts a date to the millisecond timestamp of its start in the given timezone.
+pub fn date_timestamp_ms(date: NaiveDate, tz: Tz) -> Result<i64> {
+    local_timestamp_ms(date.and_hms_opt(0, 0, 0).unwrap(), tz)
+}
+
+/// Builds stable ids for statement rows that don't carry a brokerage-assigned id.
+///
+/// The id is derived from the row content, so re-importing the same statement yields the same
+/// ids. Identical rows within one statement are disambiguated by their occurrence count.
+#[derive(Default)]
+pub struct SyntheticIdGenerator {
+    occurrences: HashMap<u64, u32>,
+}
+
+impl SyntheticIdGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn id(&mut self, prefix: &str, fields: &[&str]) -> String {
+        let hash = fnv1a(fields);
+        let occurrence = self.occurrences.entry(hash).or_insert(0);
+        *occurrence += 1;
+        format!("{}:{:016x}:{}", prefix, hash, occurrence)
+    }
+}
+
+fn fnv1a(fields: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for field in fields {
+        for byte in field.bytes().chain(std::iter::once(0x1f)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Returns the byte offset of the first line whose unquoted, comma-separated fields start with
+/// the given header fields.
+pub fn find_csv_header(content: &str, header: &[&str]) -> Option<usize> {
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let fields = line
+            .trim_end()
+            .split(',')
+            .map(|f| f.trim().trim_matches('"').trim())
+            .collect::<Vec<&str>>();
+        if fields.len() >= header.len() && fields.iter().zip(header).all(|(f, h)| f == h) {
+            return Some(offset);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// A CSV table whose fields are looked up by header name.
+pub struct CsvTable {
+    columns: HashMap<String, usize>,
+    records: Vec<StringRecord>,
+}
+
+impl CsvTable {
+    /// Parses CSV text whose first line is the header row.
+    ///
+    /// Rows may have fewer or more fields than the header, which covers the totals and
+    /// disclaimer lines that brokers append to their exports.
+    pub fn parse(text: &str) -> Result<Self> {
+        Self::parse_with_delimiter(text, b',')
+    }
+
+    pub fn parse_with_delimiter(text: &str, delimiter: u8) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes());
+
+        let columns = reader
+            .headers()?
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.to_owned(), i))
+            .collect::<HashMap<String, usize>>();
+        let records = reader
+            .records()
+            .collect::<std::result::Result<Vec<StringRecord>, csv::Error>>()?;
+
+        Ok(Self { columns, records })
+    }
+
+    pub fn has_column(&self, name: &str) -> bool {
+        self.columns.contains_key(name)
+    }
+
+    /// Returns an error naming the first of the given columns that is missing.
+    pub fn require_columns(&self, names: &[&str]) -> Result<()> {
+        match names.iter().find(|name| !self.has_column(name)) {
+            Some(name) => Err(anyhow!("CSV is missing the '{}' column", name)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = CsvRow<'_>> {
+        self.records.iter().map(|record| CsvRow {
+            table: self,
+            record,
+        })
+    }
+}
+
+pub struct CsvRow<'a> {
+    table: &'a CsvTable,
+    record: &'a StringRecord,
+}
+
+impl<'a> CsvRow<'a> {
+    /// Returns the named field, or an empty string if the row or table lacks it.
+    pub fn get(&self, column: &str) -> &'a str {
+        self.table
+            .columns
+            .get(column)
+            .and_then(|&i| self.record.get(i))
+            .unwrap_or("")
+    }
+
+    /// Returns the first of the named fields that the table has, for exports whose column names
+    /// vary between versions.
+    pub fn get_any(&self, columns: &[&str]) -> &'a str {
+        columns
+            .iter()
+            .find(|column| self.table.has_column(column))
+            .map(|column| self.get(column))
+            .unwrap_or("")
+    }
+
+    /// Returns the field following the first of the named fields that the table has, for
+    /// exports that put a value's currency or amount in an unnamed column.
+    pub fn get_any_after(&self, columns: &[&str]) -> &'a str {
+        columns
+            .iter()
+            .find_map(|column| self.table.columns.get(*column))
+            .and_then(|&i| self.record.get(i + 1))
+            .unwrap_or("")
+    }
+
+    /// Returns all fields of the row, for building synthetic ids.
+    pub fn fields(&self) -> Vec<&'a str> {
+        self.record.iter().collect()
+    }
+}
+
+/// Whether the value is an ISIN with a valid check digit, e.g. `US0378331005`.
+pub fn is_isin(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    if bytes.len() != 12
+        || !bytes[..2].iter().all(u8::is_ascii_uppercase)
+        || !bytes[2..11]
+            .iter()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+        || !bytes[11].is_ascii_digit()
+    {
+        return false;
+    }
+
+    // Letters expand to two digits (A = 10), then the Luhn check applies to the digits.
+    let digits = bytes
+        .iter()
+        .flat_map(|b| match b {
+            b'0'..=b'9' => vec![b - b'0'],
+            _ => {
+                let value = b - b'A' + 10;
+                vec![value / 10, value % 10]
+            }
+        })
+        .collect::<Vec<u8>>();
+    let sum = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| {
+            let digit = digit as u32;
+            if i % 2 == 1 {
+                let doubled = digit * 2;
+                doubled / 10 + doubled % 10
+            } else {
+                digit
+            }
+        })
+        .sum::<u32>();
+    sum % 10 == 0
+}
+
+/// Returns the first ISIN found among the words of free text, such as remittance information.
+pub fn find_isin(text: &str) -> Option<&str> {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .find(|word| is_isin(word))
+}
@@ -0,0 +1,173 @@
+pub mod pdf_text;
+pub mod schwab_layout;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{borrow::Cow, path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::AccountActivities, content_match::ContentMatch, path_match::PathMatch,
+    statement_importer::StatementImporter, writers,
+};
+use schwab_layout::SchwabPdfLayout;
+
+/// A position from the position tables of a PDF statement.
+#[derive(Debug, PartialEq)]
+pub struct PdfPosition {
+    pub account_id: String,
+    pub ticker: String,
+    pub quantity: f64,
+    pub price: f64,
+}
+
+/// The content parsed from the text of a PDF statement.
+#[derive(Debug, PartialEq)]
+pub struct PdfStatement {
+    pub positions: Vec<PdfPosition>,
+    pub accounts: Vec<AccountActivities>,
+}
+
+/// The layout rules for one broker's PDF statements.
+///
+/// A layout recognizes its statements by the broker's header text and parses the extracted
+/// text's trade, dividend and position tables.
+pub trait PdfStatementLayout: Send + Sync {
+    /// Returns the name of the layout, used in log messages.
+    fn layout_name(&self) -> &'static str;
+
+    /// Returns the brokerage id the statement's accounts are recorded under.
+    fn brokerage_id(&self) -> &'static str;
+
+    /// Returns whether the extracted text is a statement in this layout.
+    fn matches(&self, text: &str) -> bool;
+
+    /// Parses the extracted text of a statement in this layout.
+    fn parse(&self, text: &str) -> Result<PdfStatement>;
+}
+
+/// Imports PDF statements by extracting their text and parsing it with the first registered
+/// layout that recognizes it.
+///
+/// The importer starts with the built-in layouts; others are added with `layout`.
+/// Password-protected statements are opened with the passwords configured with `password`,
+/// tried in order.
+///
+/// PDF statements carry no transaction ids, so activities get synthetic ids. Importing the same
+/// period from both a PDF and another export of the same broker records its activities twice.
+pub struct PdfStatementImporter {
+    layouts: Vec<Box<dyn PdfStatementLayout>>,
+    passwords: Vec<String>,
+}
+
+impl Default for PdfStatementImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PdfStatementImporter {
+    pub fn new() -> Self {
+        Self {
+            layouts: vec![Box::new(SchwabPdfLayout::new())],
+            passwords: Vec::new(),
+        }
+    }
+
+    /// Adds a layout, tried after the layouts already registered.
+    pub fn layout(mut self, layout: Box<dyn PdfStatementLayout>) -> Self {
+        self.layouts.push(layout);
+        self
+    }
+
+    /// Adds a password to try on password-protected statements.
+    pub fn password(mut self, password: &str) -> Self {
+        self.passwords.push(password.to_owned());
+        self
+    }
+
+    fn find_layout(&self, text: &str) -> Option<&dyn PdfStatementLayout> {
+        self.layouts
+            .iter()
+            .find(|layout| layout.matches(text))
+            .map(|layout| layout.as_ref())
+    }
+
+    /// Extracts the text of a PDF statement.
+    pub fn extract_text(&self, bytes: &[u8]) -> Result<String> {
+        pdf_text::extract_text(bytes, &self.passwords)
+    }
+
+    /// Parses the extracted text of a statement with the layout that recognizes it.
+    pub fn parse(&self, text: &str) -> Result<(&'static str, PdfStatement)> {
+        let layout = self
+            .find_layout(text)
+            .ok_or_else(|| anyhow!("no PDF statement layout recognizes the statement"))?;
+        debug!("parsing PDF statement with layout {}", layout.layout_name());
+        Ok((layout.brokerage_id(), layout.parse(text)?))
+    }
+}
+
+#[async_trait]
+impl StatementImporter for PdfStatementImporter {
+    fn importer_name(&self) -> &'static str {
+        "pdf-statement"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+        {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    fn decode_content<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>> {
+        self.extract_text(bytes).map(Cow::Owned)
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        match self.find_layout(content) {
+            Some(layout) => {
+                ContentMatch::definite(format!("{} PDF statement layout", layout.layout_name()))
+            }
+            None => ContentMatch::no("no known PDF statement layout"),
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing PDF statement with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let (brokerage_id, statement) = self.parse(content)?;
+        let held_tickers = statement
+            .positions
+            .iter()
+            .map(|position| position.ticker.as_str())
+            .collect::<Vec<&str>>();
+        writers::write_statement_activities(
+            db,
+            session,
+            brokerage_id,
+            &statement.accounts,
+            &held_tickers,
+            write_batch_size,
+        )
+        .await
+    }
+}
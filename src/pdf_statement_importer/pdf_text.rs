@@ -0,0 +1,77 @@
+use anyhow::{Result, anyhow};
+use pdf_extract::{Document, LoadOptions, PlainTextOutput};
+
+use crate::parse_util;
+
+fn document_text(document: &Document) -> Result<String> {
+    let mut text = String::new();
+    pdf_extract::output_doc(document, &mut PlainTextOutput::new(&mut text))
+        .map_err(|e| anyhow!("failed to extract PDF text: {}", e))?;
+    Ok(text)
+}
+
+/// Extracts the text of a PDF, trying each password in turn if the PDF is password protected.
+pub fn extract_text(bytes: &[u8], passwords: &[String]) -> Result<String> {
+    let document = Document::load_mem(bytes).map_err(|e| anyhow!("failed to load PDF: {}", e))?;
+    if !document.is_encrypted() {
+        return document_text(&document);
+    }
+
+    passwords
+        .iter()
+        .find_map(|password| {
+            Document::load_mem_with_options(bytes, LoadOptions::with_password(password)).ok()
+        })
+        .ok_or_else(|| anyhow!("PDF is password protected and no configured password opens it"))
+        .and_then(|document| document_text(&document))
+}
+
+/// Returns the trimmed, non-blank lines of extracted text.
+pub fn text_lines(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Returns the lines after the first line starting with `heading`, up to the next line starting
+/// with one of `ends`. Returns an empty slice if the heading isn't found.
+pub fn section<'a, 'b>(lines: &'b [&'a str], heading: &str, ends: &[&str]) -> &'b [&'a str] {
+    let Some(start) = lines.iter().position(|line| line.starts_with(heading)) else {
+        return &[];
+    };
+    let body = &lines[start + 1..];
+    let end = body
+        .iter()
+        .position(|line| ends.iter().any(|e| line.starts_with(e)))
+        .unwrap_or(body.len());
+    &body[..end]
+}
+
+/// Returns the word following the given sequence of words, e.g. the account number after
+/// `Account Number`.
+pub fn word_after<'a>(text: &'a str, label: &[&str]) -> Option<&'a str> {
+    let words = text.split_whitespace().collect::<Vec<&str>>();
+    words
+        .windows(label.len() + 1)
+        .find(|window| window[..label.len()] == *label)
+        .map(|window| window[label.len()])
+}
+
+/// Splits a table row into its leading words and the amounts in its trailing columns.
+///
+/// Extracted text separates columns by single spaces, so the numeric columns are read from the
+/// end of the row until a word that isn't an amount.
+pub fn split_trailing_amounts(line: &str) -> (Vec<&str>, Vec<f64>) {
+    let mut words = line.split_whitespace().collect::<Vec<&str>>();
+    let mut amounts = Vec::new();
+    while let Some(amount) = words
+        .last()
+        .and_then(|word| parse_util::parse_amount(word).ok().flatten())
+    {
+        amounts.push(amount);
+        words.pop();
+    }
+    amounts.reverse();
+    (words, amounts)
+}
@@ -0,0 +1,191 @@
+use anyhow::{Result, anyhow};
+use brokerage_db::trade_execution::TradeSide;
+use chrono::NaiveDate;
+
+use super::{PdfPosition, PdfStatement, PdfStatementLayout, pdf_text};
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    parse_util::{self, NEW_YORK_TZ, SyntheticIdGenerator},
+    schwab_csv_importer::SCHWAB_BROKERAGE_ID,
+};
+
+const HEADER_TEXT: [&str; 2] = ["Schwab", "Statement Period"];
+
+/// Multi-word values of the transaction table's Action column.
+const ACTIONS: [&[&str]; 9] = [
+    &["Qualified", "Dividend"],
+    &["Non-Qualified", "Div"],
+    &["Cash", "Dividend"],
+    &["Credit", "Interest"],
+    &["Bank", "Interest"],
+    &["Funds", "Received"],
+    &["Funds", "Paid"],
+    &["Buy"],
+    &["Sell"],
+];
+
+/// The layout of Charles Schwab brokerage account statements.
+///
+/// Trades, dividends, interest, deposits and withdrawals come from the `Transaction Details`
+/// table, and positions from the `Positions - Equities` table. Transaction dates have no year,
+/// so they take the year of the statement period.
+#[derive(Default)]
+pub struct SchwabPdfLayout {}
+
+impl SchwabPdfLayout {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn statement_year(text: &str) -> Result<i32> {
+        text.split_whitespace()
+            .skip_while(|word| *word != "Period")
+            .take(12)
+            .find_map(|word| {
+                word.trim_end_matches(',')
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|year| *year > 1900)
+            })
+            .ok_or_else(|| anyhow!("Schwab PDF statement has no statement period year"))
+    }
+
+    fn timestamp_ms(date: &str, year: i32) -> Result<i64> {
+        let date = NaiveDate::parse_from_str(&format!("{}/{}", date, year), "%m/%d/%Y")
+            .map_err(|e| anyhow!("invalid Schwab PDF date '{}': {}", date, e))?;
+        parse_util::date_timestamp_ms(date, NEW_YORK_TZ)
+    }
+
+    fn strip_action<'a, 'b>(words: &'b [&'a str]) -> &'b [&'a str] {
+        ACTIONS
+            .iter()
+            .find(|action| words.starts_with(action))
+            .map_or(words, |action| &words[action.len()..])
+    }
+
+    fn parse_positions(lines: &[&str], account_id: &str) -> Vec<PdfPosition> {
+        pdf_text::section(lines, "Positions - Equities", &["Total Equities"])
+            .iter()
+            .filter_map(|line| {
+                let (words, amounts) = pdf_text::split_trailing_amounts(line);
+                let [quantity, price, _market_value] = amounts[..] else {
+                    return None;
+                };
+                Some(PdfPosition {
+                    account_id: account_id.to_owned(),
+                    ticker: words.first()?.to_string(),
+                    quantity,
+                    price,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_transaction(
+        words: &[&str],
+        amounts: &[f64],
+        id: String,
+        year: i32,
+    ) -> Result<Option<Activity>> {
+        let [date, category, rest @ ..] = words else {
+            return Ok(None);
+        };
+        let timestamp_ms = Self::timestamp_ms(date, year)?;
+        let rest = Self::strip_action(rest);
+        let amount = *amounts
+            .last()
+            .ok_or_else(|| anyhow!("Schwab PDF transaction on {} has no amount", date))?;
+
+        let side = match *category {
+            "Purchase" => Some(TradeSide::Buy),
+            "Sale" => Some(TradeSide::Sell),
+            _ => None,
+        };
+        if let Some(side) = side {
+            let [quantity, price, charges, _amount] = amounts[..] else {
+                return Err(anyhow!("Schwab PDF trade on {} is missing columns", date));
+            };
+            let ticker = rest
+                .first()
+                .ok_or_else(|| anyhow!("Schwab PDF trade on {} has no symbol", date))?;
+            return Ok(Some(Activity::Trade(TradeActivity {
+                brokerage_execution_id: id,
+                ticker: ticker.to_string(),
+                listing_exchange: None,
+                side,
+                quantity: quantity.abs(),
+                price,
+                commission: charges.abs(),
+                execution_timestamp_ms: timestamp_ms,
+            })));
+        }
+
+        let (kind, ticker, description) = match *category {
+            "Dividend" => (
+                CashTransactionKind::Dividend,
+                rest.first().map(|ticker| ticker.to_string()),
+                rest.get(1..).unwrap_or_default(),
+            ),
+            "Interest" => (CashTransactionKind::Interest, None, rest),
+            "Deposit" => (CashTransactionKind::Deposit, None, rest),
+            "Withdrawal" => (CashTransactionKind::Withdrawal, None, rest),
+            _ => return Ok(None),
+        };
+        Ok(Some(Activity::Cash(CashActivity {
+            brokerage_transaction_id: id,
+            kind,
+            amount,
+            currency: "USD".to_owned(),
+            timestamp_ms,
+            ticker,
+            listing_exchange: None,
+            description: description.join(" "),
+        })))
+    }
+}
+
+impl PdfStatementLayout for SchwabPdfLayout {
+    fn layout_name(&self) -> &'static str {
+        "schwab"
+    }
+
+    fn brokerage_id(&self) -> &'static str {
+        SCHWAB_BROKERAGE_ID
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        HEADER_TEXT.iter().all(|header| text.contains(header))
+            && pdf_text::word_after(text, &["Account", "Number"]).is_some()
+    }
+
+    fn parse(&self, text: &str) -> Result<PdfStatement> {
+        let account_id = pdf_text::word_after(text, &["Account", "Number"])
+            .ok_or_else(|| anyhow!("Schwab PDF statement has no account number"))?;
+        let year = Self::statement_year(text)?;
+        let lines = pdf_text::text_lines(text);
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut activities = Vec::new();
+        for line in pdf_text::section(&lines, "Transaction Details", &["Total Transactions"]) {
+            let (words, amounts) = pdf_text::split_trailing_amounts(line);
+            let is_row = words.first().is_some_and(|word| {
+                NaiveDate::parse_from_str(&format!("{}/2000", word), "%m/%d/%Y").is_ok()
+            });
+            if !is_row {
+                continue;
+            }
+
+            let id = ids.id(SCHWAB_BROKERAGE_ID, &[account_id, line]);
+            activities.extend(Self::parse_transaction(&words, &amounts, id, year)?);
+        }
+
+        Ok(PdfStatement {
+            positions: Self::parse_positions(&lines, account_id),
+            accounts: vec![AccountActivities {
+                account_id: account_id.to_owned(),
+                activities,
+            }],
+        })
+    }
+}
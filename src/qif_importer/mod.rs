@@ -0,0 +1,341 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::NaiveDate;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{collections::HashMap, path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    parse_util::{self, NEW_YORK_TZ, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const QIF_BROKERAGE_ID: &str = "qif";
+
+/// The order of the day and month in QIF dates, which the format leaves unspecified.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum QifDateFormat {
+    /// `MM/DD'YY`, as written by US versions of Quicken and Microsoft Money.
+    #[default]
+    MonthDayYear,
+    /// `DD/MM'YY`, as written by most non-US versions.
+    DayMonthYear,
+}
+
+/// Parses a QIF date such as `1/15'25`, `01/15/2025`, `1/15/95` or ` 1/15' 5`.
+///
+/// Following Quicken, two-digit years after an apostrophe are in the 2000s and two-digit years
+/// after a slash are in the 1900s.
+pub fn parse_qif_date(value: &str, format: QifDateFormat) -> Result<NaiveDate> {
+    let invalid = || anyhow!("invalid QIF date '{}'", value);
+
+    let parts = value
+        .split(['/', '\'', '-', '.'])
+        .map(|part| part.trim().parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<u32>>>()?;
+    let [first, second, year] = parts[..] else {
+        return Err(invalid());
+    };
+
+    let year = match year {
+        0..=99 if value.contains('\'') => 2000 + year,
+        0..=99 => 1900 + year,
+        _ => year,
+    };
+    let (month, day) = match format {
+        QifDateFormat::MonthDayYear => (first, second),
+        QifDateFormat::DayMonthYear => (second, first),
+    };
+
+    NaiveDate::from_ymd_opt(year as i32, month, day).ok_or_else(invalid)
+}
+
+/// A `^`-terminated QIF record, as a list of field codes and values.
+struct QifRecord<'a> {
+    fields: Vec<(char, &'a str)>,
+}
+
+impl<'a> QifRecord<'a> {
+    fn get(&self, code: char) -> &'a str {
+        self.fields
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, value)| *value)
+            .unwrap_or("")
+    }
+
+    /// The transaction total, written as `T` by Quicken and as `U` by some other programs.
+    fn total(&self) -> &'a str {
+        match self.get('T') {
+            "" => self.get('U'),
+            total => total,
+        }
+    }
+
+    fn values(&self) -> Vec<&'a str> {
+        self.fields.iter().map(|(_, value)| *value).collect()
+    }
+}
+
+/// Imports Quicken and Microsoft Money QIF investment account exports (`!Type:Invst`).
+///
+/// QIF has no account ids, so activities are recorded against the account name from the
+/// preceding `!Account` header, or failing that the name configured with `account_id`.
+/// Transactions name their security, which is recorded by the symbol its `!Type:Security` record
+/// gives, or by its name if it has no symbol.
+/// Dates are read with the configured `QifDateFormat`, which defaults to US month-first dates.
+pub struct QifImporter {
+    brokerage_id: String,
+    account_id: Option<String>,
+    date_format: QifDateFormat,
+}
+
+impl Default for QifImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QifImporter {
+    pub fn new() -> Self {
+        Self {
+            brokerage_id: QIF_BROKERAGE_ID.to_owned(),
+            account_id: None,
+            date_format: QifDateFormat::default(),
+        }
+    }
+
+    /// Sets the brokerage id the accounts are recorded under.
+    pub fn brokerage_id(mut self, brokerage_id: &str) -> Self {
+        self.brokerage_id = brokerage_id.to_owned();
+        self
+    }
+
+    /// Sets the account name used for transactions without a preceding `!Account` header.
+    pub fn account_id(mut self, account_id: &str) -> Self {
+        self.account_id = Some(account_id.to_owned());
+        self
+    }
+
+    pub fn date_format(mut self, date_format: QifDateFormat) -> Self {
+        self.date_format = date_format;
+        self
+    }
+
+    /// Splits the QIF export into its records, each with the section header it is under.
+    fn records(content: &str) -> Vec<(&str, QifRecord<'_>)> {
+        let mut records = Vec::new();
+        let mut section = "";
+        let mut fields = Vec::new();
+
+        for line in content.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('!') {
+                section = header.trim();
+                continue;
+            }
+
+            if line.starts_with('^') {
+                let record = QifRecord {
+                    fields: std::mem::take(&mut fields),
+                };
+                records.push((section, record));
+                continue;
+            }
+
+            let mut chars = line.chars();
+            if let Some(code) = chars.next() {
+                fields.push((code, chars.as_str().trim()));
+            }
+        }
+
+        records
+    }
+
+    /// Parses the QIF export into account activities, one entry per account.
+    pub fn parse(&self, content: &str) -> Result<Vec<AccountActivities>> {
+        let records = Self::records(content);
+
+        // Security records can follow the transactions that name them, so they are read first.
+        let symbols = records
+            .iter()
+            .filter(|(section, _)| section.eq_ignore_ascii_case("Type:Security"))
+            .filter(|(_, record)| !record.get('S').is_empty())
+            .map(|(_, record)| (record.get('N'), record.get('S')))
+            .collect::<HashMap<&str, &str>>();
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut accounts = Vec::<AccountActivities>::new();
+        let mut account_name = self.account_id.clone();
+
+        for (section, record) in &records {
+            if section.eq_ignore_ascii_case("Account") {
+                account_name = Some(record.get('N').to_owned());
+            } else if section.eq_ignore_ascii_case("Type:Invst") {
+                let account_id = account_name.clone().ok_or_else(|| {
+                    anyhow!("QIF transactions have no !Account header and no account is set")
+                })?;
+                let id = ids.id(&self.brokerage_id, &record.values());
+                let activities = self.parse_record(record, &id, &symbols)?;
+
+                match accounts.iter_mut().find(|a| a.account_id == account_id) {
+                    Some(account) => account.activities.extend(activities),
+                    None => accounts.push(AccountActivities {
+                        account_id,
+                        activities,
+                    }),
+                }
+            }
+        }
+
+        Ok(accounts)
+    }
+
+    fn parse_record(
+        &self,
+        record: &QifRecord,
+        id: &str,
+        symbols: &HashMap<&str, &str>,
+    ) -> Result<Vec<Activity>> {
+        let timestamp_ms = parse_util::date_timestamp_ms(
+            parse_qif_date(record.get('D'), self.date_format)?,
+            NEW_YORK_TZ,
+        )?;
+        let action = record.get('N');
+        let security = symbols
+            .get(record.get('Y'))
+            .copied()
+            .unwrap_or(record.get('Y'));
+        let quantity = parse_util::parse_amount_or_zero(record.get('Q'))?.abs();
+        let amount = parse_util::parse_amount_or_zero(record.total())?;
+        let price = match parse_util::parse_amount(record.get('I'))? {
+            Some(price) => price,
+            None if quantity != 0.0 => amount.abs() / quantity,
+            None => 0.0,
+        };
+
+        let trade = |side: TradeSide| -> Result<TradeActivity> {
+            Ok(TradeActivity {
+                brokerage_execution_id: id.to_owned(),
+                ticker: security.to_owned(),
+                listing_exchange: None,
+                side,
+                quantity,
+                price,
+                commission: parse_util::parse_amount_or_zero(record.get('O'))?.abs(),
+                execution_timestamp_ms: timestamp_ms,
+            })
+        };
+        let cash = |kind: CashTransactionKind, amount: f64| {
+            Activity::Cash(CashActivity {
+                brokerage_transaction_id: id.to_owned(),
+                kind,
+                amount,
+                currency: "USD".to_owned(),
+                timestamp_ms,
+                ticker: (!security.is_empty()).then(|| security.to_owned()),
+                listing_exchange: None,
+                description: record.get('M').to_owned(),
+            })
+        };
+        let income_kind = |action: &str| match action {
+            "Div" | "DivX" | "ReinvDiv" => CashTransactionKind::Dividend,
+            "IntInc" | "IntIncX" | "ReinvInt" => CashTransactionKind::Interest,
+            _ => CashTransactionKind::CapitalGainDistribution,
+        };
+
+        let activities = match action {
+            "Buy" | "BuyX" | "ShrsIn" => vec![Activity::Trade(trade(TradeSide::Buy)?)],
+            "Sell" | "SellX" | "ShrsOut" => vec![Activity::Trade(trade(TradeSide::Sell)?)],
+            "Div" | "DivX" | "IntInc" | "IntIncX" | "CGLong" | "CGLongX" | "CGShort"
+            | "CGShortX" => vec![cash(income_kind(action), amount)],
+            "ReinvDiv" | "ReinvInt" | "ReinvLg" | "ReinvSh" => Activity::reinvestment(
+                trade(TradeSide::Buy)?,
+                income_kind(action),
+                amount,
+                "USD",
+                record.get('M'),
+            )
+            .into(),
+            "XIn" | "ContribX" => vec![cash(CashTransactionKind::Deposit, amount)],
+            "XOut" | "WithdrwX" => vec![cash(CashTransactionKind::Withdrawal, -amount.abs())],
+            "MiscExp" | "MiscExpX" => {
+                vec![cash(CashTransactionKind::Fee, -amount.abs())]
+            }
+            _ => {
+                debug!("skipping unsupported QIF action '{}'", action);
+                vec![]
+            }
+        };
+
+        Ok(activities)
+    }
+}
+
+#[async_trait]
+impl StatementImporter for QifImporter {
+    fn importer_name(&self) -> &'static str {
+        "qif"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("qif"))
+        {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if content
+            .lines()
+            .any(|line| line.trim().eq_ignore_ascii_case("!Type:Invst"))
+        {
+            ContentMatch::likely("QIF !Type:Invst section")
+        } else {
+            ContentMatch::no("no QIF !Type:Invst section")
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing QIF export with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        for account_activities in &self.parse(content)? {
+            writers::write_account_activities(
+                db,
+                session.clone(),
+                &self.brokerage_id,
+                account_activities,
+                write_batch_size,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
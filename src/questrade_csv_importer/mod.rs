@@ -0,0 +1,299 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use calamine::{Data, Reader, Xlsx};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{borrow::Cow, io::Cursor, path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    account_type::AccountType,
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    parse_util::{self, CsvTable, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    text_encoding, writers,
+};
+
+pub const QUESTRADE_BROKERAGE_ID: &str = "questrade";
+
+const TORONTO_TZ: Tz = chrono_tz::America::Toronto;
+
+/// The date format of the CSV export, which Excel date cells are converted to.
+const DATE_FORMAT: &str = "%Y-%m-%d %I:%M:%S %p";
+
+/// The signature of zip files, such as `.xlsx` workbooks.
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+
+const QUESTRADE_HEADER: [&str; 5] = [
+    "Transaction Date",
+    "Settlement Date",
+    "Action",
+    "Symbol",
+    "Description",
+];
+
+/// Maps the activity export's account type, e.g. `Individual TFSA`, to an `AccountType`.
+pub fn parse_account_type(value: &str) -> Option<AccountType> {
+    let upper = value.to_uppercase();
+    let words = upper.split_whitespace().collect::<Vec<&str>>();
+    let has = |word: &str| words.contains(&word);
+
+    if has("TFSA") {
+        Some(AccountType::Tfsa)
+    } else if has("FHSA") {
+        Some(AccountType::Fhsa)
+    } else if has("RRSP") && has("SPOUSAL") {
+        Some(AccountType::SpousalRrsp)
+    } else if has("RRSP") {
+        Some(AccountType::Rrsp)
+    } else if has("RRIF") {
+        Some(AccountType::Rrif)
+    } else if has("RESP") {
+        Some(AccountType::Resp)
+    } else if has("LIRA") {
+        Some(AccountType::Lira)
+    } else if has("LIF") {
+        Some(AccountType::Lif)
+    } else if has("MARGIN") {
+        Some(AccountType::Margin)
+    } else if has("CASH") {
+        Some(AccountType::Cash)
+    } else {
+        None
+    }
+}
+
+/// The activities of one Questrade account, along with its registered account type.
+#[derive(Debug, PartialEq)]
+pub struct QuestradeAccount {
+    pub account_type: Option<AccountType>,
+    pub activities: AccountActivities,
+}
+
+/// Imports the Questrade activity export, saved as CSV or as an Excel `.xlsx` workbook.
+///
+/// The first worksheet of a workbook is converted to CSV text before it is matched and parsed,
+/// so both formats are read the same way.
+///
+/// The export can hold several accounts, identified by the `Account #` column. Each account's
+/// `Account Type`, such as TFSA or RRSP, is recorded on the brokerage account so registered
+/// accounts can be told apart downstream.
+///
+/// Dividend reinvestments (DRIP) are recorded as buys; the dividend itself has its own row.
+/// Journaling between listings, as done for Norbert's gambit, is recorded as a sell of the
+/// journaled shares from one listing and a buy into the other, at the journal price.
+pub struct QuestradeCsvImporter {}
+
+impl Default for QuestradeCsvImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QuestradeCsvImporter {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn parse_date(value: &str) -> Result<NaiveDate> {
+        NaiveDateTime::parse_from_str(value, DATE_FORMAT)
+            .map(|dt| dt.date())
+            .or_else(|_| DateTime::parse_from_rfc3339(value).map(|dt| dt.date_naive()))
+            .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d"))
+            .map_err(|e| anyhow!("invalid Questrade date '{}': {}", value, e))
+    }
+
+    /// Converts the first worksheet of an `.xlsx` activity export to CSV text.
+    pub fn xlsx_to_csv(bytes: &[u8]) -> Result<String> {
+        let mut workbook = Xlsx::new(Cursor::new(bytes))?;
+        let range = workbook
+            .worksheet_range_at(0)
+            .ok_or_else(|| anyhow!("Questrade Excel export has no worksheet"))??;
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for row in range.rows() {
+            writer.write_record(row.iter().map(|cell| {
+                match cell {
+                    Data::DateTime(date_time) => date_time
+                        .as_datetime()
+                        .map(|dt| dt.format(DATE_FORMAT).to_string())
+                        .unwrap_or_default(),
+                    Data::Error(_) => String::new(),
+                    cell => cell.to_string(),
+                }
+            }))?;
+        }
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+
+    /// Parses the activity export into activities, one entry per account.
+    pub fn parse(&self, content: &str) -> Result<Vec<QuestradeAccount>> {
+        let header_offset = parse_util::find_csv_header(content, &QUESTRADE_HEADER)
+            .ok_or_else(|| anyhow!("Questrade CSV header not found"))?;
+        let table = CsvTable::parse(&content[header_offset..])?;
+        table.require_columns(&["Account #", "Activity Type", "Net Amount", "Currency"])?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut accounts = Vec::<QuestradeAccount>::new();
+
+        for row in table.rows() {
+            let account_id = row.get("Account #");
+            let Ok(date) = Self::parse_date(row.get("Transaction Date")) else {
+                continue;
+            };
+            let timestamp_ms = parse_util::date_timestamp_ms(date, TORONTO_TZ)?;
+
+            let activity_type = row.get("Activity Type");
+            let action = row.get("Action");
+            let symbol = row.get("Symbol");
+            let quantity = parse_util::parse_amount_or_zero(row.get("Quantity"))?;
+            let net_amount = parse_util::parse_amount_or_zero(row.get("Net Amount"))?;
+            let id = ids.id(QUESTRADE_BROKERAGE_ID, &row.fields());
+
+            let is_journal = action == "BRW" && !symbol.is_empty() && quantity != 0.0;
+            let side = match (activity_type, action) {
+                ("Trades", "Buy") | ("Dividend reinvestment", _) => Some(TradeSide::Buy),
+                ("Trades", "Sell") => Some(TradeSide::Sell),
+                _ if is_journal && quantity > 0.0 => Some(TradeSide::Buy),
+                _ if is_journal => Some(TradeSide::Sell),
+                _ => None,
+            };
+
+            let activity = if let Some(side) = side {
+                Activity::Trade(TradeActivity {
+                    brokerage_execution_id: id,
+                    ticker: symbol.to_owned(),
+                    listing_exchange: None,
+                    side,
+                    quantity: quantity.abs(),
+                    price: parse_util::parse_amount_or_zero(row.get("Price"))?,
+                    commission: parse_util::parse_amount_or_zero(row.get("Commission"))?.abs(),
+                    execution_timestamp_ms: timestamp_ms,
+                })
+            } else {
+                let kind = match (activity_type, action) {
+                    (_, "NRT") => CashTransactionKind::Tax,
+                    ("Dividends", _) => CashTransactionKind::Dividend,
+                    ("Interest", _) => CashTransactionKind::Interest,
+                    ("Deposits", _) | (_, "CON") => CashTransactionKind::Deposit,
+                    ("Withdrawals", _) => CashTransactionKind::Withdrawal,
+                    ("Foreign Exchange", _) | (_, "FXT") => CashTransactionKind::ForeignExchange,
+                    ("Fees and rebates", _) => CashTransactionKind::Fee,
+                    ("Transfers", _) if net_amount != 0.0 => CashTransactionKind::Transfer,
+                    _ => {
+                        debug!(
+                            "skipping unsupported Questrade activity '{}' ({})",
+                            activity_type, action
+                        );
+                        continue;
+                    }
+                };
+
+                Activity::Cash(CashActivity {
+                    brokerage_transaction_id: id,
+                    kind,
+                    amount: net_amount,
+                    currency: row.get("Currency").to_owned(),
+                    timestamp_ms,
+                    ticker: (!symbol.is_empty()).then(|| symbol.to_owned()),
+                    listing_exchange: None,
+                    description: row.get("Description").to_owned(),
+                })
+            };
+
+            match accounts
+                .iter_mut()
+                .find(|a| a.activities.account_id == account_id)
+            {
+                Some(account) => account.activities.activities.push(activity),
+                None => accounts.push(QuestradeAccount {
+                    account_type: parse_account_type(row.get("Account Type")),
+                    activities: AccountActivities {
+                        account_id: account_id.to_owned(),
+                        activities: vec![activity],
+                    },
+                }),
+            }
+        }
+
+        Ok(accounts)
+    }
+}
+
+#[async_trait]
+impl StatementImporter for QuestradeCsvImporter {
+    fn importer_name(&self) -> &'static str {
+        "questrade-csv"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path
+            .extension()
+            .is_some_and(|ext| ext == "csv" || ext == "xlsx")
+        {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    fn decode_content<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>> {
+        if bytes.starts_with(ZIP_SIGNATURE) {
+            Self::xlsx_to_csv(bytes).map(Cow::Owned)
+        } else {
+            text_encoding::decode_text(bytes)
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if parse_util::find_csv_header(content, &QUESTRADE_HEADER).is_some() {
+            ContentMatch::likely("Questrade activities CSV header")
+        } else {
+            ContentMatch::no("no Questrade activities CSV header")
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing Questrade CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        for account in self.parse(content)? {
+            let brokerage_account_id = writers::write_account_activities(
+                db,
+                session.clone(),
+                QUESTRADE_BROKERAGE_ID,
+                &account.activities,
+                write_batch_size,
+            )
+            .await?;
+
+            if let Some(account_type) = account.account_type {
+                writers::maybe_add_account_type(
+                    db,
+                    session.clone(),
+                    brokerage_account_id,
+                    account_type,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    option_contract::{OptionContract, OptionRight},
+    parse_util::{self, CsvTable, NEW_YORK_TZ, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const ROBINHOOD_BROKERAGE_ID: &str = "robinhood";
+
+const ROBINHOOD_HEADER: [&str; 9] = [
+    "Activity Date",
+    "Process Date",
+    "Settle Date",
+    "Instrument",
+    "Description",
+    "Trans Code",
+    "Quantity",
+    "Price",
+    "Amount",
+];
+
+/// Parses an option contract from a description such as `AAPL 5/16/2025 Call $210.00` or
+/// `Option Expiration for TSLA 3/21/2025 Put $200.00`.
+pub fn parse_option_description(description: &str) -> Option<OptionContract> {
+    let tokens = description.split_whitespace().collect::<Vec<&str>>();
+    tokens.windows(4).find_map(|window| {
+        let right = match window[2] {
+            "Call" => OptionRight::Call,
+            "Put" => OptionRight::Put,
+            _ => return None,
+        };
+        Some(OptionContract {
+            underlying: window[0].to_owned(),
+            expiration: parse_util::parse_us_date(window[1]).ok()?,
+            strike: parse_util::parse_amount(window[3]).ok()??,
+            right,
+        })
+    })
+}
+
+/// Imports the Robinhood account activity report CSV.
+///
+/// The report doesn't name the account, so the account id is given when constructing the
+/// importer. The `Trans Code` column decides the kind of each row. Option rows are recorded
+/// against their OCC symbol, parsed from the description.
+pub struct RobinhoodCsvImporter {
+    account_id: String,
+}
+
+impl RobinhoodCsvImporter {
+    pub fn new(account_id: &str) -> Self {
+        Self {
+            account_id: account_id.to_owned(),
+        }
+    }
+
+    /// Parses the account activity report into account activities.
+    pub fn parse(&self, content: &str) -> Result<AccountActivities> {
+        let header_offset = parse_util::find_csv_header(content, &ROBINHOOD_HEADER)
+            .ok_or_else(|| anyhow!("Robinhood CSV header not found"))?;
+        let table = CsvTable::parse(&content[header_offset..])?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut activities = Vec::new();
+
+        for row in table.rows() {
+            // The report ends with a disclaimer row that has no activity date.
+            let Ok(date) = parse_util::parse_us_date(row.get("Activity Date")) else {
+                continue;
+            };
+            let timestamp_ms = parse_util::date_timestamp_ms(date, NEW_YORK_TZ)?;
+
+            let trans_code = row.get("Trans Code");
+            let instrument = row.get("Instrument");
+            let description = row.get("Description");
+            let amount = parse_util::parse_amount_or_zero(row.get("Amount"))?;
+            let price = parse_util::parse_amount_or_zero(row.get("Price"))?;
+            // Short option positions have their quantity suffixed with "S", e.g. "1S".
+            let quantity_field = row.get("Quantity");
+            let is_short = quantity_field.ends_with('S');
+            let quantity =
+                parse_util::parse_amount_or_zero(quantity_field.trim_end_matches('S'))?.abs();
+            let id = ids.id(ROBINHOOD_BROKERAGE_ID, &row.fields());
+
+            let option_ticker = || {
+                parse_option_description(description)
+                    .map(|contract| contract.occ_symbol())
+                    .ok_or_else(|| anyhow!("unrecognized option description '{}'", description))
+            };
+
+            let trade = match trans_code {
+                "Buy" => Some((instrument.to_owned(), TradeSide::Buy, price)),
+                "Sell" => Some((instrument.to_owned(), TradeSide::Sell, price)),
+                "BTO" | "BTC" => Some((option_ticker()?, TradeSide::Buy, price)),
+                "STO" | "STC" => Some((option_ticker()?, TradeSide::Sell, price)),
+                // Expiring contracts close out worthless: long positions are sold and short
+                // positions are bought back at zero.
+                "OEXP" if is_short => Some((option_ticker()?, TradeSide::Buy, 0.0)),
+                "OEXP" => Some((option_ticker()?, TradeSide::Sell, 0.0)),
+                _ => None,
+            };
+
+            if let Some((ticker, side, price)) = trade {
+                activities.push(Activity::Trade(TradeActivity {
+                    brokerage_execution_id: id,
+                    ticker,
+                    listing_exchange: None,
+                    side,
+                    quantity,
+                    price,
+                    // Trades are commission free, but the amount includes regulatory fees on
+                    // top of the traded value.
+                    commission: if trans_code == "OEXP" {
+                        0.0
+                    } else {
+                        let traded_value = quantity * price * trade_multiplier(trans_code);
+                        ((amount.abs() - traded_value).abs() * 100.0).round() / 100.0
+                    },
+                    execution_timestamp_ms: timestamp_ms,
+                }));
+                continue;
+            }
+
+            let kind = match trans_code {
+                "CDIV" | "MDIV" => CashTransactionKind::Dividend,
+                "INT" | "MINT" => CashTransactionKind::Interest,
+                "SLIP" => CashTransactionKind::Income,
+                "ACH" | "RTP" | "DCF" if amount >= 0.0 => CashTransactionKind::Deposit,
+                "ACH" | "RTP" | "DCF" => CashTransactionKind::Withdrawal,
+                "GOLD" | "AFEE" | "DFEE" => CashTransactionKind::Fee,
+                "DTAX" | "FTAX" => CashTransactionKind::Tax,
+                "JNLC" | "JNLS" => CashTransactionKind::Journal,
+                _ => {
+                    debug!("skipping unsupported Robinhood trans code '{}'", trans_code);
+                    continue;
+                }
+            };
+
+            activities.push(Activity::Cash(CashActivity {
+                brokerage_transaction_id: id,
+                kind,
+                amount,
+                currency: "USD".to_owned(),
+                timestamp_ms,
+                ticker: (!instrument.is_empty()).then(|| instrument.to_owned()),
+                listing_exchange: None,
+                description: description.to_owned(),
+            }));
+        }
+
+        Ok(AccountActivities {
+            account_id: self.account_id.clone(),
+            activities,
+        })
+    }
+}
+
+/// Option prices are quoted per share, with 100 shares per contract.
+fn trade_multiplier(trans_code: &str) -> f64 {
+    match trans_code {
+        "BTO" | "BTC" | "STO" | "STC" => 100.0,
+        _ => 1.0,
+    }
+}
+
+#[async_trait]
+impl StatementImporter for RobinhoodCsvImporter {
+    fn importer_name(&self) -> &'static str {
+        "robinhood-csv"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if parse_util::find_csv_header(content, &ROBINHOOD_HEADER).is_some() {
+            ContentMatch::likely("Robinhood activity CSV header")
+        } else {
+            ContentMatch::no("no Robinhood activity CSV header")
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing Robinhood CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let account_activities = self.parse(content)?;
+        writers::write_account_activities(
+            db,
+            session,
+            ROBINHOOD_BROKERAGE_ID,
+            &account_activities,
+            write_batch_size,
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    parse_util::{self, CsvTable, NEW_YORK_TZ, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const SCHWAB_BROKERAGE_ID: &str = "schwab";
+
+const SCHWAB_HEADER: [&str; 8] = [
+    "Date",
+    "Action",
+    "Symbol",
+    "Description",
+    "Quantity",
+    "Price",
+    "Fees & Comm",
+    "Amount",
+];
+
+/// Imports the "Transactions" CSV export from Charles Schwab.
+///
+/// Older exports start with a `Transactions for account ...` line that names the account. Newer
+/// exports don't, so the account id must then be configured with `account_id`.
+pub struct SchwabCsvImporter {
+    account_id: Option<String>,
+}
+
+impl Default for SchwabCsvImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchwabCsvImporter {
+    pub fn new() -> Self {
+        Self { account_id: None }
+    }
+
+    /// Sets the account id used when the export doesn't name its account.
+    pub fn account_id(mut self, account_id: &str) -> Self {
+        self.account_id = Some(account_id.to_owned());
+        self
+    }
+
+    fn statement_account_id(&self, preamble: &str) -> Result<String> {
+        let from_preamble = preamble.lines().find_map(|line| {
+            let (_, rest) = line.split_once("for account ")?;
+            let account = rest.split(" as of").next()?;
+            Some(account.trim().trim_matches('"').trim().to_owned())
+        });
+
+        from_preamble
+            .or_else(|| self.account_id.clone())
+            .ok_or_else(|| anyhow!("Schwab CSV does not name its account and none is configured"))
+    }
+
+    /// Parses the transactions CSV into account activities.
+    pub fn parse(&self, content: &str) -> Result<AccountActivities> {
+        let header_offset = parse_util::find_csv_header(content, &SCHWAB_HEADER)
+            .ok_or_else(|| anyhow!("Schwab CSV header not found"))?;
+        let account_id = self.statement_account_id(&content[..header_offset])?;
+        let table = CsvTable::parse(&content[header_offset..])?;
+        table.require_columns(&SCHWAB_HEADER)?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut activities = Vec::new();
+
+        for row in table.rows() {
+            let action = row.get("Action");
+            let date = row.get("Date");
+            if action.is_empty() || date.starts_with("Transactions Total") {
+                continue;
+            }
+
+            // Dates read "04/25/2025 as of 04/24/2025" when the posting date differs from the
+            // date the transaction took effect.
+            let effective_date = date.rsplit(" as of ").next().unwrap_or(date);
+            let timestamp_ms = parse_util::date_timestamp_ms(
+                parse_util::parse_us_date(effective_date)?,
+                NEW_YORK_TZ,
+            )?;
+            let symbol = row.get("Symbol");
+            let description = row.get("Description");
+            let amount = parse_util::parse_amount_or_zero(row.get("Amount"))?;
+            let id = ids.id(SCHWAB_BROKERAGE_ID, &row.fields());
+
+            let side = match action {
+                "Buy" | "Reinvest Shares" => Some(TradeSide::Buy),
+                "Sell" => Some(TradeSide::Sell),
+                _ => None,
+            };
+            if let Some(side) = side {
+                activities.push(Activity::Trade(TradeActivity {
+                    brokerage_execution_id: id,
+                    ticker: symbol.to_owned(),
+                    listing_exchange: None,
+                    side,
+                    quantity: parse_util::parse_amount_or_zero(row.get("Quantity"))?.abs(),
+                    price: parse_util::parse_amount_or_zero(row.get("Price"))?,
+                    commission: parse_util::parse_amount_or_zero(row.get("Fees & Comm"))?.abs(),
+                    execution_timestamp_ms: timestamp_ms,
+                }));
+                continue;
+            }
+
+            let kind = match action {
+                "Qualified Dividend" | "Cash Dividend" | "Non-Qualified Div"
+                | "Reinvest Dividend" | "Special Dividend" | "Pr Yr Div Reinvest" => {
+                    CashTransactionKind::Dividend
+                }
+                "Long Term Cap Gain" | "Short Term Cap Gain" => {
+                    CashTransactionKind::CapitalGainDistribution
+                }
+                "Bank Interest" | "Credit Interest" | "Margin Interest" => {
+                    CashTransactionKind::Interest
+                }
+                "Journal" | "Journaled Shares" => CashTransactionKind::Journal,
+                "MoneyLink Transfer" | "Wire Received" | "Wire Sent" | "Funds Received" => {
+                    CashTransactionKind::Transfer
+                }
+                "Service Fee" | "ADR Mgmt Fee" | "Misc Cash Entry" => CashTransactionKind::Fee,
+                "Foreign Tax Paid" | "NRA Tax Adj" => CashTransactionKind::Tax,
+                _ => {
+                    debug!("skipping unsupported Schwab action '{}'", action);
+                    continue;
+                }
+            };
+
+            activities.push(Activity::Cash(CashActivity {
+                brokerage_transaction_id: id,
+                kind,
+                amount,
+                currency: "USD".to_owned(),
+                timestamp_ms,
+                ticker: (!symbol.is_empty()).then(|| symbol.to_owned()),
+                listing_exchange: None,
+                description: description.to_owned(),
+            }));
+        }
+
+        Ok(AccountActivities {
+            account_id,
+            activities,
+        })
+    }
+}
+
+#[async_trait]
+impl StatementImporter for SchwabCsvImporter {
+    fn importer_name(&self) -> &'static str {
+        "schwab-csv"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if parse_util::find_csv_header(content, &SCHWAB_HEADER).is_some() {
+            ContentMatch::likely("Schwab transactions CSV header")
+        } else {
+            ContentMatch::no("no Schwab transactions CSV header")
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing Schwab CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let account_activities = self.parse(content)?;
+        writers::write_account_activities(
+            db,
+            session,
+            SCHWAB_BROKERAGE_ID,
+            &account_activities,
+            write_batch_size,
+        )
+        .await?;
+        Ok(())
+    }
+}
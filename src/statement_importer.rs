@@ -1,7 +1,11 @@
-use crate::path_match::PathMatch;
+use crate::{
+    content_match::ContentMatch, path_match::PathMatch,
+    streaming_statement_importer::StreamingStatementImporter, text_encoding,
+};
 use anyhow::Result;
+use chrono::NaiveDate;
 use mongodb::{ClientSession, Database, bson::oid::ObjectId};
-use std::{path::Path, sync::Arc};
+use std::{borrow::Cow, path::Path, sync::Arc};
 use tokio::sync::Mutex;
 
 use async_trait::async_trait;
@@ -9,7 +13,7 @@ use async_trait::async_trait;
 #[async_trait]
 pub trait StatementImporter {
     /// Returns the name of the importer. It must be unique among all importers.
-    fn importer_name(&self) -> &'static str;
+    fn importer_name(&self) -> &str;
 
     /// Returns whether the given file path might be a match for this importer, without opening the file.
     ///
@@ -18,22 +22,54 @@ pub trait StatementImporter {
     /// file contents.
     async fn path_may_match(&self, path: &Path) -> PathMatch;
 
-    /// Returns whether the given content string matches the importer.
+    /// Converts the bytes of a statement file to the content passed to `content_matches` and
+    /// `import`.
     ///
-    /// This is called after `path_may_match` returns `PathMatch::Match`.  If this returns
-    /// `PathMatch::Match`, the importer's `import` method will be called to import the content.'
-    async fn content_matches(&self, content: &str) -> PathMatch;
+    /// Text formats use the default, which detects the text encoding and strips any byte order
+    /// mark. Importers of binary formats, such as PDF, override this to extract the text they
+    /// parse.
+    fn decode_content<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>> {
+        text_encoding::decode_text(bytes)
+    }
+
+    /// Returns how confident the importer is that it can import the given content string, and
+    /// why.
+    ///
+    /// This is called after `path_may_match` returns `PathMatch::Match`. Of the importers that
+    /// match, the registry runs `import` on the one with the highest confidence, and fails if
+    /// several share the highest confidence.
+    async fn content_matches(&self, content: &str) -> ContentMatch;
+
+    /// Returns the date of the statement in the content, such as the end of its period, for
+    /// importing statement files in chronological order.
+    ///
+    /// Returns `None` by default, and for content that doesn't say.
+    fn statement_date(&self, _content: &str) -> Option<NaiveDate> {
+        None
+    }
+
+    /// Returns the importer as a `StreamingStatementImporter` if it can import statements
+    /// incrementally.
+    ///
+    /// Returns `None` by default.
+    fn streaming(&self) -> Option<&dyn StreamingStatementImporter> {
+        None
+    }
 
     /// Imports the content string into the brokerage database.
     ///
     /// If `session` is `None`, the entirety of the import should be done in a single transaction
     /// that covers just this import call.
     /// If `session` is `Some`, the import should be performed in the context of the provided session.
+    ///
+    /// Trades and cash transactions are written to the database at most `write_batch_size` at a
+    /// time.
     async fn import(
         &self,
         content: &str,
         db: &Database,
         session: Option<Arc<Mutex<ClientSession>>>,
         source_id: ObjectId,
+        write_batch_size: usize,
     ) -> Result<()>;
 }
@@ -0,0 +1,29 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::sync::Arc;
+use tokio::{io::AsyncBufRead, sync::Mutex};
+
+use crate::statement_importer::StatementImporter;
+
+/// An importer that parses statements incrementally from a byte stream, for statements too
+/// large to read into memory.
+///
+/// The registry streams files at least as large as its streaming threshold to the importer,
+/// after matching the importer against the start of the file. Importers advertise the
+/// capability through `StatementImporter::streaming`.
+#[async_trait]
+pub trait StreamingStatementImporter: StatementImporter + Sync {
+    /// Imports the statement read from `reader` into the brokerage database, parsing and
+    /// writing at most `batch_size` records at a time.
+    ///
+    /// `session` and `source_id` are as for `StatementImporter::import`.
+    async fn import_stream(
+        &self,
+        reader: &mut (dyn AsyncBufRead + Send + Unpin),
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        batch_size: usize,
+    ) -> Result<()>;
+}
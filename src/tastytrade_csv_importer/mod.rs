@@ -0,0 +1,229 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::{DateTime, NaiveDate};
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    option_contract::{OptionContract, OptionRight},
+    parse_util::{self, CsvRow, CsvTable, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const TASTYTRADE_BROKERAGE_ID: &str = "tastytrade";
+
+const TASTYTRADE_HEADER: [&str; 6] = [
+    "Date",
+    "Type",
+    "Sub Type",
+    "Action",
+    "Symbol",
+    "Instrument Type",
+];
+
+/// Imports the tastytrade transaction history CSV.
+///
+/// Equity options are recorded against their OCC symbol, built from the root symbol,
+/// expiration, strike and call/put columns. Futures and future options keep tastytrade's symbol.
+/// Commissions are stored on the trade execution, while regulatory and exchange fees are
+/// recorded as a separate fee cash transaction.
+pub struct TastytradeCsvImporter {
+    account_id: String,
+}
+
+impl TastytradeCsvImporter {
+    pub fn new(account_id: &str) -> Self {
+        Self {
+            account_id: account_id.to_owned(),
+        }
+    }
+
+    fn parse_timestamp_ms(value: &str) -> Result<i64> {
+        DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%z")
+            .map(|dt| dt.timestamp_millis())
+            .map_err(|e| anyhow!("invalid tastytrade date '{}': {}", value, e))
+    }
+
+    fn ticker(row: &CsvRow) -> Result<String> {
+        match row.get("Instrument Type") {
+            "Equity Option" => {
+                let expiration = row.get("Expiration Date");
+                let contract = OptionContract {
+                    underlying: row
+                        .get_any(&["Root Symbol", "Underlying Symbol"])
+                        .to_owned(),
+                    expiration: NaiveDate::parse_from_str(expiration, "%m/%d/%y").map_err(|e| {
+                        anyhow!("invalid option expiration '{}': {}", expiration, e)
+                    })?,
+                    strike: parse_util::parse_amount(row.get("Strike Price"))?
+                        .ok_or_else(|| anyhow!("option row has no strike price"))?,
+                    right: match row.get("Call or Put") {
+                        "CALL" => OptionRight::Call,
+                        "PUT" => OptionRight::Put,
+                        other => return Err(anyhow!("invalid option right '{}'", other)),
+                    },
+                };
+                Ok(contract.occ_symbol())
+            }
+            _ => Ok(row.get("Symbol").to_owned()),
+        }
+    }
+
+    /// Returns the per-share price of a trade row. Option prices are reported per contract.
+    fn price(row: &CsvRow) -> Result<f64> {
+        let average_price = parse_util::parse_amount_or_zero(row.get("Average Price"))?.abs();
+        match row.get("Instrument Type") {
+            "Equity Option" => {
+                Ok(average_price
+                    / parse_util::parse_amount(row.get("Multiplier"))?.unwrap_or(100.0))
+            }
+            _ => Ok(average_price),
+        }
+    }
+
+    /// Parses the transaction history CSV into account activities.
+    pub fn parse(&self, content: &str) -> Result<AccountActivities> {
+        let header_offset = parse_util::find_csv_header(content, &TASTYTRADE_HEADER)
+            .ok_or_else(|| anyhow!("tastytrade CSV header not found"))?;
+        let table = CsvTable::parse(&content[header_offset..])?;
+        table.require_columns(&["Value", "Quantity", "Average Price", "Commissions", "Fees"])?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut activities = Vec::new();
+
+        for row in table.rows() {
+            let timestamp_ms = Self::parse_timestamp_ms(row.get("Date"))?;
+            let id = ids.id(TASTYTRADE_BROKERAGE_ID, &row.fields());
+            let currency = match row.get("Currency") {
+                "" => "USD",
+                currency => currency,
+            };
+
+            let side = match row.get("Action") {
+                "BUY_TO_OPEN" | "BUY_TO_CLOSE" | "BUY" => Some(TradeSide::Buy),
+                "SELL_TO_OPEN" | "SELL_TO_CLOSE" | "SELL" => Some(TradeSide::Sell),
+                _ => None,
+            };
+
+            if let Some(side) = side {
+                let ticker = Self::ticker(&row)?;
+                let fees = parse_util::parse_amount_or_zero(row.get("Fees"))?;
+
+                activities.push(Activity::Trade(TradeActivity {
+                    brokerage_execution_id: id.clone(),
+                    ticker: ticker.clone(),
+                    listing_exchange: None,
+                    side,
+                    quantity: parse_util::parse_amount_or_zero(row.get("Quantity"))?.abs(),
+                    price: Self::price(&row)?,
+                    commission: parse_util::parse_amount_or_zero(row.get("Commissions"))?.abs(),
+                    execution_timestamp_ms: timestamp_ms,
+                }));
+
+                if fees != 0.0 {
+                    activities.push(Activity::Cash(CashActivity {
+                        brokerage_transaction_id: format!("{}:fees", id),
+                        kind: CashTransactionKind::Fee,
+                        amount: fees,
+                        currency: currency.to_owned(),
+                        timestamp_ms,
+                        ticker: Some(ticker),
+                        listing_exchange: None,
+                        description: format!("Fees: {}", row.get("Description")),
+                    }));
+                }
+                continue;
+            }
+
+            let sub_type = row.get("Sub Type");
+            let kind = match (row.get("Type"), sub_type) {
+                ("Money Movement", "Dividend") => CashTransactionKind::Dividend,
+                ("Money Movement", "Credit Interest" | "Debit Interest") => {
+                    CashTransactionKind::Interest
+                }
+                ("Money Movement", "Deposit") => CashTransactionKind::Deposit,
+                ("Money Movement", "Withdrawal") => CashTransactionKind::Withdrawal,
+                ("Money Movement", "Fee" | "Balance Adjustment") => CashTransactionKind::Fee,
+                ("Money Movement", "Transfer") => CashTransactionKind::Transfer,
+                _ => {
+                    debug!("skipping unsupported tastytrade sub type '{}'", sub_type);
+                    continue;
+                }
+            };
+
+            let symbol = row.get("Symbol");
+            activities.push(Activity::Cash(CashActivity {
+                brokerage_transaction_id: id,
+                kind,
+                amount: parse_util::parse_amount_or_zero(row.get("Value"))?,
+                currency: currency.to_owned(),
+                timestamp_ms,
+                ticker: (!symbol.is_empty()).then(|| symbol.to_owned()),
+                listing_exchange: None,
+                description: row.get("Description").to_owned(),
+            }));
+        }
+
+        Ok(AccountActivities {
+            account_id: self.account_id.clone(),
+            activities,
+        })
+    }
+}
+
+#[async_trait]
+impl StatementImporter for TastytradeCsvImporter {
+    fn importer_name(&self) -> &'static str {
+        "tastytrade-csv"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if parse_util::find_csv_header(content, &TASTYTRADE_HEADER).is_some() {
+            ContentMatch::likely("tastytrade transactions CSV header")
+        } else {
+            ContentMatch::no("no tastytrade transactions CSV header")
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing tastytrade CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let account_activities = self.parse(content)?;
+        writers::write_account_activities(
+            db,
+            session,
+            TASTYTRADE_BROKERAGE_ID,
+            &account_activities,
+            write_batch_size,
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::{Result, anyhow};
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use std::borrow::Cow;
+
+/// The number of leading bytes examined to detect UTF-16 text without a byte order mark.
+const UTF16_SNIFF_LEN: usize = 64;
+
+fn decode_with<'a>(encoding: &'static Encoding, bytes: &'a [u8]) -> Result<Cow<'a, str>> {
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    if had_errors {
+        return Err(anyhow!("content is not valid {} text", encoding.name()));
+    }
+    Ok(text)
+}
+
+/// Detects UTF-16 text without a byte order mark by the zero high bytes of ASCII characters.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(UTF16_SNIFF_LEN) & !1];
+    if sample.len() < 4 {
+        return None;
+    }
+    let zeros = |offset: usize| {
+        sample
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    let pairs = sample.len() / 2;
+    match (zeros(0), zeros(1)) {
+        (0, odd) if odd * 2 > pairs => Some(UTF_16LE),
+        (even, 0) if even * 2 > pairs => Some(UTF_16BE),
+        _ => None,
+    }
+}
+
+/// Returns the encoding named by an `<?xml ... encoding="..."?>` declaration.
+fn xml_declared_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    if !bytes.starts_with(b"<?xml") {
+        return None;
+    }
+    let end = bytes.iter().position(|b| *b == b'>')?;
+    let declaration = std::str::from_utf8(&bytes[..end]).ok()?;
+    let value = declaration.split("encoding=").nth(1)?;
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let label = value[1..].split(quote).next()?;
+    Encoding::for_label(label.as_bytes())
+}
+
+/// Decodes the bytes of a statement file to text, without any byte order mark.
+///
+/// The encoding is taken from a byte order mark, then detected as UTF-16 by the zero bytes of
+/// ASCII characters, then UTF-8 if the bytes are valid UTF-8, then from an XML declaration,
+/// and finally assumed to be Windows-1252, the superset of Latin-1 that brokers' CSV exports
+/// use when they aren't UTF-8.
+pub fn decode_text(bytes: &[u8]) -> Result<Cow<'_, str>> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        return decode_with(encoding, &bytes[bom_len..]);
+    }
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return decode_with(encoding, bytes);
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Ok(Cow::Borrowed(text));
+    }
+    match xml_declared_encoding(bytes) {
+        Some(encoding) => decode_with(encoding, bytes),
+        None => decode_with(WINDOWS_1252, bytes),
+    }
+}
+
+/// Returns whether the start of a file is UTF-8 text that `decode_text` would decode as such:
+/// valid UTF-8 without a byte order mark for another encoding, that isn't detected as UTF-16
+/// and doesn't declare another encoding.
+pub fn is_utf8(bytes: &[u8]) -> bool {
+    let text = match Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) if encoding == UTF_8 => &bytes[bom_len..],
+        Some(_) => return false,
+        None => bytes,
+    };
+    sniff_utf16(text).is_none()
+        && std::str::from_utf8(text).is_ok()
+        && xml_declared_encoding(text).is_none_or(|encoding| encoding == UTF_8)
+}
+
+/// Trims the start of a UTF-8 file, read without the rest of it, to its last complete
+/// character.
+pub fn trim_partial_utf8(bytes: &[u8]) -> &[u8] {
+    match std::str::from_utf8(bytes) {
+        Err(e) if e.error_len().is_none() => &bytes[..e.valid_up_to()],
+        _ => bytes,
+    }
+}
+
+/// Returns the content from its root element, skipping any byte order mark, XML declaration,
+/// processing instructions, comments, doctype and whitespace before it.
+pub fn skip_xml_prolog(content: &str) -> &str {
+    let mut rest = content.trim_start_matches('\u{feff}').trim_start();
+    loop {
+        let end = if rest.starts_with("<?") {
+            rest.find("?>").map(|i| i + 2)
+        } else if rest.starts_with("<!--") {
+            rest.find("-->").map(|i| i + 3)
+        } else if rest.starts_with("<!") {
+            rest.find('>').map(|i| i + 1)
+        } else {
+            return rest;
+        };
+        match end {
+            Some(end) => rest = rest[end..].trim_start(),
+            None => return "",
+        }
+    }
+}
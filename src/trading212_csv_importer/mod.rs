@@ -0,0 +1,283 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::NaiveDateTime;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    parse_util::{self, CsvRow, CsvTable, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const TRADING212_BROKERAGE_ID: &str = "trading212";
+
+const TRADING212_HEADER: [&str; 5] = ["Action", "Time", "ISIN", "Ticker", "Name"];
+
+/// Imports the Trading 212 history export CSV.
+///
+/// The export doesn't name the account, so the account id is given when constructing the
+/// importer. Securities are recorded by ticker, or by ISIN for rows without one. Fractional share
+/// quantities are kept exactly as exported.
+///
+/// Dividends are recorded gross in the instrument currency, with the withholding tax as a
+/// separate tax cash transaction. Currency conversion fees and stamp duty on trades are likewise
+/// recorded as separate fee and tax cash transactions.
+pub struct Trading212CsvImporter {
+    account_id: String,
+}
+
+impl Trading212CsvImporter {
+    pub fn new(account_id: &str) -> Self {
+        Self {
+            account_id: account_id.to_owned(),
+        }
+    }
+
+    fn parse_timestamp_ms(value: &str) -> Result<i64> {
+        // Times are in UTC, with or without fractional seconds.
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+            .map(|dt| dt.and_utc().timestamp_millis())
+            .map_err(|e| anyhow!("invalid Trading 212 time '{}': {}", value, e))
+    }
+
+    /// Returns a cash activity for an amount and currency column pair, if the amount is non-zero.
+    fn column_cash(
+        row: &CsvRow,
+        column: &str,
+        id: &str,
+        kind: CashTransactionKind,
+        timestamp_ms: i64,
+        ticker: Option<&str>,
+    ) -> Result<Option<Activity>> {
+        let amount = parse_util::parse_amount_or_zero(row.get(column))?;
+        if amount == 0.0 {
+            return Ok(None);
+        }
+
+        Ok(Some(Activity::Cash(CashActivity {
+            brokerage_transaction_id: format!("{}:{}", id, column.to_lowercase().replace(' ', "-")),
+            kind,
+            amount: -amount.abs(),
+            currency: row.get(&format!("Currency ({})", column)).to_owned(),
+            timestamp_ms,
+            ticker: ticker.map(str::to_owned),
+            listing_exchange: None,
+            description: format!("{}: {}", column, row.get("Name")),
+        })))
+    }
+
+    /// Parses the history export into account activities.
+    pub fn parse(&self, content: &str) -> Result<AccountActivities> {
+        let header_offset = parse_util::find_csv_header(content, &TRADING212_HEADER)
+            .ok_or_else(|| anyhow!("Trading 212 CSV header not found"))?;
+        let table = CsvTable::parse(&content[header_offset..])?;
+        table.require_columns(&["No. of shares", "Price / share", "Total"])?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut activities = Vec::new();
+
+        for row in table.rows() {
+            let action = row.get("Action");
+            let timestamp_ms = Self::parse_timestamp_ms(row.get("Time"))?;
+            let id = match row.get("ID") {
+                "" => ids.id(TRADING212_BROKERAGE_ID, &row.fields()),
+                id => id.to_owned(),
+            };
+            let ticker = match row.get("Ticker") {
+                "" => row.get("ISIN"),
+                ticker => ticker,
+            };
+            let security = (!ticker.is_empty()).then_some(ticker);
+            let shares = parse_util::parse_amount_or_zero(row.get("No. of shares"))?;
+            let price = parse_util::parse_amount_or_zero(row.get("Price / share"))?;
+            let total = parse_util::parse_amount_or_zero(row.get("Total"))?;
+            let total_currency = row.get("Currency (Total)");
+            let cash = |brokerage_transaction_id: String,
+                        kind: CashTransactionKind,
+                        amount: f64,
+                        currency: &str| {
+                Activity::Cash(CashActivity {
+                    brokerage_transaction_id,
+                    kind,
+                    amount,
+                    currency: currency.to_owned(),
+                    timestamp_ms,
+                    ticker: security.map(str::to_owned),
+                    listing_exchange: None,
+                    description: match row.get("Notes") {
+                        "" => format!("{} {}", action, row.get("Name")).trim().to_owned(),
+                        notes => notes.to_owned(),
+                    },
+                })
+            };
+
+            let lowercase_action = action.to_lowercase();
+            let side = if lowercase_action.ends_with(" buy") {
+                Some(TradeSide::Buy)
+            } else if lowercase_action.ends_with(" sell") {
+                Some(TradeSide::Sell)
+            } else {
+                None
+            };
+
+            if let Some(side) = side {
+                activities.push(Activity::Trade(TradeActivity {
+                    brokerage_execution_id: id.clone(),
+                    ticker: ticker.to_owned(),
+                    listing_exchange: None,
+                    side,
+                    quantity: shares.abs(),
+                    price,
+                    commission: (parse_util::parse_amount_or_zero(row.get("Transaction fee"))?
+                        + parse_util::parse_amount_or_zero(row.get("Finra fee"))?)
+                    .abs(),
+                    execution_timestamp_ms: timestamp_ms,
+                }));
+                for (column, kind) in [
+                    ("Currency conversion fee", CashTransactionKind::Fee),
+                    ("Stamp duty reserve tax", CashTransactionKind::Tax),
+                ] {
+                    activities.extend(Self::column_cash(
+                        &row,
+                        column,
+                        &id,
+                        kind,
+                        timestamp_ms,
+                        security,
+                    )?);
+                }
+                continue;
+            }
+
+            if lowercase_action.starts_with("dividend") {
+                let gross = (shares.abs() * price * 100.0).round() / 100.0;
+                activities.push(cash(
+                    id.clone(),
+                    CashTransactionKind::Dividend,
+                    gross,
+                    row.get("Currency (Price / share)"),
+                ));
+                activities.extend(Self::column_cash(
+                    &row,
+                    "Withholding tax",
+                    &id,
+                    CashTransactionKind::Tax,
+                    timestamp_ms,
+                    security,
+                )?);
+                continue;
+            }
+
+            match action {
+                "Deposit" => activities.push(cash(
+                    id,
+                    CashTransactionKind::Deposit,
+                    total.abs(),
+                    total_currency,
+                )),
+                "Withdrawal" => activities.push(cash(
+                    id,
+                    CashTransactionKind::Withdrawal,
+                    -total.abs(),
+                    total_currency,
+                )),
+                "Interest on cash" | "Lending interest" => activities.push(cash(
+                    id,
+                    CashTransactionKind::Interest,
+                    total,
+                    total_currency,
+                )),
+                "Currency conversion" => {
+                    let from =
+                        parse_util::parse_amount(row.get("Currency conversion from amount"))?;
+                    let to = parse_util::parse_amount(row.get("Currency conversion to amount"))?;
+                    match (from, to) {
+                        (Some(from), Some(to)) => {
+                            activities.push(cash(
+                                format!("{}:from", id),
+                                CashTransactionKind::ForeignExchange,
+                                -from.abs(),
+                                row.get("Currency (Currency conversion from amount)"),
+                            ));
+                            activities.push(cash(
+                                format!("{}:to", id),
+                                CashTransactionKind::ForeignExchange,
+                                to.abs(),
+                                row.get("Currency (Currency conversion to amount)"),
+                            ));
+                        }
+                        _ => activities.push(cash(
+                            id,
+                            CashTransactionKind::ForeignExchange,
+                            total,
+                            total_currency,
+                        )),
+                    }
+                }
+                _ => debug!("skipping unsupported Trading 212 action '{}'", action),
+            }
+        }
+
+        Ok(AccountActivities {
+            account_id: self.account_id.clone(),
+            activities,
+        })
+    }
+}
+
+#[async_trait]
+impl StatementImporter for Trading212CsvImporter {
+    fn importer_name(&self) -> &'static str {
+        "trading212-csv"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if parse_util::find_csv_header(content, &TRADING212_HEADER).is_some() {
+            ContentMatch::likely("Trading 212 history CSV header")
+        } else {
+            ContentMatch::no("no Trading 212 history CSV header")
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing Trading 212 CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let account_activities = self.parse(content)?;
+        writers::write_account_activities(
+            db,
+            session,
+            TRADING212_BROKERAGE_ID,
+            &account_activities,
+            write_batch_size,
+        )
+        .await?;
+        Ok(())
+    }
+}
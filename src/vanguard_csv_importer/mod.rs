@@ -0,0 +1,237 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use brokerage_db::trade_execution::TradeSide;
+use chrono::NaiveDate;
+use mongodb::{ClientSession, Database, bson::oid::ObjectId};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    activity::{AccountActivities, Activity, CashActivity, TradeActivity},
+    cash_transaction::CashTransactionKind,
+    content_match::ContentMatch,
+    parse_util::{self, CsvTable, NEW_YORK_TZ, SyntheticIdGenerator},
+    path_match::PathMatch,
+    statement_importer::StatementImporter,
+    writers,
+};
+
+pub const VANGUARD_BROKERAGE_ID: &str = "vanguard";
+
+const HOLDINGS_HEADER: [&str; 3] = ["Account Number", "Investment Name", "Symbol"];
+const TRANSACTIONS_HEADER: [&str; 3] = ["Account Number", "Trade Date", "Settlement Date"];
+
+/// A holding from the first table of a Vanguard download.
+#[derive(Debug, PartialEq)]
+pub struct VanguardHolding {
+    pub account_id: String,
+    pub investment_name: String,
+    pub symbol: String,
+    pub shares: f64,
+    pub share_price: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct VanguardStatement {
+    pub holdings: Vec<VanguardHolding>,
+    pub accounts: Vec<AccountActivities>,
+}
+
+/// Imports the Vanguard transaction download CSV.
+///
+/// The download holds two tables: account holdings followed by transactions. Mutual fund trades
+/// keep the fractional share quantity and NAV share price exactly as Vanguard reports them.
+pub struct VanguardCsvImporter {}
+
+impl Default for VanguardCsvImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VanguardCsvImporter {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn parse_date(value: &str) -> Result<NaiveDate> {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").or_else(|_| parse_util::parse_us_date(value))
+    }
+
+    fn parse_holdings(text: &str) -> Result<Vec<VanguardHolding>> {
+        let table = CsvTable::parse(text)?;
+        table.require_columns(&["Shares", "Share Price"])?;
+
+        table
+            .rows()
+            .filter(|row| !row.get("Account Number").is_empty() && !row.get("Symbol").is_empty())
+            .map(|row| {
+                Ok(VanguardHolding {
+                    account_id: row.get("Account Number").to_owned(),
+                    investment_name: row.get("Investment Name").to_owned(),
+                    symbol: row.get("Symbol").to_owned(),
+                    shares: parse_util::parse_amount_or_zero(row.get("Shares"))?,
+                    share_price: parse_util::parse_amount_or_zero(row.get("Share Price"))?,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_transactions(text: &str) -> Result<Vec<AccountActivities>> {
+        let table = CsvTable::parse(text)?;
+        table.require_columns(&["Transaction Type", "Symbol", "Shares", "Share Price"])?;
+
+        let mut ids = SyntheticIdGenerator::new();
+        let mut accounts = Vec::<AccountActivities>::new();
+
+        for row in table.rows() {
+            let account_id = row.get("Account Number");
+            let Ok(date) = Self::parse_date(row.get("Trade Date")) else {
+                continue;
+            };
+            let timestamp_ms = parse_util::date_timestamp_ms(date, NEW_YORK_TZ)?;
+
+            let transaction_type = row.get("Transaction Type");
+            let symbol = row.get("Symbol");
+            let shares = parse_util::parse_amount_or_zero(row.get("Shares"))?;
+            let id = ids.id(VANGUARD_BROKERAGE_ID, &row.fields());
+
+            let side = match transaction_type {
+                "Buy" | "Reinvestment" | "Conversion (incoming)" => Some(TradeSide::Buy),
+                "Sell" | "Conversion (outgoing)" => Some(TradeSide::Sell),
+                "Exchange" if shares > 0.0 => Some(TradeSide::Buy),
+                "Exchange" => Some(TradeSide::Sell),
+                _ => None,
+            };
+
+            let activity = if let Some(side) = side {
+                Activity::Trade(TradeActivity {
+                    brokerage_execution_id: id,
+                    ticker: symbol.to_owned(),
+                    listing_exchange: None,
+                    side,
+                    quantity: shares.abs(),
+                    price: parse_util::parse_amount_or_zero(row.get("Share Price"))?,
+                    commission: parse_util::parse_amount_or_zero(
+                        row.get_any(&["Commissions and Fees", "Commission Fees"]),
+                    )?
+                    .abs(),
+                    execution_timestamp_ms: timestamp_ms,
+                })
+            } else {
+                let kind = match transaction_type {
+                    "Dividend" => CashTransactionKind::Dividend,
+                    "Capital gain (LT)" | "Capital gain (ST)" => {
+                        CashTransactionKind::CapitalGainDistribution
+                    }
+                    "Interest" => CashTransactionKind::Interest,
+                    "Funds Received" | "Contribution" => CashTransactionKind::Deposit,
+                    "Withdrawal" | "Distribution" => CashTransactionKind::Withdrawal,
+                    "Fee" => CashTransactionKind::Fee,
+                    _ => {
+                        // Includes sweeps into and out of the settlement fund.
+                        debug!(
+                            "skipping unsupported Vanguard transaction type '{}'",
+                            transaction_type
+                        );
+                        continue;
+                    }
+                };
+
+                Activity::Cash(CashActivity {
+                    brokerage_transaction_id: id,
+                    kind,
+                    amount: parse_util::parse_amount_or_zero(row.get("Net Amount"))?,
+                    currency: "USD".to_owned(),
+                    timestamp_ms,
+                    ticker: (!symbol.is_empty()).then(|| symbol.to_owned()),
+                    listing_exchange: None,
+                    description: row.get("Transaction Description").to_owned(),
+                })
+            };
+
+            match accounts.iter_mut().find(|a| a.account_id == account_id) {
+                Some(account) => account.activities.push(activity),
+                None => accounts.push(AccountActivities {
+                    account_id: account_id.to_owned(),
+                    activities: vec![activity],
+                }),
+            }
+        }
+
+        Ok(accounts)
+    }
+
+    /// Splits the download into its holdings and transactions tables and parses both.
+    pub fn parse(&self, content: &str) -> Result<VanguardStatement> {
+        let transactions_offset = parse_util::find_csv_header(content, &TRANSACTIONS_HEADER)
+            .ok_or_else(|| anyhow!("Vanguard transactions table not found"))?;
+
+        let holdings = match parse_util::find_csv_header(content, &HOLDINGS_HEADER) {
+            Some(offset) if offset < transactions_offset => {
+                Self::parse_holdings(&content[offset..transactions_offset])?
+            }
+            _ => Vec::new(),
+        };
+        let accounts = Self::parse_transactions(&content[transactions_offset..])?;
+
+        Ok(VanguardStatement { holdings, accounts })
+    }
+}
+
+#[async_trait]
+impl StatementImporter for VanguardCsvImporter {
+    fn importer_name(&self) -> &'static str {
+        "vanguard-csv"
+    }
+
+    async fn path_may_match(&self, path: &Path) -> PathMatch {
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            PathMatch::Match
+        } else {
+            PathMatch::NoMatch
+        }
+    }
+
+    async fn content_matches(&self, content: &str) -> ContentMatch {
+        if parse_util::find_csv_header(content, &TRANSACTIONS_HEADER).is_some() {
+            ContentMatch::likely("Vanguard transactions CSV header")
+        } else {
+            ContentMatch::no("no Vanguard transactions CSV header")
+        }
+    }
+
+    async fn import(
+        &self,
+        content: &str,
+        db: &Database,
+        session: Option<Arc<Mutex<ClientSession>>>,
+        source_id: ObjectId,
+        write_batch_size: usize,
+    ) -> Result<()> {
+        debug!(
+            "Importing Vanguard CSV with importer {}, source_id {}",
+            self.importer_name(),
+            source_id,
+        );
+
+        let statement = self.parse(content)?;
+
+        // Held funds are added as securities even if the download has no trades for them.
+        let held_tickers = statement
+            .holdings
+            .iter()
+            .map(|holding| holding.symbol.as_str())
+            .collect::<Vec<&str>>();
+        writers::write_statement_activities(
+            db,
+            session,
+            VANGUARD_BROKERAGE_ID,
+            &statement.accounts,
+            &held_tickers,
+            write_batch_size,
+        )
+        .await
+    }
+}
@@ -1,143 +1,294 @@
 use std::sync::Arc;
 
 use anyhow::Result;
-use brokerage_db::{
-    account::BrokerageAccount,
-    security::{Security, SecurityType},
-    trade_execution::{TradeExecution, TradeSide},
+use brokerage_db::{account::BrokerageAccount, trade_execution::TradeExecution};
+use mongodb::{
+    ClientSession, Database,
+    bson::{doc, oid::ObjectId},
 };
-use mongodb::{ClientSession, Database, bson::oid::ObjectId};
 use tokio::sync::Mutex;
 use tracing::{debug, info};
 
-pub struct TradeWriter {
-    brokerage_account_id: Option<ObjectId>,
-    brokerage_execution_id: Option<String>,
-    commission: Option<f64>,
-    execution_timestamp_ms: Option<i64>,
-    quantity: Option<f64>,
-    price: Option<f64>,
-    security_id: Option<ObjectId>,
-    side: Option<TradeSide>,
-}
-
-impl TradeWriter {
-    pub fn new() -> Self {
-        Self {
-            brokerage_account_id: None,
-            brokerage_execution_id: None,
-            commission: None,
-            execution_timestamp_ms: None,
-            quantity: None,
-            price: None,
-            security_id: None,
-            side: None,
-        }
-    }
+use crate::{
+    account_type::{AccountType, BrokerageAccountType},
+    activity::{AccountActivities, Activity},
+    batch_writer::BatchWriter,
+    cash_transaction::CashTransaction,
+    db_util,
+};
 
-    pub fn brokerage_account_id(mut self, id: ObjectId) -> Self {
-        self.brokerage_account_id = Some(id);
-        self
-    }
+/// Listing exchange recorded for securities from statements that don't name an exchange.
+pub const UNKNOWN_LISTING_EXCHANGE: &str = "UNKNOWN";
 
-    pub fn brokerage_execution_id(mut self, id: &str) -> Self {
-        self.brokerage_execution_id = Some(id.to_owned());
-        self
+pub async fn maybe_add_brokerage_account(
+    db: &Database,
+    session: Option<Arc<Mutex<ClientSession>>>,
+    brokerage_id: &str,
+    account_id: &str,
+) -> Result<BrokerageAccount> {
+    match BrokerageAccount::find_by_brokerage_and_account_id(db, brokerage_id, account_id).await? {
+        Some(brokerage_account) => {
+            debug!(
+                "Brokerage account already exists: {} at {}",
+                account_id, brokerage_id
+            );
+            Ok(brokerage_account)
+        }
+        None => {
+            let new_account = BrokerageAccount::new(brokerage_id, account_id);
+            new_account.insert(db, session).await?;
+            info!(
+                "Added new brokerage account: {} at {}",
+                account_id, brokerage_id
+            );
+            Ok(new_account)
+        }
     }
+}
 
-    pub fn commission(mut self, commission: f64) -> Self {
-        self.commission = Some(commission);
-        self
+/// Records the account type of a brokerage account, unless it already has one.
+///
+/// The existing account type is looked up in the session, so that one recorded earlier in the
+/// same transaction is found.
+pub async fn maybe_add_account_type(
+    db: &Database,
+    session: Option<Arc<Mutex<ClientSession>>>,
+    brokerage_account_id: ObjectId,
+    account_type: AccountType,
+) -> Result<()> {
+    let existing = db_util::find::<BrokerageAccountType>(
+        doc! { "brokerage_account_id": brokerage_account_id },
+        None,
+        db,
+        BrokerageAccountType::COLLECTION_NAME,
+        session.clone(),
+    )
+    .await?;
+    if let Some(existing) = existing.first() {
+        debug!(
+            "account type already recorded ({:?}), skipping db insert",
+            existing.account_type()
+        );
+        return Ok(());
     }
 
-    pub fn execution_timestamp_ms(mut self, timestamp: i64) -> Self {
-        self.execution_timestamp_ms = Some(timestamp);
-        self
-    }
+    BrokerageAccountType::new(brokerage_account_id, account_type)
+        .insert(db, session)
+        .await
+}
 
-    pub fn quantity(mut self, quantity: f64) -> Self {
-        self.quantity = Some(quantity);
-        self
-    }
+/// Adds parsed activities for a brokerage account to the writer, along with any securities they
+/// reference, preloading what already exists `write_batch_size` activities at a time.
+async fn add_activities(
+    writer: &mut BatchWriter,
+    db: &Database,
+    session: Option<Arc<Mutex<ClientSession>>>,
+    brokerage_account_id: ObjectId,
+    activities: &[Activity],
+    write_batch_size: usize,
+) -> Result<()> {
+    for batch in activities.chunks(write_batch_size) {
+        let tickers = batch
+            .iter()
+            .filter_map(|activity| match activity {
+                Activity::Trade(trade) => Some(trade.ticker.as_str()),
+                Activity::Cash(cash) => cash.ticker.as_deref(),
+            })
+            .collect::<Vec<&str>>();
+        writer
+            .preload_securities(db, session.clone(), &tickers)
+            .await?;
+        let execution_ids = batch
+            .iter()
+            .filter_map(|activity| match activity {
+                Activity::Trade(trade) => Some(trade.brokerage_execution_id.as_str()),
+                Activity::Cash(_) => None,
+            })
+            .collect::<Vec<&str>>();
+        writer
+            .preload_trade_executions(db, session.clone(), brokerage_account_id, &execution_ids)
+            .await?;
+        let cash_transaction_ids = batch
+            .iter()
+            .filter_map(|activity| match activity {
+                Activity::Trade(_) => None,
+                Activity::Cash(cash) => Some(cash.brokerage_transaction_id.as_str()),
+            })
+            .collect::<Vec<&str>>();
+        writer
+            .preload_cash_transactions(
+                db,
+                session.clone(),
+                brokerage_account_id,
+                &cash_transaction_ids,
+            )
+            .await?;
 
-    pub fn price(mut self, price: f64) -> Self {
-        self.price = Some(price);
-        self
+        for activity in batch {
+            match activity {
+                Activity::Trade(trade) => {
+                    let security_id = match &trade.listing_exchange {
+                        Some(listing_exchange) => {
+                            writer
+                                .security(
+                                    db,
+                                    session.clone(),
+                                    &trade.ticker,
+                                    listing_exchange,
+                                    None,
+                                )
+                                .await?
+                        }
+                        None => {
+                            writer
+                                .security_by_ticker(db, session.clone(), &trade.ticker)
+                                .await?
+                        }
+                    };
+                    let trade_execution = TradeExecution::builder()
+                        .brokerage_account_id(brokerage_account_id)
+                        .brokerage_execution_id(&trade.brokerage_execution_id)
+                        .commission(trade.commission)
+                        .execution_timestamp_ms(trade.execution_timestamp_ms)
+                        .quantity(trade.quantity)
+                        .price(trade.price)
+                        .security_id(security_id)
+                        .side(trade.side.clone())
+                        .build()?;
+                    writer
+                        .add_trade(trade_execution, db, session.clone())
+                        .await?;
+                }
+                Activity::Cash(cash) => {
+                    let security_id = match (&cash.ticker, &cash.listing_exchange) {
+                        (Some(ticker), Some(listing_exchange)) => Some(
+                            writer
+                                .security(db, session.clone(), ticker, listing_exchange, None)
+                                .await?,
+                        ),
+                        (Some(ticker), None) => Some(
+                            writer
+                                .security_by_ticker(db, session.clone(), ticker)
+                                .await?,
+                        ),
+                        (None, _) => None,
+                    };
+                    let cash_transaction = CashTransaction::new(
+                        brokerage_account_id,
+                        &cash.brokerage_transaction_id,
+                        cash.kind.clone(),
+                        cash.amount,
+                        &cash.currency,
+                        cash.timestamp_ms,
+                        security_id,
+                        &cash.description,
+                    );
+                    writer
+                        .add_cash_transaction(cash_transaction, db, session.clone())
+                        .await?;
+                }
+            }
+        }
     }
 
-    pub fn security_id(mut self, id: ObjectId) -> Self {
-        self.security_id = Some(id);
-        self
-    }
+    Ok(())
+}
 
-    pub fn side(mut self, side: TradeSide) -> Self {
-        self.side = Some(side);
-        self
-    }
+/// Adds the brokerage account if needed, then adds its parsed activities to the writer.
+///
+/// Returns the id of the brokerage account.
+async fn add_account_activities(
+    writer: &mut BatchWriter,
+    db: &Database,
+    session: Option<Arc<Mutex<ClientSession>>>,
+    brokerage_id: &str,
+    account_activities: &AccountActivities,
+    write_batch_size: usize,
+) -> Result<ObjectId> {
+    let brokerage_account = maybe_add_brokerage_account(
+        db,
+        session.clone(),
+        brokerage_id,
+        &account_activities.account_id,
+    )
+    .await?;
 
-    pub async fn insert(
-        self,
-        db: &Database,
-        session: Option<Arc<Mutex<ClientSession>>>,
-    ) -> Result<()> {
-        let trade = TradeExecution::builder()
-            .brokerage_account_id(self.brokerage_account_id.unwrap())
-            .brokerage_execution_id(&self.brokerage_execution_id.unwrap())
-            .commission(self.commission.unwrap())
-            .execution_timestamp_ms(self.execution_timestamp_ms.unwrap())
-            .quantity(self.quantity.unwrap())
-            .price(self.price.unwrap())
-            .security_id(self.security_id.unwrap())
-            .side(self.side.unwrap())
-            .build()?;
+    info!(
+        "Importing {} activities for {} brokerage account: {}",
+        account_activities.activities.len(),
+        brokerage_id,
+        brokerage_account.account_id(),
+    );
 
-        trade.insert(db, session).await
-    }
+    add_activities(
+        writer,
+        db,
+        session,
+        brokerage_account.id(),
+        &account_activities.activities,
+        write_batch_size,
+    )
+    .await?;
+    Ok(brokerage_account.id())
 }
 
-pub async fn maybe_add_brokerage_account(
+/// Adds the brokerage account if needed, then writes its parsed activities.
+///
+/// The activities are written in bulk with a `BatchWriter`, `write_batch_size` at a time.
+/// Returns the id of the brokerage account.
+pub async fn write_account_activities(
     db: &Database,
     session: Option<Arc<Mutex<ClientSession>>>,
     brokerage_id: &str,
-    account_id: &str,
-) -> Result<BrokerageAccount> {
-    let brokerage_account =
-        BrokerageAccount::find_by_brokerage_and_account_id(db, brokerage_id, account_id).await?;
-    if brokerage_account.is_none() {
-        let new_account = BrokerageAccount::new(brokerage_id, account_id);
-        new_account.insert(db, session).await?;
-        info!(
-            "Added new brokerage account: {} at {}",
-            account_id, brokerage_id
-        );
-        Ok(new_account)
-    } else {
-        debug!(
-            "Brokerage account already exists: {} at {}",
-            account_id, brokerage_id
-        );
-        Ok(brokerage_account.unwrap())
-    }
+    account_activities: &AccountActivities,
+    write_batch_size: usize,
+) -> Result<ObjectId> {
+    let mut writer = BatchWriter::new().batch_size(write_batch_size)?;
+    let brokerage_account_id = add_account_activities(
+        &mut writer,
+        db,
+        session.clone(),
+        brokerage_id,
+        account_activities,
+        write_batch_size,
+    )
+    .await?;
+    writer.flush(db, session).await?;
+    Ok(brokerage_account_id)
 }
 
-pub async fn maybe_add_security(
+/// Writes the parsed activities of each account of a statement, along with the securities of
+/// the statement's holdings, which exist even if no activity refers to them.
+///
+/// Everything is written in bulk with one `BatchWriter`, `write_batch_size` activities at a time.
+pub async fn write_statement_activities(
     db: &Database,
     session: Option<Arc<Mutex<ClientSession>>>,
-    ticker: &str,
-    listing_exchange: &str,
-    ibkr_conid: Option<u32>,
-) -> Result<Security> {
-    let security = Security::find_by_ticker_and_exchange(db, ticker, listing_exchange).await?;
-    if security.is_none() {
-        let new_security = Security::new(SecurityType::Stock, ticker, listing_exchange, ibkr_conid);
-        new_security.insert(db, session).await?;
-        info!("Added security: {} on {}", ticker, listing_exchange);
-        Ok(new_security)
-    } else {
-        debug!(
-            "security already exists ({} at {}), skipping db insert",
-            ticker, listing_exchange
-        );
-        Ok(security.unwrap())
+    brokerage_id: &str,
+    accounts: &[AccountActivities],
+    held_tickers: &[&str],
+    write_batch_size: usize,
+) -> Result<()> {
+    let mut writer = BatchWriter::new().batch_size(write_batch_size)?;
+    writer
+        .preload_securities(db, session.clone(), held_tickers)
+        .await?;
+    for ticker in held_tickers {
+        writer
+            .security_by_ticker(db, session.clone(), ticker)
+            .await?;
+    }
+
+    for account_activities in accounts {
+        add_account_activities(
+            &mut writer,
+            db,
+            session.clone(),
+            brokerage_id,
+            account_activities,
+            write_batch_size,
+        )
+        .await?;
     }
+    writer.flush(db, session).await
 }
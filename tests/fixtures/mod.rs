@@ -2,7 +2,16 @@ use std::path::PathBuf;
 
 use anyhow::Result;
 use brokerage_statement_importer::{
-    ibkr_flex_statement_importer::IbkrFlexStatementImporter, importer_registry::ImporterRegistry,
+    alpaca_json_importer::AlpacaJsonImporter, camt053_importer::Camt053Importer,
+    coinbase_csv_importer::CoinbaseCsvImporter, degiro_csv_importer::DegiroCsvImporter,
+    etrade_csv_importer::EtradeCsvImporter, fidelity_csv_importer::FidelityCsvImporter,
+    fix_log_importer::FixLogImporter, ibkr_flex_statement_importer::IbkrFlexStatementImporter,
+    importer_registry::ImporterRegistry, kraken_csv_importer::KrakenCsvImporter,
+    mt940_importer::Mt940Importer, ofx_importer::OfxImporter,
+    pdf_statement_importer::PdfStatementImporter, qif_importer::QifImporter,
+    questrade_csv_importer::QuestradeCsvImporter, robinhood_csv_importer::RobinhoodCsvImporter,
+    schwab_csv_importer::SchwabCsvImporter, tastytrade_csv_importer::TastytradeCsvImporter,
+    trading212_csv_importer::Trading212CsvImporter, vanguard_csv_importer::VanguardCsvImporter,
 };
 use mongodb::{Client, Database};
 use rstest::fixture;
@@ -10,10 +19,47 @@ use testcontainers_modules::{
     mongo::Mongo,
     testcontainers::{ContainerAsync, runners::AsyncRunner},
 };
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
 
 pub const IBKR_ACCOUNT_ID: &str = "U1234567";
 pub const IBKR_SINGLE_TRADE_TICKER: &str = "ARGX";
 pub const IBKR_SINGLE_TRADE_BROKERAGE_EXECUTION_ID: &str = "0000edae.680b59d1.01.01";
+pub const SCHWAB_ACCOUNT_ID: &str = "XXXX-1234";
+pub const FIDELITY_INDIVIDUAL_ACCOUNT_ID: &str = "X12345678";
+pub const FIDELITY_ROTH_IRA_ACCOUNT_ID: &str = "Z98765432";
+pub const VANGUARD_ACCOUNT_ID: &str = "87654321";
+pub const ETRADE_ACCOUNT_ID: &str = "####5678";
+pub const ROBINHOOD_ACCOUNT_ID: &str = "5RH12345";
+pub const TASTYTRADE_ACCOUNT_ID: &str = "5WT00001";
+pub const OFX_BROKERAGE_ID: &str = "ExampleBroker";
+pub const OFX_ACCOUNT_ID: &str = "OFX-100200";
+pub const QIF_ACCOUNT_NAME: &str = "Retirement Brokerage";
+pub const DEGIRO_ACCOUNT_ID: &str = "DG-12345678";
+pub const TRADING212_ACCOUNT_ID: &str = "T212-ISA-001";
+pub const QUESTRADE_TFSA_ACCOUNT_ID: &str = "51234567";
+pub const QUESTRADE_RRSP_ACCOUNT_ID: &str = "28765432";
+pub const COINBASE_ACCOUNT_ID: &str = "6f1c2a4e-0b7d-4d8e-9a51-3c2e8f1b7a90";
+pub const KRAKEN_ACCOUNT_ID: &str = "AA12 N84G 7QJW 3XLC";
+pub const ALPACA_ACCOUNT_ID: &str = "PA3K7Q9ZJ2XN";
+pub const FIX_BROKERAGE_ID: &str = "prime-broker";
+pub const FIX_ACCOUNT_ID: &str = "U7654321";
+pub const SCHWAB_PDF_ACCOUNT_ID: &str = "1234-5678";
+pub const SCHWAB_PDF_PASSWORD: &str = "investor-2025";
+pub const CAMT053_BROKERAGE_ID: &str = "PRVBCHZZ";
+pub const CAMT053_ACCOUNT_ID: &str = "CH9300762011623852957";
+pub const MT940_BROKERAGE_ID: &str = "DEUTDEFF";
+pub const MT940_ACCOUNT_ID: &str = "DE89370400440532013000";
+pub const ACME_BROKERAGE_ID: &str = "acme-bank";
+pub const ACME_ACCOUNT_ID: &str = "DE-4711";
+pub const NORTHWIND_BROKERAGE_ID: &str = "northwind";
+pub const FLEX_WEB_SERVICE_TOKEN: &str = "123456789012345678901234";
+pub const FLEX_WEB_SERVICE_QUERY_ID: &str = "987654";
+pub const FLEX_WEB_SERVICE_REFERENCE_CODE: &str = "1234567890";
+/// Reference code for which the stub returns an HTML error page instead of a statement.
+pub const FLEX_WEB_SERVICE_HTML_REFERENCE_CODE: &str = "1234567899";
 
 pub struct DbDesc {
     pub _client: Client,
@@ -121,9 +167,307 @@ pub fn single_trade_flex_pathbuf() -> PathBuf {
     file
 }
 
+pub fn data_file_pathbuf(file_name: &str) -> PathBuf {
+    std::env::current_dir()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join("data")
+        .join(file_name)
+}
+
+#[fixture]
+pub fn schwab_transactions_pathbuf() -> PathBuf {
+    data_file_pathbuf("schwab_transactions.csv")
+}
+
+#[fixture]
+pub fn fidelity_history_pathbuf() -> PathBuf {
+    data_file_pathbuf("History_for_Account_Multi.csv")
+}
+
+#[fixture]
+pub fn vanguard_download_pathbuf() -> PathBuf {
+    data_file_pathbuf("vanguard_download.csv")
+}
+
+#[fixture]
+pub fn etrade_transactions_pathbuf() -> PathBuf {
+    data_file_pathbuf("etrade_transactions.csv")
+}
+
+#[fixture]
+pub fn robinhood_activity_pathbuf() -> PathBuf {
+    data_file_pathbuf("robinhood_activity.csv")
+}
+
+#[fixture]
+pub fn tastytrade_transactions_pathbuf() -> PathBuf {
+    data_file_pathbuf("tastytrade_transactions.csv")
+}
+
+#[fixture]
+pub fn ofx_statement_pathbuf() -> PathBuf {
+    data_file_pathbuf("ofx_investment_statement.ofx")
+}
+
+#[fixture]
+pub fn qfx_statement_pathbuf() -> PathBuf {
+    data_file_pathbuf("ofx_investment_statement.qfx")
+}
+
+#[fixture]
+pub fn qif_investments_pathbuf() -> PathBuf {
+    data_file_pathbuf("quicken_investments.qif")
+}
+
+#[fixture]
+pub fn degiro_transactions_pathbuf() -> PathBuf {
+    data_file_pathbuf("degiro_transactions.csv")
+}
+
+#[fixture]
+pub fn degiro_account_statement_pathbuf() -> PathBuf {
+    data_file_pathbuf("degiro_account_statement.csv")
+}
+
+#[fixture]
+pub fn trading212_history_pathbuf() -> PathBuf {
+    data_file_pathbuf("trading212_history.csv")
+}
+
+#[fixture]
+pub fn questrade_activities_pathbuf() -> PathBuf {
+    data_file_pathbuf("questrade_activities.csv")
+}
+
+#[fixture]
+pub fn questrade_activities_xlsx_pathbuf() -> PathBuf {
+    data_file_pathbuf("questrade_activities.xlsx")
+}
+
+#[fixture]
+pub fn coinbase_transactions_pathbuf() -> PathBuf {
+    data_file_pathbuf("coinbase_transactions.csv")
+}
+
+#[fixture]
+pub fn kraken_ledgers_pathbuf() -> PathBuf {
+    data_file_pathbuf("kraken_ledgers.csv")
+}
+
+#[fixture]
+pub fn kraken_trades_pathbuf() -> PathBuf {
+    data_file_pathbuf("kraken_trades.csv")
+}
+
+#[fixture]
+pub fn alpaca_activities_pathbuf() -> PathBuf {
+    data_file_pathbuf("alpaca_activities.json")
+}
+
+#[fixture]
+pub fn fix_drop_copy_pathbuf() -> PathBuf {
+    data_file_pathbuf("fix_drop_copy.log")
+}
+
+#[fixture]
+pub fn schwab_statement_pdf_pathbuf() -> PathBuf {
+    data_file_pathbuf("schwab_statement.pdf")
+}
+
+#[fixture]
+pub fn schwab_statement_protected_pdf_pathbuf() -> PathBuf {
+    data_file_pathbuf("schwab_statement_protected.pdf")
+}
+
+#[fixture]
+pub fn camt053_statement_pathbuf() -> PathBuf {
+    data_file_pathbuf("camt053_statement.xml")
+}
+
+#[fixture]
+pub fn mt940_statement_pathbuf() -> PathBuf {
+    data_file_pathbuf("mt940_statement.sta")
+}
+
+#[fixture]
+pub fn acme_depot_mapping_pathbuf() -> PathBuf {
+    data_file_pathbuf("acme_depot_mapping.toml")
+}
+
+#[fixture]
+pub fn acme_depot_csv_pathbuf() -> PathBuf {
+    data_file_pathbuf("acme_2025-03.csv")
+}
+
+#[fixture]
+pub fn northwind_mapping_pathbuf() -> PathBuf {
+    data_file_pathbuf("northwind_mapping.yaml")
+}
+
+#[fixture]
+pub fn northwind_activity_csv_pathbuf() -> PathBuf {
+    data_file_pathbuf("northwind_activity.csv")
+}
+
+#[fixture]
+pub fn trade_confirmation_eml_pathbuf() -> PathBuf {
+    data_file_pathbuf("trade_confirmation.eml")
+}
+
+#[fixture]
+pub fn confirmations_mbox_pathbuf() -> PathBuf {
+    data_file_pathbuf("confirmations.mbox")
+}
+
+#[fixture]
+pub fn confirmations_maildir_pathbuf() -> PathBuf {
+    data_file_pathbuf("confirmations_maildir")
+}
+
+#[fixture]
+pub fn statements_zip_pathbuf() -> PathBuf {
+    data_file_pathbuf("statements_2025.zip")
+}
+
+#[fixture]
+pub fn cash_statements_tar_gz_pathbuf() -> PathBuf {
+    data_file_pathbuf("cash_statements_2025-03.tar.gz")
+}
+
+#[fixture]
+pub fn single_trade_flex_gz_pathbuf() -> PathBuf {
+    data_file_pathbuf("ibkr_flex_single_trade.xml.gz")
+}
+
+#[fixture]
+pub fn single_trade_flex_prolog_pathbuf() -> PathBuf {
+    data_file_pathbuf("ibkr_flex_single_trade_prolog.xml")
+}
+
+#[fixture]
+pub fn acme_depot_windows_1252_csv_pathbuf() -> PathBuf {
+    data_file_pathbuf("acme_2025-04.csv")
+}
+
+#[fixture]
+pub fn acme_depot_utf16_csv_pathbuf() -> PathBuf {
+    data_file_pathbuf("acme_2025-05.csv")
+}
+
+#[fixture]
+pub fn generic_transactions_mapping_pathbuf() -> PathBuf {
+    data_file_pathbuf("generic_transactions_mapping.toml")
+}
+
 #[fixture]
 pub fn registry() -> ImporterRegistry {
     let mut registry = ImporterRegistry::new();
     registry.register_importer(Box::new(IbkrFlexStatementImporter::new()));
+    registry.register_importer(Box::new(SchwabCsvImporter::new()));
+    registry.register_importer(Box::new(FidelityCsvImporter::new()));
+    registry.register_importer(Box::new(VanguardCsvImporter::new()));
+    registry.register_importer(Box::new(EtradeCsvImporter::new()));
+    registry.register_importer(Box::new(RobinhoodCsvImporter::new(ROBINHOOD_ACCOUNT_ID)));
+    registry.register_importer(Box::new(TastytradeCsvImporter::new(TASTYTRADE_ACCOUNT_ID)));
+    registry.register_importer(Box::new(OfxImporter::new()));
+    registry.register_importer(Box::new(QifImporter::new()));
+    registry.register_importer(Box::new(DegiroCsvImporter::new(DEGIRO_ACCOUNT_ID)));
+    registry.register_importer(Box::new(Trading212CsvImporter::new(TRADING212_ACCOUNT_ID)));
+    registry.register_importer(Box::new(QuestradeCsvImporter::new()));
+    registry.register_importer(Box::new(CoinbaseCsvImporter::new()));
+    registry.register_importer(Box::new(KrakenCsvImporter::new(KRAKEN_ACCOUNT_ID)));
+    registry.register_importer(Box::new(AlpacaJsonImporter::new(ALPACA_ACCOUNT_ID)));
+    registry.register_importer(Box::new(FixLogImporter::new(FIX_BROKERAGE_ID)));
+    registry.register_importer(Box::new(
+        PdfStatementImporter::new().password(SCHWAB_PDF_PASSWORD),
+    ));
+    registry.register_importer(Box::new(Camt053Importer::new()));
+    registry.register_importer(Box::new(Mt940Importer::new()));
+    registry
+        .register_csv_mapping_files(&[acme_depot_mapping_pathbuf(), northwind_mapping_pathbuf()])
+        .expect("CSV mappings should load");
     registry
 }
+
+/// Minimal stand-in for the IBKR Flex Web Service.
+///
+/// `SendRequest` hands out a fixed reference code. The first `GetStatement` call reports that the
+/// statement is still being generated; subsequent calls return the single trade Flex statement.
+pub struct FlexWebServiceStub {
+    pub base_url: String,
+}
+
+impl FlexWebServiceStub {
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let base_url = format!("http://{}/FlexWebService", listener.local_addr()?);
+
+        tokio::spawn(async move {
+            let mut get_statement_calls = 0;
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buffer = vec![0u8; 4096];
+                let len = stream.read(&mut buffer).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buffer[..len]).to_string();
+                let token_param = format!("t={}", FLEX_WEB_SERVICE_TOKEN);
+
+                let body = if !request.contains(&token_param) {
+                    flex_response(
+                        "Fail",
+                        "<ErrorCode>1012</ErrorCode><ErrorMessage>Token has expired.</ErrorMessage>",
+                    )
+                } else if request.starts_with("GET /FlexWebService/SendRequest") {
+                    flex_response(
+                        "Success",
+                        &format!(
+                            "<ReferenceCode>{}</ReferenceCode>",
+                            FLEX_WEB_SERVICE_REFERENCE_CODE
+                        ),
+                    )
+                } else if request.starts_with("GET /FlexWebService/GetStatement")
+                    && request.contains(&format!("q={}", FLEX_WEB_SERVICE_HTML_REFERENCE_CODE))
+                {
+                    "<html><body><h1>Service Unavailable</h1></body></html>".to_owned()
+                } else if request.starts_with("GET /FlexWebService/GetStatement") {
+                    get_statement_calls += 1;
+                    if get_statement_calls == 1 {
+                        flex_response(
+                            "Warn",
+                            "<ErrorCode>1019</ErrorCode><ErrorMessage>Statement generation in progress. Please try again shortly.</ErrorMessage>",
+                        )
+                    } else {
+                        single_trade_flex().to_owned()
+                    }
+                } else {
+                    flex_response(
+                        "Fail",
+                        "<ErrorCode>1020</ErrorCode><ErrorMessage>Invalid request.</ErrorMessage>",
+                    )
+                };
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        Ok(Self { base_url })
+    }
+}
+
+fn flex_response(status: &str, details: &str) -> String {
+    format!(
+        "<FlexStatementResponse timestamp='26 April, 2025 01:34 PM EDT'><Status>{}</Status>{}</FlexStatementResponse>",
+        status, details
+    )
+}
+
+#[fixture]
+pub async fn flex_web_service_stub() -> Result<FlexWebServiceStub> {
+    FlexWebServiceStub::start().await
+}
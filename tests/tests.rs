@@ -1,16 +1,73 @@
 mod fixtures;
 
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-use crate::ibkr_flex_statement_importer::IBKR_BROKERAGE_ID;
+use crate::{
+    alpaca_json_importer::ALPACA_BROKERAGE_ID, coinbase_csv_importer::COINBASE_BROKERAGE_ID,
+    degiro_csv_importer::DEGIRO_BROKERAGE_ID, etrade_csv_importer::ETRADE_BROKERAGE_ID,
+    fidelity_csv_importer::FIDELITY_BROKERAGE_ID, ibkr_flex_statement_importer::IBKR_BROKERAGE_ID,
+    questrade_csv_importer::QUESTRADE_BROKERAGE_ID, robinhood_csv_importer::ROBINHOOD_BROKERAGE_ID,
+    schwab_csv_importer::SCHWAB_BROKERAGE_ID, tastytrade_csv_importer::TASTYTRADE_BROKERAGE_ID,
+    trading212_csv_importer::TRADING212_BROKERAGE_ID, vanguard_csv_importer::VANGUARD_BROKERAGE_ID,
+};
 use anyhow::Result;
 use brokerage_db::{
-    account::BrokerageAccount, security::Security, trade_execution::TradeExecution,
+    account::BrokerageAccount,
+    security::{Security, SecurityType},
+    trade_execution::{TradeExecution, TradeSide},
+};
+use brokerage_statement_importer::{
+    account_type::{AccountType, BrokerageAccountType},
+    activity::{Activity, TradeActivity},
+    alpaca_json_importer::AlpacaJsonImporter,
+    archive::{is_archive, is_streamable, read_entries, stream_entries},
+    camt053_importer::{Camt053Importer, bank_transaction_kind},
+    cash_transaction::{CashTransaction, CashTransactionKind},
+    coinbase_csv_importer::CoinbaseCsvImporter,
+    configurable_csv_importer::{ConfigurableCsvImporter, mapping::CsvMapping},
+    content_match::{ContentMatch, MatchConfidence},
+    crypto_asset::CRYPTO_LISTING_EXCHANGE,
+    degiro_csv_importer::{DegiroCsvImporter, DegiroCsvKind, ISIN_LISTING_EXCHANGE},
+    email_ingestion::{
+        EmailIngestSummary, Mailbox, ingest_mailbox, parse_email, processed_email::ProcessedEmail,
+    },
+    etrade_csv_importer::EtradeCsvImporter,
+    fidelity_csv_importer::FidelityCsvImporter,
+    file_selection::FileSelection,
+    fix_log_importer::FixLogImporter,
+    ibkr_flex_statement_importer::{
+        IbkrFlexStatementImporter, flex_stream::FlexTradeReader,
+        flex_web_service::FlexWebServiceFetcher,
+    },
+    imported_file::ImportedFile,
+    importer_registry::{DEFAULT_WRITE_BATCH_SIZE, ImporterRegistry},
+    kraken_csv_importer::{
+        KRAKEN_BROKERAGE_ID, KrakenCsvImporter, normalize_kraken_asset, split_kraken_pair,
+    },
+    mt940_importer::{Mt940Importer, parse_statement_line},
+    ofx_importer::{OfxImporter, ofx_document::parse_ofx_timestamp_ms},
+    option_contract::OptionRight,
+    path_match::PathMatch,
+    pdf_statement_importer::{PdfPosition, PdfStatementImporter},
+    qif_importer::{QIF_BROKERAGE_ID, QifDateFormat, QifImporter, parse_qif_date},
+    questrade_csv_importer::{QuestradeAccount, QuestradeCsvImporter},
+    robinhood_csv_importer::{RobinhoodCsvImporter, parse_option_description},
+    schwab_csv_importer::SchwabCsvImporter,
+    statement_importer::StatementImporter,
+    tastytrade_csv_importer::TastytradeCsvImporter,
+    text_encoding::{decode_text, is_utf8, skip_xml_prolog, trim_partial_utf8},
+    trading212_csv_importer::Trading212CsvImporter,
+    vanguard_csv_importer::VanguardCsvImporter,
+    *,
 };
-use brokerage_statement_importer::{importer_registry::ImporterRegistry, *};
 use fixtures::*;
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, oid::ObjectId};
 use rstest::rstest;
+use tokio::sync::Mutex;
 use tracing_test::traced_test;
 
 #[test]
@@ -144,3 +201,3569 @@ async fn test_import_file_ibkr_single_trade_execution(
 
     Ok(())
 }
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_fetch_flex_statement_from_web_service(
+    #[future] flex_web_service_stub: Result<FlexWebServiceStub>,
+    single_trade_flex: &str,
+) -> Result<()> {
+    let stub = flex_web_service_stub?;
+
+    let fetcher = FlexWebServiceFetcher::new(FLEX_WEB_SERVICE_TOKEN, FLEX_WEB_SERVICE_QUERY_ID)
+        .base_url(&stub.base_url)
+        .poll_interval(Duration::from_millis(10));
+
+    let (reference_code, statement) = fetcher.fetch_statement().await?;
+    assert_eq!(reference_code, FLEX_WEB_SERVICE_REFERENCE_CODE);
+    assert_eq!(statement, single_trade_flex);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_fetch_flex_statement_with_bad_token_fails(
+    #[future] flex_web_service_stub: Result<FlexWebServiceStub>,
+) -> Result<()> {
+    let stub = flex_web_service_stub?;
+
+    let fetcher = FlexWebServiceFetcher::new("bad-token", FLEX_WEB_SERVICE_QUERY_ID)
+        .base_url(&stub.base_url)
+        .poll_interval(Duration::from_millis(10));
+
+    assert!(fetcher.fetch_statement().await.is_err());
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_get_flex_statement_rejects_non_statement(
+    #[future] flex_web_service_stub: Result<FlexWebServiceStub>,
+) -> Result<()> {
+    let stub = flex_web_service_stub?;
+
+    let fetcher = FlexWebServiceFetcher::new(FLEX_WEB_SERVICE_TOKEN, FLEX_WEB_SERVICE_QUERY_ID)
+        .base_url(&stub.base_url);
+
+    assert!(
+        fetcher
+            .get_statement(FLEX_WEB_SERVICE_HTML_REFERENCE_CODE)
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_fetch_and_import_flex_statement_from_web_service(
+    #[future] db_desc: Result<DbDesc>,
+    #[future] flex_web_service_stub: Result<FlexWebServiceStub>,
+    registry: ImporterRegistry,
+    single_trade_flex: &str,
+) -> Result<()> {
+    let db_desc = db_desc?;
+    let stub = flex_web_service_stub?;
+    let download_dir = tempfile::tempdir()?;
+
+    let saved_path = FlexWebServiceFetcher::new(FLEX_WEB_SERVICE_TOKEN, FLEX_WEB_SERVICE_QUERY_ID)
+        .base_url(&stub.base_url)
+        .poll_interval(Duration::from_millis(10))
+        .download_dir(download_dir.path())
+        .fetch_and_import(&registry, &db_desc.db, None)
+        .await?;
+
+    // Verify a copy of the statement was saved.
+    assert_eq!(std::fs::read_to_string(&saved_path)?, single_trade_flex);
+
+    // Verify trade execution was added.
+    let trade_execution = TradeExecution::find_by_brokerage_execution_id(
+        &db_desc.db,
+        IBKR_SINGLE_TRADE_BROKERAGE_EXECUTION_ID,
+    )
+    .await?;
+    assert!(trade_execution.is_some());
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_schwab_transactions_csv(schwab_transactions_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(schwab_transactions_pathbuf)?;
+    let account_activities = SchwabCsvImporter::new().parse(&content)?;
+
+    assert_eq!(account_activities.account_id, SCHWAB_ACCOUNT_ID);
+    assert_eq!(account_activities.activities.len(), 8);
+
+    let trades = account_activities
+        .activities
+        .iter()
+        .filter_map(|a| match a {
+            Activity::Trade(trade) => Some(trade),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(trades.len(), 3);
+    assert_eq!(trades[0].ticker, "MSFT");
+    assert_eq!(trades[0].quantity, 10.0);
+    assert_eq!(trades[0].price, 391.85);
+    assert_eq!(trades[1].side, TradeSide::Sell);
+    assert_eq!(trades[1].commission, 0.02);
+    assert_eq!(trades[2].ticker, "SCHD");
+    assert_eq!(trades[2].quantity, 0.4512);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_schwab_transactions(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    schwab_transactions_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![schwab_transactions_pathbuf])
+        .await?;
+
+    // Verify the account was added.
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        SCHWAB_BROKERAGE_ID,
+        SCHWAB_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+
+    // Verify the reinvested shares were recorded as a trade.
+    let securities = Security::find_by_ticker(&db_desc.db, "SCHD").await?;
+    assert_eq!(securities.len(), 1);
+
+    // Verify the cash activity was recorded.
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    assert_eq!(cash_transactions.len(), 5);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_ticker_on_several_exchanges(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    schwab_transactions_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+    for listing_exchange in ["ARCA", "BATS"] {
+        Security::new(SecurityType::Stock, "SCHD", listing_exchange, None)
+            .insert(&db_desc.db, None)
+            .await?;
+    }
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![schwab_transactions_pathbuf])
+        .await?;
+
+    // The statement doesn't name an exchange, so its rows link to neither listing.
+    let unknown = Security::find_by_ticker_and_exchange(&db_desc.db, "SCHD", "UNKNOWN")
+        .await?
+        .expect("Security on an unknown exchange should exist");
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        SCHWAB_BROKERAGE_ID,
+        SCHWAB_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    let security_ids = cash_transactions
+        .iter()
+        .filter_map(CashTransaction::security_id)
+        .collect::<Vec<_>>();
+    assert!(!security_ids.is_empty());
+    assert!(security_ids.iter().all(|id| *id == unknown.id()));
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_fidelity_multi_account_history(fidelity_history_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(fidelity_history_pathbuf)?;
+    let accounts = FidelityCsvImporter::new().parse(&content)?;
+
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].account_id, FIDELITY_INDIVIDUAL_ACCOUNT_ID);
+    assert_eq!(accounts[1].account_id, FIDELITY_ROTH_IRA_ACCOUNT_ID);
+
+    // The core money market redemption is a sweep, not a trade.
+    assert_eq!(accounts[0].activities.len(), 2);
+    let Activity::Trade(sell) = &accounts[0].activities[1] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(sell.side, TradeSide::Sell);
+    assert_eq!(sell.quantity, 20.0);
+    assert_eq!(sell.commission, 0.03);
+
+    // Dividend, reinvestment trade and core money market dividend.
+    assert_eq!(accounts[1].activities.len(), 3);
+    assert!(matches!(accounts[1].activities[1], Activity::Trade(_)));
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_fidelity_history_account_names(fidelity_history_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(fidelity_history_pathbuf)?
+        .replace(&format!("\"{}\"", FIDELITY_INDIVIDUAL_ACCOUNT_ID), "\"\"")
+        .replace(&format!("\"{}\"", FIDELITY_ROTH_IRA_ACCOUNT_ID), "\"\"");
+
+    // Account names without a number fall back to the configured account id.
+    let accounts = FidelityCsvImporter::new()
+        .account_id(FIDELITY_INDIVIDUAL_ACCOUNT_ID)
+        .parse(&content)?;
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].account_id, FIDELITY_INDIVIDUAL_ACCOUNT_ID);
+    assert!(FidelityCsvImporter::new().parse(&content).is_err());
+
+    // Older downloads name the account number after the account name.
+    let content = content.replace(
+        "\"ROTH IRA\"",
+        &format!("\"ROTH IRA {}\"", FIDELITY_ROTH_IRA_ACCOUNT_ID),
+    );
+    let accounts = FidelityCsvImporter::new()
+        .account_id(FIDELITY_INDIVIDUAL_ACCOUNT_ID)
+        .parse(&content)?;
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[1].account_id, FIDELITY_ROTH_IRA_ACCOUNT_ID);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_fidelity_multi_account_history(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    fidelity_history_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![fidelity_history_pathbuf])
+        .await?;
+
+    // Verify both accounts were added.
+    for account_id in [FIDELITY_INDIVIDUAL_ACCOUNT_ID, FIDELITY_ROTH_IRA_ACCOUNT_ID] {
+        let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+            &db_desc.db,
+            FIDELITY_BROKERAGE_ID,
+            account_id,
+        )
+        .await?;
+        assert!(brokerage_account.is_some());
+    }
+
+    // Verify the core money market position was not added as a security.
+    assert!(
+        Security::find_by_ticker(&db_desc.db, "SPAXX")
+            .await?
+            .is_empty()
+    );
+    assert_eq!(Security::find_by_ticker(&db_desc.db, "VTI").await?.len(), 1);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_vanguard_download(vanguard_download_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(vanguard_download_pathbuf)?;
+    let statement = VanguardCsvImporter::new().parse(&content)?;
+
+    assert_eq!(statement.holdings.len(), 3);
+    assert_eq!(statement.holdings[0].symbol, "VFIAX");
+    assert_eq!(statement.holdings[0].shares, 14.296);
+
+    assert_eq!(statement.accounts.len(), 1);
+    assert_eq!(statement.accounts[0].account_id, VANGUARD_ACCOUNT_ID);
+
+    // The settlement fund sweep is skipped.
+    let activities = &statement.accounts[0].activities;
+    assert_eq!(activities.len(), 5);
+
+    // Fractional shares and NAV prices are kept exactly as reported.
+    let Activity::Trade(buy) = &activities[0] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(buy.quantity, "1.951".parse::<f64>()?);
+    assert_eq!(buy.price, "512.34".parse::<f64>()?);
+
+    let Activity::Trade(reinvestment) = &activities[3] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(reinvestment.side, TradeSide::Buy);
+    assert_eq!(reinvestment.quantity, "0.052".parse::<f64>()?);
+    assert_eq!(reinvestment.price, "498.0769".parse::<f64>()?);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_vanguard_download(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    vanguard_download_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![vanguard_download_pathbuf])
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        VANGUARD_BROKERAGE_ID,
+        VANGUARD_ACCOUNT_ID,
+    )
+    .await?;
+    assert!(brokerage_account.is_some());
+
+    // Verify held funds were added as securities, including those without trades.
+    assert_eq!(
+        Security::find_by_ticker(&db_desc.db, "VMFXX").await?.len(),
+        1
+    );
+    let securities = Security::find_by_ticker(&db_desc.db, "VFIAX").await?;
+    assert_eq!(securities.len(), 1);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_etrade_transactions_with_share_deposits(
+    etrade_transactions_pathbuf: PathBuf,
+) -> Result<()> {
+    let content = std::fs::read_to_string(etrade_transactions_pathbuf)?;
+    let account_activities = EtradeCsvImporter::new().parse(&content)?;
+
+    assert_eq!(account_activities.account_id, ETRADE_ACCOUNT_ID);
+    assert_eq!(account_activities.activities.len(), 6);
+
+    // RSU shares are bought at the vest-date fair market value from the description.
+    let Activity::Trade(rsu_vest) = &account_activities.activities[1] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(rsu_vest.side, TradeSide::Buy);
+    assert_eq!(rsu_vest.quantity, 55.0);
+    assert_eq!(rsu_vest.price, 148.37);
+
+    // Without a fair market value, ESPP shares fall back to the discounted price on the row.
+    let Activity::Trade(espp_purchase) = &account_activities.activities[2] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(espp_purchase.price, 126.5);
+
+    // A configured fair market value takes precedence over the discounted price.
+    let account_activities = EtradeCsvImporter::new()
+        .vest_fair_market_value(
+            "ACME",
+            chrono::NaiveDate::from_ymd_opt(2025, 3, 31).unwrap(),
+            148.82,
+        )
+        .parse(&content)?;
+    let Activity::Trade(espp_purchase) = &account_activities.activities[2] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(espp_purchase.price, 148.82);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_etrade_share_deposit_without_fair_market_value_fails() -> Result<()> {
+    let content = "Activity/Trade Date,Transaction Date,Settlement Date,Activity Type,Description,Symbol,Cusip,Quantity #,Price $,Amount $,Commission,Category,Note\n\
+        04/15/25,04/15/25,04/15/25,Receive,ACME CORP COM RSU RELEASE,ACME,004563101,55,0,0.00,0.00,--,--\n";
+
+    let importer = EtradeCsvImporter::new().account_id(ETRADE_ACCOUNT_ID);
+    assert!(importer.parse(content).is_err());
+
+    // A configured fair market value fills the gap.
+    let importer = importer.vest_fair_market_value(
+        "ACME",
+        chrono::NaiveDate::from_ymd_opt(2025, 4, 15).unwrap(),
+        148.37,
+    );
+    let account_activities = importer.parse(content)?;
+    let Activity::Trade(rsu_vest) = &account_activities.activities[0] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(rsu_vest.price, 148.37);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_etrade_transactions(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    etrade_transactions_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![etrade_transactions_pathbuf])
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        ETRADE_BROKERAGE_ID,
+        ETRADE_ACCOUNT_ID,
+    )
+    .await?;
+    assert!(brokerage_account.is_some());
+    assert_eq!(
+        Security::find_by_ticker(&db_desc.db, "ACME").await?.len(),
+        1
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_robinhood_option_description() {
+    let contract = parse_option_description("Option Expiration for TSLA 3/21/2025 Put $200.00")
+        .expect("option contract");
+    assert_eq!(contract.underlying, "TSLA");
+    assert_eq!(contract.right, OptionRight::Put);
+    assert_eq!(contract.strike, 200.0);
+    assert_eq!(contract.occ_symbol(), "TSLA  250321P00200000");
+
+    assert!(parse_option_description("Apple").is_none());
+}
+
+#[rstest]
+fn test_parse_robinhood_activity(robinhood_activity_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(robinhood_activity_pathbuf)?;
+    let account_activities = RobinhoodCsvImporter::new(ROBINHOOD_ACCOUNT_ID).parse(&content)?;
+
+    assert_eq!(account_activities.account_id, ROBINHOOD_ACCOUNT_ID);
+    assert_eq!(account_activities.activities.len(), 8);
+
+    let trades = account_activities
+        .activities
+        .iter()
+        .filter_map(|a| match a {
+            Activity::Trade(trade) => Some(trade),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(trades.len(), 4);
+
+    assert_eq!(trades[0].ticker, "AAPL");
+    assert_eq!(trades[0].commission, 0.0);
+
+    // Option trades are recorded against the OCC symbol, with the regulatory fees split out.
+    assert_eq!(trades[1].ticker, "AAPL  250516C00210000");
+    assert_eq!(trades[1].price, 3.45);
+    assert_eq!(trades[1].commission, 0.04);
+
+    assert_eq!(trades[2].side, TradeSide::Sell);
+    assert_eq!(trades[2].commission, 0.03);
+
+    // The short put expired, closing the position with a zero-priced buy.
+    assert_eq!(trades[3].ticker, "TSLA  250321P00200000");
+    assert_eq!(trades[3].side, TradeSide::Buy);
+    assert_eq!(trades[3].price, 0.0);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_robinhood_activity(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    robinhood_activity_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![robinhood_activity_pathbuf])
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        ROBINHOOD_BROKERAGE_ID,
+        ROBINHOOD_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    assert_eq!(cash_transactions.len(), 4);
+    assert_eq!(
+        Security::find_by_ticker(&db_desc.db, "AAPL  250516C00210000")
+            .await?
+            .len(),
+        1
+    );
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_tastytrade_transactions(tastytrade_transactions_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(tastytrade_transactions_pathbuf)?;
+    let account_activities = TastytradeCsvImporter::new(TASTYTRADE_ACCOUNT_ID).parse(&content)?;
+
+    // Three trades, two fee records and two money movements.
+    assert_eq!(account_activities.activities.len(), 7);
+
+    let Activity::Trade(put_sale) = &account_activities.activities[0] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(put_sale.ticker, "SPY   250516P00500000");
+    assert_eq!(put_sale.side, TradeSide::Sell);
+    assert_eq!(put_sale.quantity, 2.0);
+    assert_eq!(put_sale.price, 4.25);
+    assert_eq!(put_sale.commission, 2.0);
+
+    // Regulatory fees are kept apart from the commission.
+    let Activity::Cash(put_sale_fees) = &account_activities.activities[1] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(put_sale_fees.kind, CashTransactionKind::Fee);
+    assert_eq!(put_sale_fees.amount, -0.28);
+    assert_eq!(
+        put_sale_fees.ticker.as_deref(),
+        Some("SPY   250516P00500000")
+    );
+
+    let Activity::Trade(expiration) = &account_activities.activities[4] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(expiration.ticker, "QQQ   250422C00470000");
+    assert_eq!(expiration.price, 0.0);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_tastytrade_transactions(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    tastytrade_transactions_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![tastytrade_transactions_pathbuf])
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        TASTYTRADE_BROKERAGE_ID,
+        TASTYTRADE_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    assert_eq!(cash_transactions.len(), 4);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_ofx_sgml_statement(ofx_statement_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(ofx_statement_pathbuf)?;
+    let statement = OfxImporter::new().parse(&content)?;
+
+    assert_eq!(statement.brokerage_id, OFX_BROKERAGE_ID);
+    assert_eq!(statement.securities.len(), 3);
+    // The treasury note has no ticker, so it is stored under its CUSIP.
+    assert_eq!(statement.securities[2].stored_ticker(), "912828YK0");
+    assert_eq!(statement.positions.len(), 2);
+    assert_eq!(statement.positions[0].ticker, "MSFT");
+    assert_eq!(statement.positions[1].ticker, "912828YK0");
+
+    assert_eq!(statement.accounts.len(), 1);
+    let account = &statement.accounts[0];
+    assert_eq!(account.account_id, OFX_ACCOUNT_ID);
+    // Two trades, a dividend, a reinvestment (income and buy) and a deposit.
+    assert_eq!(account.activities.len(), 6);
+
+    let Activity::Trade(buy) = &account.activities[0] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(buy.brokerage_execution_id, "T20250402-1");
+    assert_eq!(buy.ticker, "MSFT");
+    assert_eq!(buy.side, TradeSide::Buy);
+    assert_eq!(buy.quantity, 10.0);
+    assert_eq!(buy.price, 388.45);
+    assert_eq!(buy.commission, 0.02);
+    // 2025-04-02 14:30 EDT
+    assert_eq!(buy.execution_timestamp_ms, 1743618600000);
+
+    let Activity::Trade(sell) = &account.activities[1] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(sell.ticker, "VTSAX");
+    assert_eq!(sell.side, TradeSide::Sell);
+    assert_eq!(sell.quantity, 25.125);
+
+    let Activity::Cash(reinvested_income) = &account.activities[3] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(
+        reinvested_income.kind,
+        CashTransactionKind::CapitalGainDistribution
+    );
+    assert_eq!(reinvested_income.amount, 52.10);
+    assert_eq!(reinvested_income.description, "REINVEST VTSAX & CAP GAIN");
+
+    let Activity::Cash(deposit) = &account.activities[5] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(deposit.kind, CashTransactionKind::Deposit);
+    assert_eq!(deposit.amount, 5000.0);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_ofx_timestamp() -> Result<()> {
+    assert_eq!(parse_ofx_timestamp_ms("20250415")?, 1744675200000);
+    assert_eq!(
+        parse_ofx_timestamp_ms("20250415093000.000[-4:EDT]")?,
+        1744723800000
+    );
+    assert!(parse_ofx_timestamp_ms("2025041").is_err());
+    // Byte 8 falls inside the multi-byte character.
+    assert!(parse_ofx_timestamp_ms("2025041\u{e9}093000").is_err());
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_ofx_xml_statement(qfx_statement_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(qfx_statement_pathbuf)?;
+    let statement = OfxImporter::new().parse(&content)?;
+
+    assert_eq!(statement.brokerage_id, OFX_BROKERAGE_ID);
+    assert_eq!(statement.accounts[0].account_id, "OFX-300400");
+
+    let Activity::Trade(sell) = &statement.accounts[0].activities[0] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(sell.ticker, "NVDA");
+    assert_eq!(sell.side, TradeSide::Sell);
+    assert_eq!(sell.quantity, 5.0);
+    assert_eq!(sell.commission, 0.03);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_ofx_statement(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    ofx_statement_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![ofx_statement_pathbuf])
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        OFX_BROKERAGE_ID,
+        OFX_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+
+    let reinvestment = TradeExecution::find_by_brokerage_execution_id(&db_desc.db, "R20250425-1")
+        .await?
+        .expect("Reinvestment trade should exist");
+    assert_eq!(reinvestment.brokerage_account_id(), brokerage_account.id());
+
+    // The held treasury note is added even though it wasn't traded.
+    let securities = Security::find_by_ticker(&db_desc.db, "912828YK0").await?;
+    assert_eq!(securities.len(), 1);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_qif_dates() -> Result<()> {
+    let date = |y, m, d| chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+    assert_eq!(
+        parse_qif_date("1/15'25", QifDateFormat::MonthDayYear)?,
+        date(2025, 1, 15)
+    );
+    assert_eq!(
+        parse_qif_date(" 4/ 2' 5", QifDateFormat::MonthDayYear)?,
+        date(2005, 4, 2)
+    );
+    assert_eq!(
+        parse_qif_date("12/31/98", QifDateFormat::MonthDayYear)?,
+        date(1998, 12, 31)
+    );
+    assert_eq!(
+        parse_qif_date("4/2'25", QifDateFormat::DayMonthYear)?,
+        date(2025, 2, 4)
+    );
+    assert!(parse_qif_date("15/1'25", QifDateFormat::MonthDayYear).is_err());
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_qif_investments(qif_investments_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(qif_investments_pathbuf)?;
+    let accounts = QifImporter::new().parse(&content)?;
+
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].account_id, QIF_ACCOUNT_NAME);
+    let activities = &accounts[0].activities;
+    // A transfer in, five trades and two dividends, one of them reinvested.
+    assert_eq!(activities.len(), 8);
+
+    let Activity::Trade(buy) = &activities[1] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(buy.ticker, "MSFT");
+    assert_eq!(buy.side, TradeSide::Buy);
+    assert_eq!(buy.quantity, 10.0);
+    assert_eq!(buy.price, 388.45);
+    assert_eq!(buy.commission, 5.0);
+
+    let Activity::Cash(reinvested_dividend) = &activities[3] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(reinvested_dividend.kind, CashTransactionKind::Dividend);
+    assert_eq!(reinvested_dividend.amount, 52.10);
+    let Activity::Trade(reinvestment) = &activities[4] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(reinvestment.ticker, "VTSAX");
+    assert_eq!(reinvestment.quantity, 0.395);
+
+    let Activity::Trade(shares_in) = &activities[5] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(shares_in.ticker, "AAPL");
+    assert_eq!(shares_in.side, TradeSide::Buy);
+    assert_eq!(shares_in.price, 150.0);
+
+    let Activity::Trade(shares_out) = &activities[7] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(shares_out.side, TradeSide::Sell);
+    assert_eq!(shares_out.quantity, 5.0);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_qif_security_without_symbol_uses_name() -> Result<()> {
+    let content = "!Account\nNBrokerage\n^\n\
+        !Type:Invst\nD1/15'25\nNBuy\nYAcme Private Fund\nI10\nQ5\n^\n\
+        D1/16'25\nNBuy\nYMicrosoft Corp\nI388.45\nQ10\n^\n\
+        !Type:Security\nNAcme Private Fund\nTOther\n^\nNMicrosoft Corp\nSMSFT\nTStock\n^\n";
+
+    let accounts = QifImporter::new().parse(content)?;
+    let activities = &accounts[0].activities;
+    let Activity::Trade(unlisted) = &activities[0] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(unlisted.ticker, "Acme Private Fund");
+    let Activity::Trade(listed) = &activities[1] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(listed.ticker, "MSFT");
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_qif_without_account_uses_configured_account() -> Result<()> {
+    let content = "!Type:Invst\nD1/15'25\nNBuy\nYMSFT\nI388.45\nQ10\n^\n";
+
+    assert!(QifImporter::new().parse(content).is_err());
+
+    let accounts = QifImporter::new().account_id("Old IRA").parse(content)?;
+    assert_eq!(accounts[0].account_id, "Old IRA");
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_qif_investments(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    qif_investments_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![qif_investments_pathbuf])
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        QIF_BROKERAGE_ID,
+        QIF_ACCOUNT_NAME,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    assert_eq!(cash_transactions.len(), 3);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_degiro_transactions(degiro_transactions_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(degiro_transactions_pathbuf)?;
+    assert_eq!(
+        DegiroCsvImporter::detect(&content),
+        Some(DegiroCsvKind::Transactions)
+    );
+    let account_activities = DegiroCsvImporter::new(DEGIRO_ACCOUNT_ID).parse(&content)?;
+
+    // Three trades and one AutoFX fee.
+    assert_eq!(account_activities.activities.len(), 4);
+
+    let Activity::Trade(buy) = &account_activities.activities[0] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(buy.ticker, "US0378331005");
+    assert_eq!(buy.listing_exchange.as_deref(), Some(ISIN_LISTING_EXCHANGE));
+    assert_eq!(buy.side, TradeSide::Buy);
+    assert_eq!(buy.quantity, 5.0);
+    assert_eq!(buy.price, 199.74);
+    assert_eq!(buy.commission, 2.0);
+    // 2025-04-22 15:31 CEST
+    assert_eq!(buy.execution_timestamp_ms, 1745328660000);
+
+    let Activity::Cash(autofx_fee) = &account_activities.activities[1] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(autofx_fee.kind, CashTransactionKind::Fee);
+    assert_eq!(autofx_fee.amount, -2.2);
+    assert_eq!(autofx_fee.currency, "EUR");
+    assert_eq!(autofx_fee.ticker.as_deref(), Some("US0378331005"));
+    assert_eq!(
+        autofx_fee.listing_exchange.as_deref(),
+        Some(ISIN_LISTING_EXCHANGE)
+    );
+
+    // Comma decimal separators.
+    let Activity::Trade(sell) = &account_activities.activities[2] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(sell.side, TradeSide::Sell);
+    assert_eq!(sell.quantity, 2.0);
+    assert_eq!(sell.price, 598.4);
+    assert_eq!(sell.commission, 3.0);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_degiro_dutch_account_statement(
+    degiro_account_statement_pathbuf: PathBuf,
+) -> Result<()> {
+    let content = std::fs::read_to_string(degiro_account_statement_pathbuf)?;
+    assert_eq!(
+        DegiroCsvImporter::detect(&content),
+        Some(DegiroCsvKind::AccountStatement)
+    );
+    let account_activities = DegiroCsvImporter::new(DEGIRO_ACCOUNT_ID).parse(&content)?;
+
+    // The buy and its transaction fees come from the Transactions CSV instead.
+    let kinds = account_activities
+        .activities
+        .iter()
+        .map(|activity| match activity {
+            Activity::Cash(cash) => (cash.kind.clone(), cash.amount, cash.currency.as_str()),
+            Activity::Trade(_) => panic!("expected only cash activities"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            (CashTransactionKind::Dividend, 1.3, "USD"),
+            (CashTransactionKind::Tax, -0.2, "USD"),
+            (CashTransactionKind::Fee, -2.5, "EUR"),
+            (CashTransactionKind::ForeignExchange, -879.17, "EUR"),
+            (CashTransactionKind::Deposit, 2000.0, "EUR"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_degiro_amount_separators(
+    degiro_transactions_pathbuf: PathBuf,
+    degiro_account_statement_pathbuf: PathBuf,
+) -> Result<()> {
+    // English exports group thousands with a lone comma followed by three digits.
+    let content = std::fs::read_to_string(degiro_transactions_pathbuf)?
+        .replace("NDQ,XNAS,5,199.74", "NDQ,XNAS,\"1,000\",199.74");
+    let account_activities = DegiroCsvImporter::new(DEGIRO_ACCOUNT_ID).parse(&content)?;
+    let Activity::Trade(buy) = &account_activities.activities[0] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(buy.quantity, 1000.0);
+    let Activity::Trade(sell) = &account_activities.activities[2] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(sell.price, 598.4);
+
+    // Dutch exports always use a decimal comma.
+    let content = std::fs::read_to_string(degiro_account_statement_pathbuf)?
+        .replace("\"2000,00\",EUR", "\"2,125\",EUR");
+    let account_activities = DegiroCsvImporter::new(DEGIRO_ACCOUNT_ID).parse(&content)?;
+    let Some(Activity::Cash(deposit)) = account_activities.activities.last() else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(deposit.kind, CashTransactionKind::Deposit);
+    assert_eq!(deposit.amount, 2.125);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_degiro_exports(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    degiro_transactions_pathbuf: PathBuf,
+    degiro_account_statement_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(
+            &db_desc.db,
+            None,
+            vec![
+                degiro_transactions_pathbuf,
+                degiro_account_statement_pathbuf,
+            ],
+        )
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        DEGIRO_BROKERAGE_ID,
+        DEGIRO_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+
+    // Trades, the AutoFX fee and the dividend all refer to the one security for the ISIN.
+    let securities = Security::find_by_ticker(&db_desc.db, "US0378331005").await?;
+    assert_eq!(securities.len(), 1);
+
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    assert_eq!(cash_transactions.len(), 6);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_degiro_account_statement_before_transactions(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    degiro_transactions_pathbuf: PathBuf,
+    degiro_account_statement_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    for pathbuf in [
+        degiro_account_statement_pathbuf,
+        degiro_transactions_pathbuf,
+    ] {
+        registry
+            .import_statement_files(&db_desc.db, None, vec![pathbuf])
+            .await?;
+    }
+
+    // The dividend added the ISIN's security, which the later trade and AutoFX fee reuse.
+    let securities = Security::find_by_ticker(&db_desc.db, "US0378331005").await?;
+    assert_eq!(securities.len(), 1);
+    let security = &securities[0];
+    assert_eq!(security.listing_exchange(), ISIN_LISTING_EXCHANGE);
+    assert_eq!(
+        db_desc
+            .db
+            .collection::<TradeExecution>(TradeExecution::COLLECTION_NAME)
+            .count_documents(doc! { "security_id": security.id() })
+            .await?,
+        1
+    );
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        DEGIRO_BROKERAGE_ID,
+        DEGIRO_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    // The dividend, its tax, the FX debit and the AutoFX fee.
+    assert_eq!(
+        cash_transactions
+            .iter()
+            .filter(|cash_transaction| cash_transaction.security_id() == Some(security.id()))
+            .count(),
+        4
+    );
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_trading212_history(trading212_history_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(trading212_history_pathbuf)?;
+    let account_activities = Trading212CsvImporter::new(TRADING212_ACCOUNT_ID).parse(&content)?;
+
+    // A deposit, three trades with two conversion fees and a stamp duty charge, two dividends
+    // with one withholding tax, interest and a two-sided currency conversion.
+    assert_eq!(account_activities.activities.len(), 13);
+
+    let Activity::Trade(fractional_buy) = &account_activities.activities[1] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(fractional_buy.brokerage_execution_id, "EOF2841937165");
+    assert_eq!(fractional_buy.ticker, "AAPL");
+    assert_eq!(fractional_buy.quantity.to_string(), "0.1234567891");
+    assert_eq!(fractional_buy.price, 235.12);
+
+    let Activity::Cash(conversion_fee) = &account_activities.activities[2] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(conversion_fee.kind, CashTransactionKind::Fee);
+    assert_eq!(conversion_fee.amount, -0.03);
+    assert_eq!(conversion_fee.currency, "GBP");
+
+    let Activity::Cash(stamp_duty) = &account_activities.activities[4] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(stamp_duty.kind, CashTransactionKind::Tax);
+    assert_eq!(stamp_duty.amount, -0.53);
+
+    let Activity::Cash(dividend) = &account_activities.activities[8] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(dividend.kind, CashTransactionKind::Dividend);
+    assert_eq!(dividend.ticker.as_deref(), Some("VUSA"));
+    assert_eq!(dividend.amount, 3.1);
+    assert_eq!(dividend.currency, "USD");
+    let Activity::Cash(withholding_tax) = &account_activities.activities[9] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(withholding_tax.kind, CashTransactionKind::Tax);
+    assert_eq!(withholding_tax.amount, -0.47);
+
+    let Activity::Cash(converted_to) = &account_activities.activities[12] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(converted_to.kind, CashTransactionKind::ForeignExchange);
+    assert_eq!(converted_to.amount, 134.1);
+    assert_eq!(converted_to.currency, "USD");
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_trading212_history(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    trading212_history_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![trading212_history_pathbuf])
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        TRADING212_BROKERAGE_ID,
+        TRADING212_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+
+    // Fractional quantities round-trip through the database unchanged.
+    let fractional_buy =
+        TradeExecution::find_by_brokerage_execution_id(&db_desc.db, "EOF2841937165")
+            .await?
+            .expect("Trade execution should exist");
+    assert_eq!(
+        fractional_buy.brokerage_account_id(),
+        brokerage_account.id()
+    );
+    assert_eq!(fractional_buy.quantity().to_string(), "0.1234567891");
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_questrade_activities(questrade_activities_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(questrade_activities_pathbuf)?;
+    let accounts = QuestradeCsvImporter::new().parse(&content)?;
+
+    assert_eq!(accounts.len(), 2);
+    let tfsa = &accounts[0];
+    assert_eq!(tfsa.activities.account_id, QUESTRADE_TFSA_ACCOUNT_ID);
+    assert_eq!(tfsa.account_type, Some(AccountType::Tfsa));
+    assert!(tfsa.account_type.as_ref().unwrap().is_registered());
+    assert_eq!(tfsa.activities.activities.len(), 4);
+
+    let Activity::Cash(contribution) = &tfsa.activities.activities[0] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(contribution.kind, CashTransactionKind::Deposit);
+    assert_eq!(contribution.amount, 7000.0);
+
+    let Activity::Trade(drip) = &tfsa.activities.activities[3] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(drip.ticker, "XEQT.TO");
+    assert_eq!(drip.side, TradeSide::Buy);
+    assert_eq!(drip.quantity, 0.753);
+    assert_eq!(drip.price, 32.02);
+
+    let rrsp = &accounts[1];
+    assert_eq!(rrsp.activities.account_id, QUESTRADE_RRSP_ACCOUNT_ID);
+    assert_eq!(rrsp.account_type, Some(AccountType::Rrsp));
+
+    // Norbert's gambit: the journal moves the shares from DLR.TO to DLR.U.TO.
+    let journal = rrsp.activities.activities[1..3]
+        .iter()
+        .map(|activity| match activity {
+            Activity::Trade(trade) => (trade.ticker.as_str(), trade.side.clone(), trade.quantity),
+            Activity::Cash(_) => panic!("expected a trade"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        journal,
+        vec![
+            ("DLR.TO", TradeSide::Sell, 1000.0),
+            ("DLR.U.TO", TradeSide::Buy, 1000.0),
+        ]
+    );
+
+    let Activity::Cash(conversion) = &rrsp.activities.activities[5] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(conversion.kind, CashTransactionKind::ForeignExchange);
+    assert_eq!(conversion.currency, "USD");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_parse_questrade_xlsx_activities(
+    questrade_activities_pathbuf: PathBuf,
+    questrade_activities_xlsx_pathbuf: PathBuf,
+) -> Result<()> {
+    let importer = QuestradeCsvImporter::new();
+    assert_eq!(
+        importer
+            .path_may_match(&questrade_activities_xlsx_pathbuf)
+            .await,
+        PathMatch::Match
+    );
+    let bytes = std::fs::read(questrade_activities_xlsx_pathbuf)?;
+    let content = importer.decode_content(&bytes)?;
+    assert!(importer.content_matches(&content).await.is_match());
+
+    // The workbook holds the same activities as the CSV export, with date cells and numbers.
+    let summarize = |accounts: Vec<QuestradeAccount>| {
+        accounts
+            .into_iter()
+            .map(|account| {
+                let activities = account
+                    .activities
+                    .activities
+                    .iter()
+                    .map(|activity| match activity {
+                        Activity::Trade(trade) => format!(
+                            "{} {:?} {} @ {} at {}",
+                            trade.ticker,
+                            trade.side,
+                            trade.quantity,
+                            trade.price,
+                            trade.execution_timestamp_ms
+                        ),
+                        Activity::Cash(cash) => format!(
+                            "{:?} {} {} at {}",
+                            cash.kind, cash.amount, cash.currency, cash.timestamp_ms
+                        ),
+                    })
+                    .collect::<Vec<_>>();
+                (
+                    account.activities.account_id,
+                    account.account_type,
+                    activities,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let csv_content = std::fs::read_to_string(questrade_activities_pathbuf)?;
+    assert_eq!(
+        summarize(importer.parse(&content)?),
+        summarize(importer.parse(&csv_content)?)
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_questrade_activities(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    questrade_activities_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![questrade_activities_pathbuf])
+        .await?;
+
+    for (account_id, account_type) in [
+        (QUESTRADE_TFSA_ACCOUNT_ID, AccountType::Tfsa),
+        (QUESTRADE_RRSP_ACCOUNT_ID, AccountType::Rrsp),
+    ] {
+        let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+            &db_desc.db,
+            QUESTRADE_BROKERAGE_ID,
+            account_id,
+        )
+        .await?
+        .expect("Brokerage account should exist");
+
+        let brokerage_account_type =
+            BrokerageAccountType::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id())
+                .await?
+                .expect("Account type should be recorded");
+        assert_eq!(brokerage_account_type.account_type(), &account_type);
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_questrade_activities_in_session(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    questrade_activities_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+    let session = Arc::new(Mutex::new(db_desc.db.client().start_session().await?));
+
+    registry
+        .import_statement_files(
+            &db_desc.db,
+            Some(session),
+            vec![questrade_activities_pathbuf],
+        )
+        .await?;
+
+    // Each account is added once, with its account type.
+    assert_eq!(
+        db_desc
+            .db
+            .collection::<BrokerageAccount>(BrokerageAccount::COLLECTION_NAME)
+            .count_documents(doc! { "brokerage_id": QUESTRADE_BROKERAGE_ID })
+            .await?,
+        2
+    );
+    for account_id in [QUESTRADE_TFSA_ACCOUNT_ID, QUESTRADE_RRSP_ACCOUNT_ID] {
+        let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+            &db_desc.db,
+            QUESTRADE_BROKERAGE_ID,
+            account_id,
+        )
+        .await?
+        .expect("Brokerage account should exist");
+        assert!(
+            BrokerageAccountType::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id())
+                .await?
+                .is_some()
+        );
+    }
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_coinbase_transactions(coinbase_transactions_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(coinbase_transactions_pathbuf)?;
+    let account_activities = CoinbaseCsvImporter::new().parse(&content)?;
+
+    assert_eq!(account_activities.account_id, COINBASE_ACCOUNT_ID);
+    // The convert is a sell and a buy.
+    assert_eq!(account_activities.activities.len(), 8);
+
+    let Activity::Trade(buy) = &account_activities.activities[1] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(buy.brokerage_execution_id, "67a1b2c3d4e5f60718293a4c");
+    assert_eq!(buy.ticker, "BTC");
+    assert_eq!(
+        buy.listing_exchange.as_deref(),
+        Some(CRYPTO_LISTING_EXCHANGE)
+    );
+    assert_eq!(buy.quantity, 0.04);
+    assert_eq!(buy.price, 96500.0);
+    assert_eq!(buy.commission, 23.16);
+
+    let Activity::Cash(staking) = &account_activities.activities[3] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(staking.kind, CashTransactionKind::Income);
+    assert_eq!(staking.amount, 0.0042);
+    assert_eq!(staking.currency, "ETH");
+    assert_eq!(
+        staking.listing_exchange.as_deref(),
+        Some(CRYPTO_LISTING_EXCHANGE)
+    );
+
+    let Activity::Trade(convert_sell) = &account_activities.activities[4] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(convert_sell.ticker, "ETH");
+    assert_eq!(convert_sell.side, TradeSide::Sell);
+    assert_eq!(convert_sell.price, 2700.0);
+    let Activity::Trade(convert_buy) = &account_activities.activities[5] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(convert_buy.ticker, "BTC");
+    assert_eq!(convert_buy.side, TradeSide::Buy);
+    assert_eq!(convert_buy.quantity, 0.01411);
+
+    let Activity::Cash(send) = &account_activities.activities[6] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(send.kind, CashTransactionKind::Transfer);
+    assert_eq!(send.amount, -0.02);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_coinbase_convert_zero_quantity(coinbase_transactions_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(coinbase_transactions_pathbuf)?;
+    for zero_quantity in [
+        content.replace("Convert,ETH,-0.5,", "Convert,ETH,0,"),
+        content.replace("to 0.01411 BTC", "to 0 BTC"),
+    ] {
+        assert_ne!(zero_quantity, content);
+        assert!(CoinbaseCsvImporter::new().parse(&zero_quantity).is_err());
+    }
+
+    Ok(())
+}
+
+#[rstest]
+fn test_kraken_asset_names() {
+    assert_eq!(normalize_kraken_asset("XXBT"), "BTC");
+    assert_eq!(normalize_kraken_asset("ZEUR"), "EUR");
+    assert_eq!(normalize_kraken_asset("ETH2.S"), "ETH");
+    assert_eq!(normalize_kraken_asset("DOT.S"), "DOT");
+    assert_eq!(normalize_kraken_asset("SOL"), "SOL");
+    // Assets listed since the legacy codes keep any leading X or Z.
+    assert_eq!(normalize_kraken_asset("ZEUS"), "ZEUS");
+    assert_eq!(normalize_kraken_asset("XCN"), "XCN");
+    assert_eq!(
+        split_kraken_pair("XXBTZUSD"),
+        Some(("BTC".to_owned(), "USD".to_owned()))
+    );
+    assert_eq!(
+        split_kraken_pair("SOLUSDT"),
+        Some(("SOL".to_owned(), "USDT".to_owned()))
+    );
+    assert_eq!(
+        split_kraken_pair("ETH/XBT"),
+        Some(("ETH".to_owned(), "BTC".to_owned()))
+    );
+}
+
+#[rstest]
+fn test_parse_kraken_ledgers(kraken_ledgers_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(kraken_ledgers_pathbuf)?;
+    let activities = KrakenCsvImporter::new(KRAKEN_ACCOUNT_ID)
+        .parse(&content)?
+        .activities;
+
+    // A deposit, two trades with one base-asset fee, two wallet transfers, a staking reward and
+    // a withdrawal with its fee.
+    assert_eq!(activities.len(), 9);
+
+    // Fee in the quote asset.
+    let Activity::Trade(btc_buy) = &activities[1] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(btc_buy.brokerage_execution_id, "TZ4RJZ-5HQGD-MN3O4A");
+    assert_eq!(btc_buy.ticker, "BTC");
+    assert_eq!(btc_buy.side, TradeSide::Buy);
+    assert_eq!(btc_buy.quantity, 0.016);
+    assert_eq!(btc_buy.price, 93750.0);
+    assert_eq!(btc_buy.commission, 3.9);
+
+    // Fee in the base asset.
+    let Activity::Trade(eth_buy) = &activities[2] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(eth_buy.ticker, "ETH");
+    assert_eq!(eth_buy.price, 3250.0);
+    assert_eq!(eth_buy.commission, 0.0);
+    let Activity::Cash(eth_fee) = &activities[3] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(eth_fee.kind, CashTransactionKind::Fee);
+    assert_eq!(eth_fee.amount, -0.0008);
+    assert_eq!(eth_fee.currency, "ETH");
+
+    let Activity::Cash(reward) = &activities[6] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(reward.kind, CashTransactionKind::Income);
+    assert_eq!(reward.currency, "ETH");
+    assert_eq!(
+        reward.listing_exchange.as_deref(),
+        Some(CRYPTO_LISTING_EXCHANGE)
+    );
+
+    let Activity::Cash(withdrawal) = &activities[7] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(withdrawal.kind, CashTransactionKind::Transfer);
+    assert_eq!(withdrawal.amount, -0.01);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_kraken_trades(kraken_trades_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(kraken_trades_pathbuf)?;
+    let activities = KrakenCsvImporter::new(KRAKEN_ACCOUNT_ID)
+        .parse(&content)?
+        .activities;
+
+    let trades = activities
+        .iter()
+        .map(|activity| match activity {
+            Activity::Trade(trade) => (
+                trade.brokerage_execution_id.as_str(),
+                trade.ticker.as_str(),
+                trade.side.clone(),
+            ),
+            Activity::Cash(_) => panic!("expected only trades"),
+        })
+        .collect::<Vec<_>>();
+    // The ledgers export uses the same execution ids.
+    assert_eq!(
+        trades,
+        vec![
+            ("TZ4RJZ-5HQGD-MN3O4A", "BTC", TradeSide::Buy),
+            ("TXFQ2N-IMQ4B-VWTLJX", "ETH", TradeSide::Buy),
+            ("T5ZGQK-2RR2C-XJ5VFT", "SOL", TradeSide::Sell),
+        ]
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_coinbase_transactions(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    coinbase_transactions_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![coinbase_transactions_pathbuf])
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        COINBASE_BROKERAGE_ID,
+        COINBASE_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+
+    // The staking reward refers to the crypto security rather than adding another.
+    let securities = Security::find_by_ticker(&db_desc.db, "ETH").await?;
+    assert_eq!(securities.len(), 1);
+    assert_eq!(securities[0].listing_exchange(), CRYPTO_LISTING_EXCHANGE);
+
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    assert_eq!(cash_transactions.len(), 3);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_kraken_ledgers(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    kraken_ledgers_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![kraken_ledgers_pathbuf])
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        KRAKEN_BROKERAGE_ID,
+        KRAKEN_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+    let eth = Security::find_by_ticker_and_exchange(&db_desc.db, "ETH", CRYPTO_LISTING_EXCHANGE)
+        .await?
+        .expect("Crypto security should exist");
+    assert_eq!(Security::find_by_ticker(&db_desc.db, "ETH").await?.len(), 1);
+
+    // The base-asset fee and the staking reward both refer to the asset's crypto security.
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    let eth_fee = cash_transactions
+        .iter()
+        .find(|cash_transaction| {
+            cash_transaction.brokerage_transaction_id() == "LK5V7N-NDU3K-LZPTJB:fee"
+        })
+        .expect("Base-asset fee should exist");
+    assert_eq!(eth_fee.kind(), &CashTransactionKind::Fee);
+    assert_eq!(eth_fee.security_id(), Some(eth.id()));
+    let reward = cash_transactions
+        .iter()
+        .find(|cash_transaction| cash_transaction.kind() == &CashTransactionKind::Income)
+        .expect("Staking reward should exist");
+    assert_eq!(reward.security_id(), Some(eth.id()));
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_alpaca_activities(alpaca_activities_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(alpaca_activities_pathbuf)?;
+    let accounts = AlpacaJsonImporter::new(ALPACA_ACCOUNT_ID).parse(&content)?;
+
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].account_id, ALPACA_ACCOUNT_ID);
+    // The merger activity is skipped.
+    let activities = &accounts[0].activities;
+    assert_eq!(activities.len(), 8);
+
+    // Partial fills of one order are separate executions.
+    let fills = activities
+        .iter()
+        .filter_map(|activity| match activity {
+            Activity::Trade(trade) if trade.ticker == "AAPL" => Some(trade),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(fills.len(), 2);
+    assert_eq!(
+        fills[0].brokerage_execution_id,
+        "20250103093112345::7a1f0e9c-5b6d-4c3a-8e2f-1a2b3c4d5e60"
+    );
+    assert_eq!(fills[0].quantity, 60.0);
+    assert_eq!(fills[0].price, 243.1);
+    assert_eq!(fills[0].execution_timestamp_ms, 1735914672345);
+    assert_eq!(fills[1].quantity, 40.0);
+    assert_eq!(fills[1].price, 243.12);
+
+    let Activity::Trade(option) = &activities[3] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(option.ticker, "AAPL  250221C00260000");
+    assert_eq!(option.side, TradeSide::Sell);
+    assert_eq!(option.quantity, 2.0);
+
+    let kinds = activities
+        .iter()
+        .filter_map(|activity| match activity {
+            Activity::Cash(cash) => Some((cash.kind.clone(), cash.amount)),
+            Activity::Trade(_) => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            (CashTransactionKind::Deposit, 25000.0),
+            (CashTransactionKind::Dividend, 25.0),
+            (CashTransactionKind::Tax, -3.75),
+            (CashTransactionKind::Fee, -1.5),
+            (CashTransactionKind::Journal, -5000.0),
+        ]
+    );
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_alpaca_broker_api_accounts() -> Result<()> {
+    let content = r#"[
+        {"id": "1", "account_id": "acct-a", "activity_type": "CSD", "date": "2025-01-02", "net_amount": 100},
+        {"id": "2", "account_id": "acct-b", "activity_type": "CSD", "date": "2025-01-02", "net_amount": "250.5"},
+        {"id": "3", "account_id": "acct-a", "activity_type": "INT", "date": "2025-01-31", "net_amount": "0.42"}
+    ]"#;
+    let accounts = AlpacaJsonImporter::new(ALPACA_ACCOUNT_ID).parse(content)?;
+
+    let summary = accounts
+        .iter()
+        .map(|a| (a.account_id.as_str(), a.activities.len()))
+        .collect::<Vec<_>>();
+    assert_eq!(summary, vec![("acct-a", 2), ("acct-b", 1)]);
+
+    let Activity::Cash(deposit) = &accounts[1].activities[0] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(deposit.amount, 250.5);
+    assert_eq!(deposit.currency, "USD");
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_alpaca_activities(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    alpaca_activities_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![alpaca_activities_pathbuf])
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        ALPACA_BROKERAGE_ID,
+        ALPACA_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+
+    let execution = TradeExecution::find_by_brokerage_execution_id(
+        &db_desc.db,
+        "20250103093112789::8b2f1f0d-6c7e-4d4b-9f30-2b3c4d5e6f71",
+    )
+    .await?
+    .expect("Trade execution should exist");
+    assert_eq!(execution.brokerage_account_id(), brokerage_account.id());
+    assert_eq!(execution.quantity(), 40.0);
+
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    assert_eq!(cash_transactions.len(), 5);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_fix_drop_copy(fix_drop_copy_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(fix_drop_copy_pathbuf)?;
+    let accounts = FixLogImporter::new(FIX_BROKERAGE_ID).parse(&content)?;
+
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].account_id, FIX_ACCOUNT_ID);
+
+    // The order acknowledgement and the resent fill are skipped, the first partial fill is
+    // corrected and the short sale is busted.
+    let trades = accounts[0]
+        .activities
+        .iter()
+        .map(|activity| match activity {
+            Activity::Trade(trade) => trade,
+            Activity::Cash(_) => panic!("expected only trades"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(trades.len(), 3);
+
+    assert_eq!(trades[0].brokerage_execution_id, "EXEC-0006");
+    assert_eq!(trades[0].ticker, "MSFT");
+    assert_eq!(trades[0].side, TradeSide::Buy);
+    assert_eq!(trades[0].quantity, 200.0);
+    assert_eq!(trades[0].price, 421.49);
+    assert_eq!(trades[0].commission, 1.0);
+    assert_eq!(trades[0].execution_timestamp_ms, 1735914672345);
+
+    assert_eq!(trades[1].brokerage_execution_id, "EXEC-0003");
+    assert_eq!(trades[1].quantity, 100.0);
+    assert_eq!(trades[1].price, 421.56);
+
+    assert_eq!(trades[2].ticker, "NVDA  250221C00150000");
+    assert_eq!(trades[2].side, TradeSide::Sell);
+    assert_eq!(trades[2].commission, 1.3);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_fix_42_soh_delimited() -> Result<()> {
+    let content = [
+        "8=FIX.4.2\x019=120\x0135=8\x0117=A1\x0120=0\x0131=12.5\x0132=40\x0154=2\x0155=IBM\x0160=20250106-15:00:00\x01150=2\x0110=000\x01",
+        "8=FIX.4.2\x019=120\x0135=8\x0117=A2\x0120=0\x0131=13\x0132=10\x0154=1\x0155=IBM\x0160=20250106-15:05:00\x01150=1\x0110=000\x01",
+        "8=FIX.4.2\x019=120\x0135=8\x0117=A3\x0119=A2\x0120=1\x0131=13\x0132=10\x0154=1\x0155=IBM\x0160=20250106-15:06:00\x01150=1\x0110=000\x01",
+    ]
+    .join("\n");
+    let accounts = FixLogImporter::new(FIX_BROKERAGE_ID)
+        .account_id(FIX_ACCOUNT_ID)
+        .parse(&content)?;
+
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].account_id, FIX_ACCOUNT_ID);
+    assert_eq!(
+        accounts[0].activities,
+        vec![Activity::Trade(TradeActivity {
+            brokerage_execution_id: "A1".to_owned(),
+            ticker: "IBM".to_owned(),
+            listing_exchange: None,
+            side: TradeSide::Sell,
+            quantity: 40.0,
+            price: 12.5,
+            commission: 0.0,
+            execution_timestamp_ms: 1736175600000,
+        })]
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_fix_drop_copy(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    fix_drop_copy_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(&db_desc.db, None, vec![fix_drop_copy_pathbuf])
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        FIX_BROKERAGE_ID,
+        FIX_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+
+    let corrected = TradeExecution::find_by_brokerage_execution_id(&db_desc.db, "EXEC-0006")
+        .await?
+        .expect("Corrected execution should exist");
+    assert_eq!(corrected.brokerage_account_id(), brokerage_account.id());
+    assert_eq!(corrected.price(), 421.49);
+    assert!(
+        TradeExecution::find_by_brokerage_execution_id(&db_desc.db, "EXEC-0002")
+            .await?
+            .is_none()
+    );
+    assert!(
+        TradeExecution::find_by_brokerage_execution_id(&db_desc.db, "EXEC-0005")
+            .await?
+            .is_none()
+    );
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_schwab_pdf_statement(schwab_statement_pdf_pathbuf: PathBuf) -> Result<()> {
+    let importer = PdfStatementImporter::new();
+    let text = importer.extract_text(&std::fs::read(schwab_statement_pdf_pathbuf)?)?;
+    let (brokerage_id, statement) = importer.parse(&text)?;
+
+    assert_eq!(brokerage_id, SCHWAB_BROKERAGE_ID);
+    assert_eq!(
+        statement.positions[1],
+        PdfPosition {
+            account_id: SCHWAB_PDF_ACCOUNT_ID.to_owned(),
+            ticker: "MSFT".to_owned(),
+            quantity: 20.0,
+            price: 375.39,
+        }
+    );
+    assert_eq!(statement.positions.len(), 2);
+
+    assert_eq!(statement.accounts.len(), 1);
+    assert_eq!(statement.accounts[0].account_id, SCHWAB_PDF_ACCOUNT_ID);
+    let activities = &statement.accounts[0].activities;
+    assert_eq!(activities.len(), 5);
+
+    let Activity::Trade(sale) = &activities[2] else {
+        panic!("expected a trade");
+    };
+    assert_eq!(sale.ticker, "NVDA");
+    assert_eq!(sale.side, TradeSide::Sell);
+    assert_eq!(sale.quantity, 5.0);
+    assert_eq!(sale.price, 117.52);
+    assert_eq!(sale.commission, 0.02);
+
+    let Activity::Cash(dividend) = &activities[1] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(dividend.kind, CashTransactionKind::Dividend);
+    assert_eq!(dividend.ticker.as_deref(), Some("MSFT"));
+    assert_eq!(dividend.amount, 16.6);
+    assert_eq!(dividend.description, "MICROSOFT CORP");
+
+    let Activity::Cash(interest) = &activities[4] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(interest.kind, CashTransactionKind::Interest);
+    assert_eq!(interest.amount, 0.41);
+    assert_eq!(interest.description, "SCHWAB1 INT 02/27-03/30");
+
+    Ok(())
+}
+
+#[rstest]
+fn test_extract_protected_pdf_statement(
+    schwab_statement_pdf_pathbuf: PathBuf,
+    schwab_statement_protected_pdf_pathbuf: PathBuf,
+) -> Result<()> {
+    let bytes = std::fs::read(schwab_statement_protected_pdf_pathbuf)?;
+
+    assert!(PdfStatementImporter::new().extract_text(&bytes).is_err());
+    assert!(
+        PdfStatementImporter::new()
+            .password("wrong")
+            .extract_text(&bytes)
+            .is_err()
+    );
+
+    let text = PdfStatementImporter::new()
+        .password("wrong")
+        .password(SCHWAB_PDF_PASSWORD)
+        .extract_text(&bytes)?;
+    let unprotected_text =
+        PdfStatementImporter::new().extract_text(&std::fs::read(schwab_statement_pdf_pathbuf)?)?;
+    assert_eq!(text, unprotected_text);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_schwab_pdf_statement(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    schwab_statement_protected_pdf_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(
+            &db_desc.db,
+            None,
+            vec![schwab_statement_protected_pdf_pathbuf],
+        )
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        SCHWAB_BROKERAGE_ID,
+        SCHWAB_PDF_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    assert_eq!(cash_transactions.len(), 3);
+
+    // The position table adds securities without transactions.
+    let securities = Security::find_by_ticker(&db_desc.db, "MSFT").await?;
+    assert_eq!(securities.len(), 1);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_camt053_statement(camt053_statement_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(camt053_statement_pathbuf)?;
+    let statement = Camt053Importer::new().parse(&content)?;
+    assert_eq!(statement.brokerage_id, CAMT053_BROKERAGE_ID);
+    assert_eq!(statement.accounts.len(), 1);
+    assert_eq!(statement.accounts[0].account_id, CAMT053_ACCOUNT_ID);
+
+    // The securities trade settlement and the pending dividend are skipped.
+    let activities = &statement.accounts[0].activities;
+    assert_eq!(activities.len(), 5);
+
+    let Activity::Cash(dividend) = &activities[0] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(dividend.kind, CashTransactionKind::Dividend);
+    assert_eq!(dividend.brokerage_transaction_id, "PB-20250312-0001");
+    assert_eq!(dividend.amount, 1250.0);
+    assert_eq!(dividend.currency, "CHF");
+    assert_eq!(dividend.ticker.as_deref(), Some("CH0038863350"));
+    assert_eq!(dividend.description, "CASH DIVIDEND NESTLE SA");
+
+    let Activity::Cash(tax) = &activities[1] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(tax.kind, CashTransactionKind::Tax);
+    assert_eq!(tax.amount, -437.5);
+    assert_eq!(tax.ticker.as_deref(), Some("CH0038863350"));
+
+    let Activity::Cash(fee) = &activities[2] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(fee.kind, CashTransactionKind::Fee);
+    assert_eq!(fee.amount, -85.0);
+    assert_eq!(fee.ticker, None);
+    assert_eq!(fee.description, "CUSTODY FEE Q1 2025");
+
+    // Entries without a reference get a synthetic id.
+    let Activity::Cash(interest) = &activities[3] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(interest.kind, CashTransactionKind::Interest);
+    assert!(interest.brokerage_transaction_id.starts_with("camt053"));
+
+    let Activity::Cash(transfer) = &activities[4] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(transfer.kind, CashTransactionKind::Transfer);
+    assert_eq!(transfer.amount, 50000.0);
+
+    Ok(())
+}
+
+#[test]
+fn test_camt053_bank_transaction_kind() {
+    assert_eq!(
+        bank_transaction_kind("SECU", "CORP", "DVCA"),
+        Some(CashTransactionKind::Dividend)
+    );
+    assert_eq!(
+        bank_transaction_kind("PMNT", "RCDT", "ESCT"),
+        Some(CashTransactionKind::Transfer)
+    );
+    assert_eq!(
+        bank_transaction_kind("FORX", "SPOT", "OTHR"),
+        Some(CashTransactionKind::ForeignExchange)
+    );
+    assert_eq!(bank_transaction_kind("SECU", "SETT", "TRAD"), None);
+}
+
+#[test]
+fn test_parse_mt940_statement_line() -> Result<()> {
+    let line = parse_statement_line("2503140314D6,24NTAXNONREF//DB-TAX-0314")?;
+    assert_eq!(
+        line.value_date,
+        chrono::NaiveDate::from_ymd_opt(2025, 3, 14).unwrap()
+    );
+    assert_eq!(
+        line.entry_date,
+        chrono::NaiveDate::from_ymd_opt(2025, 3, 14)
+    );
+    assert_eq!(line.amount, -6.24);
+    assert_eq!(line.transaction_type, "TAX");
+    assert_eq!(line.customer_reference, "NONREF");
+    assert_eq!(line.bank_reference.as_deref(), Some("DB-TAX-0314"));
+
+    // A reversal of a credit is a debit.
+    let line = parse_statement_line("250320RC15,00NTRFREF1")?;
+    assert_eq!(line.entry_date, None);
+    assert_eq!(line.amount, -15.0);
+    assert_eq!(line.customer_reference, "REF1");
+
+    assert!(parse_statement_line("2503X0C15,00NTRF").is_err());
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_mt940_statement(mt940_statement_pathbuf: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(mt940_statement_pathbuf)?;
+    let statement = Mt940Importer::new().parse(&content)?;
+    assert_eq!(statement.brokerage_id, MT940_BROKERAGE_ID);
+    assert_eq!(statement.accounts.len(), 1);
+    assert_eq!(statement.accounts[0].account_id, MT940_ACCOUNT_ID);
+
+    // The securities purchase is skipped.
+    let activities = &statement.accounts[0].activities;
+    assert_eq!(activities.len(), 4);
+
+    let Activity::Cash(dividend) = &activities[0] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(dividend.kind, CashTransactionKind::Dividend);
+    assert_eq!(dividend.brokerage_transaction_id, "DB-DIV-0314");
+    assert_eq!(dividend.amount, 41.6);
+    assert_eq!(dividend.currency, "EUR");
+    assert_eq!(dividend.ticker.as_deref(), Some("DE0007164600"));
+    assert_eq!(
+        dividend.description,
+        "166 DIVIDENDE ERTRAGSGUTSCHRIFT SAP SE ISIN DE0007164600 STK 20"
+    );
+
+    let Activity::Cash(tax) = &activities[1] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(tax.kind, CashTransactionKind::Tax);
+    assert_eq!(tax.amount, -6.24);
+    assert_eq!(tax.ticker.as_deref(), Some("DE0007164600"));
+
+    let Activity::Cash(transfer) = &activities[2] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(transfer.kind, CashTransactionKind::Transfer);
+    assert_eq!(transfer.brokerage_transaction_id, "EINZAHLUNG");
+    assert_eq!(transfer.amount, 2000.0);
+
+    let Activity::Cash(fee) = &activities[3] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(fee.kind, CashTransactionKind::Fee);
+    assert!(fee.brokerage_transaction_id.starts_with("mt940"));
+    assert_eq!(fee.amount, -12.5);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_camt053_and_mt940_statements(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    camt053_statement_pathbuf: PathBuf,
+    mt940_statement_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(
+            &db_desc.db,
+            None,
+            vec![camt053_statement_pathbuf, mt940_statement_pathbuf],
+        )
+        .await?;
+
+    for (brokerage_id, account_id, count) in [
+        (CAMT053_BROKERAGE_ID, CAMT053_ACCOUNT_ID, 5),
+        (MT940_BROKERAGE_ID, MT940_ACCOUNT_ID, 4),
+    ] {
+        let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+            &db_desc.db,
+            brokerage_id,
+            account_id,
+        )
+        .await?
+        .expect("Brokerage account should exist");
+        let cash_transactions =
+            CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id())
+                .await?;
+        assert_eq!(cash_transactions.len(), count);
+    }
+
+    // Dividends link their security by ISIN.
+    let securities = Security::find_by_ticker(&db_desc.db, "DE0007164600").await?;
+    assert_eq!(securities.len(), 1);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_configurable_csv_toml_mapping(
+    acme_depot_mapping_pathbuf: PathBuf,
+    acme_depot_csv_pathbuf: PathBuf,
+) -> Result<()> {
+    let importer = ConfigurableCsvImporter::from_file(&acme_depot_mapping_pathbuf)?;
+    assert_eq!(importer.importer_name(), "acme-depot");
+
+    let content = std::fs::read_to_string(acme_depot_csv_pathbuf)?;
+    let accounts = importer.parse(&content)?;
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].account_id, ACME_ACCOUNT_ID);
+
+    // The unmapped transfer row is skipped.
+    let activities = &accounts[0].activities;
+    assert_eq!(activities.len(), 5);
+    assert_eq!(
+        activities[0],
+        Activity::Trade(TradeActivity {
+            brokerage_execution_id: "AC-1001".to_owned(),
+            ticker: "SAP".to_owned(),
+            listing_exchange: None,
+            side: TradeSide::Buy,
+            quantity: 10.0,
+            price: 245.3,
+            commission: 4.95,
+            // 2025-03-03 09:31:12 CET
+            execution_timestamp_ms: 1740990672000,
+        })
+    );
+    let Activity::Trade(sell) = &activities[1] else {
+        panic!("expected a trade activity");
+    };
+    assert_eq!(sell.side, TradeSide::Sell);
+
+    let Activity::Cash(dividend) = &activities[2] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(dividend.kind, CashTransactionKind::Dividend);
+    assert_eq!(dividend.amount, 1380.0);
+    assert_eq!(dividend.currency, "EUR");
+    assert_eq!(dividend.ticker.as_deref(), Some("ALV"));
+    assert_eq!(dividend.description, "Allianz SE Dividende");
+
+    let Activity::Cash(fee) = &activities[3] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(fee.kind, CashTransactionKind::Fee);
+    assert_eq!(fee.amount, -12.5);
+    assert_eq!(fee.ticker, None);
+    assert!(fee.brokerage_transaction_id.starts_with(ACME_BROKERAGE_ID));
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_configurable_csv_misspelt_column(
+    acme_depot_mapping_pathbuf: PathBuf,
+    acme_depot_csv_pathbuf: PathBuf,
+) -> Result<()> {
+    let mapping = std::fs::read_to_string(acme_depot_mapping_pathbuf)?
+        .replace(r#"quantity = "Anzahl""#, r#"quantity = "Anzhal""#);
+    let importer = ConfigurableCsvImporter::new(CsvMapping::from_toml(&mapping)?)?;
+
+    // Trades would otherwise be imported with a zero quantity.
+    let content = std::fs::read_to_string(acme_depot_csv_pathbuf)?;
+    assert!(importer.parse(&content).is_err());
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_configurable_csv_yaml_mapping(
+    northwind_mapping_pathbuf: PathBuf,
+    northwind_activity_csv_pathbuf: PathBuf,
+) -> Result<()> {
+    let importer = ConfigurableCsvImporter::from_file(&northwind_mapping_pathbuf)?;
+    let content = std::fs::read_to_string(northwind_activity_csv_pathbuf)?;
+    let accounts = importer.parse(&content)?;
+
+    // Rows are split by the account column, and the totals row is skipped.
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].account_id, "NW-001");
+    assert_eq!(accounts[0].activities.len(), 2);
+    assert_eq!(accounts[1].account_id, "NW-002");
+    assert_eq!(accounts[1].activities.len(), 2);
+
+    // Actions match case-insensitively.
+    let Activity::Trade(sell) = &accounts[1].activities[0] else {
+        panic!("expected a trade activity");
+    };
+    assert_eq!(sell.side, TradeSide::Sell);
+    assert_eq!(sell.commission, 1.0);
+
+    let Activity::Cash(deposit) = &accounts[1].activities[1] else {
+        panic!("expected a cash activity");
+    };
+    assert_eq!(deposit.kind, CashTransactionKind::Deposit);
+    assert_eq!(deposit.amount, 5000.0);
+    assert_eq!(deposit.currency, "USD");
+
+    Ok(())
+}
+
+#[test]
+fn test_configurable_csv_mapping_validation() -> Result<()> {
+    let mapping = |extra: &str| {
+        CsvMapping::from_toml(&format!(
+            r#"
+name = "test-csv"
+brokerage_id = "test"
+date_formats = ["%Y-%m-%d"]
+{}
+
+[columns]
+date = "Date"
+action = "Action"
+
+[actions]
+buy = ["Buy"]
+"#,
+            extra
+        ))
+    };
+
+    assert!(ConfigurableCsvImporter::new(mapping(r#"account_id = "A1""#)?).is_ok());
+    // An account id or account column is required.
+    assert!(ConfigurableCsvImporter::new(mapping("")?).is_err());
+    assert!(
+        ConfigurableCsvImporter::new(mapping(
+            r#"account_id = "A1"
+decimal_separator = ";""#
+        )?)
+        .is_err()
+    );
+    assert!(
+        ConfigurableCsvImporter::new(mapping(
+            r#"account_id = "A1"
+timezone = "Mars/Olympus_Mons""#
+        )?)
+        .is_err()
+    );
+    // The decimal separator can't also be the delimiter, which defaults to ','.
+    assert!(
+        ConfigurableCsvImporter::new(mapping(
+            r#"account_id = "A1"
+decimal_separator = ",""#
+        )?)
+        .is_err()
+    );
+    assert!(mapping(r#"acount_id = "A1""#).is_err());
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_register_csv_mapping_files(
+    acme_depot_mapping_pathbuf: PathBuf,
+    northwind_mapping_pathbuf: PathBuf,
+) -> Result<()> {
+    let mut registry = ImporterRegistry::new();
+    registry.register_csv_mapping_files(&[
+        acme_depot_mapping_pathbuf.clone(),
+        northwind_mapping_pathbuf.clone(),
+    ])?;
+
+    let acme = registry
+        .importer("acme-depot")
+        .expect("acme mapping should be registered");
+    assert_eq!(
+        acme.path_may_match(&PathBuf::from("ACME_2025-04.CSV"))
+            .await,
+        PathMatch::Match
+    );
+    assert_eq!(
+        acme.path_may_match(&PathBuf::from("northwind_activity.csv"))
+            .await,
+        PathMatch::NoMatch
+    );
+    assert!(registry.importer("northwind-activity").is_some());
+
+    // Mapping names must be unique within the registry.
+    assert!(
+        registry
+            .register_csv_mapping_files(&[northwind_mapping_pathbuf])
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_configurable_csv(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    acme_depot_csv_pathbuf: PathBuf,
+    northwind_activity_csv_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(
+            &db_desc.db,
+            None,
+            vec![acme_depot_csv_pathbuf, northwind_activity_csv_pathbuf],
+        )
+        .await?;
+
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        ACME_BROKERAGE_ID,
+        ACME_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+    for execution_id in ["AC-1001", "AC-1002"] {
+        assert!(
+            TradeExecution::find_by_brokerage_execution_id(&db_desc.db, execution_id)
+                .await?
+                .is_some()
+        );
+    }
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    assert_eq!(cash_transactions.len(), 3);
+
+    for account_id in ["NW-001", "NW-002"] {
+        assert!(
+            BrokerageAccount::find_by_brokerage_and_account_id(
+                &db_desc.db,
+                NORTHWIND_BROKERAGE_ID,
+                account_id,
+            )
+            .await?
+            .is_some()
+        );
+    }
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_email(
+    trade_confirmation_eml_pathbuf: PathBuf,
+    camt053_statement_pathbuf: PathBuf,
+) -> Result<()> {
+    let raw = std::fs::read(&trade_confirmation_eml_pathbuf)?;
+    let message = parse_email(&raw, &trade_confirmation_eml_pathbuf)?;
+    assert_eq!(message.message_id, "stmt-20250401-0001@privatbank.example");
+    assert_eq!(message.subject, "Your account statement March 2025");
+
+    let names = message
+        .documents
+        .iter()
+        .map(|d| d.path.file_name().unwrap().to_str().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(names, ["statement_2025-03.xml", "logo.png", "body.txt"]);
+    assert_eq!(
+        message.documents[0].path,
+        PathBuf::from(format!(
+            "{}!/stmt-20250401-0001@privatbank.example/1/statement_2025-03.xml",
+            trade_confirmation_eml_pathbuf.display()
+        ))
+    );
+
+    // Attachments are decoded from their transfer encoding.
+    assert_eq!(
+        message.documents[0].bytes,
+        std::fs::read(camt053_statement_pathbuf)?
+    );
+    assert!(String::from_utf8(message.documents[2].bytes.clone())?.starts_with("Dear client"));
+
+    Ok(())
+}
+
+#[rstest]
+fn test_parse_email_duplicate_attachment_names() -> Result<()> {
+    let raw = "Message-ID: <dup@example.com>\r\n\
+        Subject: Statements\r\n\
+        MIME-Version: 1.0\r\n\
+        Content-Type: multipart/mixed; boundary=\"b\"\r\n\r\n\
+        --b\r\n\
+        Content-Type: text/csv\r\n\
+        Content-Disposition: attachment; filename=\"statement.csv\"\r\n\r\n\
+        first\r\n\
+        --b\r\n\
+        Content-Type: text/csv\r\n\
+        Content-Disposition: attachment; filename=\"statement.csv\"\r\n\r\n\
+        second\r\n\
+        --b--\r\n";
+
+    let message = parse_email(raw.as_bytes(), Path::new("inbox.eml"))?;
+    let paths = message
+        .documents
+        .iter()
+        .map(|d| d.path.to_str().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        paths,
+        [
+            "inbox.eml!/dup@example.com/1/statement.csv",
+            "inbox.eml!/dup@example.com/2/statement.csv",
+        ]
+    );
+
+    Ok(())
+}
+
+#[rstest]
+fn test_read_mailboxes(
+    trade_confirmation_eml_pathbuf: PathBuf,
+    confirmations_mbox_pathbuf: PathBuf,
+    confirmations_maildir_pathbuf: PathBuf,
+) -> Result<()> {
+    let eml = Mailbox::at(&trade_confirmation_eml_pathbuf);
+    assert_eq!(eml, Mailbox::Eml(trade_confirmation_eml_pathbuf));
+    assert_eq!(eml.messages()?.len(), 1);
+
+    let mbox = Mailbox::at(&confirmations_mbox_pathbuf);
+    assert_eq!(mbox, Mailbox::Mbox(confirmations_mbox_pathbuf.clone()));
+    let messages = mbox.messages()?;
+    assert_eq!(messages.len(), 4);
+    let message = parse_email(&messages[1], &confirmations_mbox_pathbuf)?;
+    assert_eq!(message.message_id, "mt940-20250331@deutschebank.example");
+    assert_eq!(message.documents.len(), 1);
+    assert!(message.documents[0].path.ends_with("body.txt"));
+
+    let maildir = Mailbox::at(&confirmations_maildir_pathbuf);
+    assert_eq!(maildir, Mailbox::Maildir(confirmations_maildir_pathbuf));
+    assert_eq!(maildir.messages()?.len(), 1);
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_ingest_mailboxes(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    confirmations_mbox_pathbuf: PathBuf,
+    confirmations_maildir_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    // The camt.053 attachment and the inline MT940 body are imported; the logo, the covering
+    // note and the unrelated email are not. The repeated statement email is skipped.
+    let summary = ingest_mailbox(
+        &registry,
+        &db_desc.db,
+        None,
+        &Mailbox::at(&confirmations_mbox_pathbuf),
+    )
+    .await?;
+    assert_eq!(
+        summary,
+        EmailIngestSummary {
+            messages_processed: 3,
+            messages_skipped: 1,
+            documents_imported: 2,
+            documents_skipped: 0,
+        }
+    );
+
+    let processed = ProcessedEmail::find_by_message_id(
+        &db_desc.db,
+        None,
+        "stmt-20250401-0001@privatbank.example",
+    )
+    .await?
+    .expect("Processed email should be recorded");
+    assert_eq!(processed.imported_documents(), 1);
+
+    // An ingestion that failed after importing the statement but before recording the email
+    // doesn't import the statement again.
+    db_desc
+        .db
+        .collection::<ProcessedEmail>(ProcessedEmail::COLLECTION_NAME)
+        .delete_one(doc! { "message_id": "stmt-20250401-0001@privatbank.example" })
+        .await?;
+    let summary = ingest_mailbox(
+        &registry,
+        &db_desc.db,
+        None,
+        &Mailbox::at(&confirmations_mbox_pathbuf),
+    )
+    .await?;
+    assert_eq!(
+        summary,
+        EmailIngestSummary {
+            messages_processed: 1,
+            messages_skipped: 3,
+            documents_imported: 0,
+            documents_skipped: 1,
+        }
+    );
+
+    for (brokerage_id, account_id, count) in [
+        (CAMT053_BROKERAGE_ID, CAMT053_ACCOUNT_ID, 5),
+        (MT940_BROKERAGE_ID, MT940_ACCOUNT_ID, 4),
+    ] {
+        let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+            &db_desc.db,
+            brokerage_id,
+            account_id,
+        )
+        .await?
+        .expect("Brokerage account should exist");
+        let cash_transactions =
+            CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id())
+                .await?;
+        assert_eq!(cash_transactions.len(), count);
+    }
+
+    // The Maildir holds the same MT940 email, which was already processed.
+    let summary = ingest_mailbox(
+        &registry,
+        &db_desc.db,
+        None,
+        &Mailbox::at(&confirmations_maildir_pathbuf),
+    )
+    .await?;
+    assert_eq!(summary.messages_processed, 0);
+    assert_eq!(summary.messages_skipped, 1);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_read_archive_entries(
+    statements_zip_pathbuf: PathBuf,
+    cash_statements_tar_gz_pathbuf: PathBuf,
+    single_trade_flex_gz_pathbuf: PathBuf,
+    single_trade_flex_pathbuf: PathBuf,
+) -> Result<()> {
+    assert!(is_archive(&statements_zip_pathbuf));
+    assert!(is_archive(&PathBuf::from("2024.TGZ")));
+    assert!(!is_archive(&single_trade_flex_pathbuf));
+
+    let flex = std::fs::read(&single_trade_flex_pathbuf)?;
+    let entries = read_entries(
+        &single_trade_flex_gz_pathbuf,
+        &std::fs::read(&single_trade_flex_gz_pathbuf)?,
+    )?;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        entries[0].path.to_string_lossy(),
+        format!(
+            "{}!/ibkr_flex_single_trade.xml",
+            single_trade_flex_gz_pathbuf.display()
+        )
+    );
+    assert_eq!(entries[0].bytes, flex);
+
+    // Directories are skipped.
+    let entries = read_entries(
+        &cash_statements_tar_gz_pathbuf,
+        &std::fs::read(&cash_statements_tar_gz_pathbuf)?,
+    )?;
+    let names = entries
+        .iter()
+        .map(|e| e.path.file_name().unwrap().to_str().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(names, ["camt053_statement.xml", "mt940_statement.sta"]);
+
+    // Nested archives are expanded.
+    let entries = read_entries(
+        &statements_zip_pathbuf,
+        &std::fs::read(&statements_zip_pathbuf)?,
+    )?;
+    let paths = entries
+        .iter()
+        .map(|e| {
+            e.path
+                .to_string_lossy()
+                .strip_prefix(&statements_zip_pathbuf.display().to_string())
+                .unwrap()
+                .to_owned()
+        })
+        .collect::<Vec<String>>();
+    assert_eq!(
+        paths,
+        [
+            "!/2025/apr.xml",
+            "!/README.txt",
+            "!/2025/cash_statements_2025-03.tar.gz!/cash/camt053_statement.xml",
+            "!/2025/cash_statements_2025-03.tar.gz!/cash/mt940_statement.sta",
+        ]
+    );
+    assert_eq!(entries[0].bytes, flex);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_stream_archive_entries(
+    statements_zip_pathbuf: PathBuf,
+    cash_statements_tar_gz_pathbuf: PathBuf,
+    single_trade_flex_gz_pathbuf: PathBuf,
+    single_trade_flex_pathbuf: PathBuf,
+) -> Result<()> {
+    assert!(is_streamable(&cash_statements_tar_gz_pathbuf));
+    assert!(is_streamable(&single_trade_flex_gz_pathbuf));
+    assert!(!is_streamable(&statements_zip_pathbuf));
+    assert!(stream_entries(&statements_zip_pathbuf, 16).is_err());
+
+    // Entries are received with their start, and streamed from their start.
+    let flex = std::fs::read(&single_trade_flex_pathbuf)?;
+    let mut entries = stream_entries(&single_trade_flex_gz_pathbuf, 16)?;
+    let entry = entries.recv().await.expect("the .gz file has an entry")?;
+    assert_eq!(
+        entry.path.to_string_lossy(),
+        format!(
+            "{}!/ibkr_flex_single_trade.xml",
+            single_trade_flex_gz_pathbuf.display()
+        )
+    );
+    assert_eq!(entry.prefix, flex[..16]);
+    let mut stream = entry.stream()?;
+    let mut streamed = Vec::new();
+    tokio::io::AsyncReadExt::read_to_end(&mut stream.reader, &mut streamed).await?;
+    assert_eq!(streamed, flex);
+    stream.finish().await?;
+    assert!(entries.recv().await.is_none());
+
+    // Entries can be read whole or skipped.
+    let tar_entries = read_entries(
+        &cash_statements_tar_gz_pathbuf,
+        &std::fs::read(&cash_statements_tar_gz_pathbuf)?,
+    )?;
+    let mut entries = stream_entries(&cash_statements_tar_gz_pathbuf, 16)?;
+    let entry = entries.recv().await.expect("the tarball has entries")?;
+    assert_eq!(entry.path, tar_entries[0].path);
+    assert_eq!(entry.read().await?, tar_entries[0].bytes);
+    let entry = entries.recv().await.expect("the tarball has two entries")?;
+    assert_eq!(entry.path, tar_entries[1].path);
+    entry.skip()?;
+    assert!(entries.recv().await.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_streamed_content_is_utf8() {
+    assert!(is_utf8(b"<?xml version=\"1.0\"?>\n<FlexQueryResponse>"));
+    assert!(is_utf8(b"\xEF\xBB\xBFDate,Action,Caf\xC3\xA9"));
+    assert!(is_utf8(
+        b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<FlexQueryResponse>"
+    ));
+    assert!(!is_utf8(b"Date,Action,Caf\xE9"));
+    assert!(!is_utf8(b"\xFF\xFED\x00a\x00t\x00e\x00"));
+    assert!(!is_utf8(b"D\x00a\x00t\x00e\x00,\x00A\x00c\x00t\x00"));
+    assert!(!is_utf8(
+        b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n<FlexQueryResponse>"
+    ));
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_archives(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    statements_zip_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    // The README entry isn't a statement and is skipped.
+    registry
+        .import_statement_files(&db_desc.db, None, vec![statements_zip_pathbuf.clone()])
+        .await?;
+
+    assert!(
+        TradeExecution::find_by_brokerage_execution_id(
+            &db_desc.db,
+            IBKR_SINGLE_TRADE_BROKERAGE_EXECUTION_ID,
+        )
+        .await?
+        .is_some()
+    );
+
+    for (brokerage_id, account_id) in [
+        (CAMT053_BROKERAGE_ID, CAMT053_ACCOUNT_ID),
+        (MT940_BROKERAGE_ID, MT940_ACCOUNT_ID),
+    ] {
+        assert!(
+            BrokerageAccount::find_by_brokerage_and_account_id(
+                &db_desc.db,
+                brokerage_id,
+                account_id,
+            )
+            .await?
+            .is_some()
+        );
+    }
+
+    // The import ledger records the archive-qualified paths.
+    let flex_path = format!("{}!/2025/apr.xml", statements_zip_pathbuf.display());
+    let imported = ImportedFile::find_by_path(&db_desc.db, &flex_path).await?;
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].importer_name(), "ibkr-flex");
+    let readme_path = format!("{}!/README.txt", statements_zip_pathbuf.display());
+    assert!(
+        ImportedFile::find_by_path(&db_desc.db, &readme_path)
+            .await?
+            .is_empty()
+    );
+
+    Ok(())
+}
+
+/// Lays out a statement archive directory with statements from several months, along with
+/// hidden, temporary and draft files that should not be selected.
+fn statement_directory() -> Result<tempfile::TempDir> {
+    let dir = tempfile::tempdir()?;
+    let data = |name: &str| std::fs::read(PathBuf::from("tests/fixtures/data").join(name));
+    let files = [
+        ("2025/04/flex.xml", data("ibkr_flex_single_trade.xml")?),
+        ("2025/04/camt.xml", data("camt053_statement.xml")?),
+        ("2025/03/bank.sta", data("mt940_statement.sta")?),
+        (
+            "2025/04/investments.ofx",
+            data("ofx_investment_statement.ofx")?,
+        ),
+        ("notes.txt", b"Statements for 2025.\n".to_vec()),
+        (".cache/flex.xml", data("ibkr_flex_single_trade.xml")?),
+        ("2025/04/.flex.xml", data("ibkr_flex_single_trade.xml")?),
+        ("2025/04/~$camt.xml", data("camt053_statement.xml")?),
+        ("2025/04/flex.xml.part", data("ibkr_flex_single_trade.xml")?),
+        ("2025/04/flex.xml~", data("ibkr_flex_single_trade.xml")?),
+        ("drafts/flex.xml", data("ibkr_flex_single_trade.xml")?),
+    ];
+    for (name, contents) in files {
+        let path = dir.path().join(name);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, contents)?;
+    }
+
+    // Files whose importer can't tell their statement date are ordered by modification time.
+    let modified = std::time::UNIX_EPOCH + Duration::from_secs(1735689600); // 2025-01-01
+    std::fs::File::options()
+        .write(true)
+        .open(dir.path().join("notes.txt"))?
+        .set_modified(modified)?;
+
+    Ok(dir)
+}
+
+#[test]
+fn test_file_selection() -> Result<()> {
+    let dir = statement_directory()?;
+    let root = dir.path().display().to_string();
+    let relative = |paths: Vec<PathBuf>| {
+        paths
+            .iter()
+            .map(|p| p.strip_prefix(dir.path()).unwrap().display().to_string())
+            .collect::<Vec<String>>()
+    };
+
+    let files = FileSelection::new()
+        .path(&root)
+        .exclude("**/drafts/**")?
+        .files()?;
+    assert_eq!(
+        relative(files),
+        [
+            "2025/03/bank.sta",
+            "2025/04/camt.xml",
+            "2025/04/flex.xml",
+            "2025/04/investments.ofx",
+            "notes.txt",
+        ]
+    );
+
+    let files = FileSelection::new()
+        .path(&format!("{}/**/*.xml", root))
+        .include("*.xml")?
+        .exclude("**/drafts/**")?
+        .files()?;
+    assert_eq!(relative(files), ["2025/04/camt.xml", "2025/04/flex.xml"]);
+
+    // Explicitly named files are selected even if they look temporary.
+    let files = FileSelection::new()
+        .path(&format!("{}/2025/04/flex.xml.part", root))
+        .path(&format!("{}/2025/03", root))
+        .files()?;
+    assert_eq!(
+        relative(files),
+        ["2025/03/bank.sta", "2025/04/flex.xml.part"]
+    );
+
+    assert!(
+        FileSelection::new()
+            .path(&format!("{}/missing", root))
+            .files()
+            .is_err()
+    );
+    assert!(FileSelection::new().include("[").is_err());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_file_selection_symlinks() -> Result<()> {
+    let dir = statement_directory()?;
+    let root = dir.path().display().to_string();
+    let linked_dir = tempfile::tempdir()?;
+    std::fs::copy(
+        "tests/fixtures/data/mt940_statement.sta",
+        linked_dir.path().join("bank.sta"),
+    )?;
+    std::os::unix::fs::symlink(linked_dir.path(), dir.path().join("linked"))?;
+    // A link back up the tree is walked once, not forever.
+    std::os::unix::fs::symlink(dir.path(), dir.path().join("2025/04/all"))?;
+    // A broken link is skipped.
+    std::os::unix::fs::symlink(
+        dir.path().join("missing.sta"),
+        dir.path().join("broken.sta"),
+    )?;
+
+    let files = FileSelection::new()
+        .path(&root)
+        .exclude("**/drafts/**")?
+        .files()?;
+    let relative = files
+        .iter()
+        .map(|p| p.strip_prefix(dir.path()).unwrap().display().to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(
+        relative,
+        [
+            "2025/03/bank.sta",
+            "2025/04/camt.xml",
+            "2025/04/flex.xml",
+            "2025/04/investments.ofx",
+            "linked/bank.sta",
+            "notes.txt",
+        ]
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_chronological_statement_files(registry: ImporterRegistry) -> Result<()> {
+    let dir = statement_directory()?;
+    let selection = FileSelection::new()
+        .path(&dir.path().display().to_string())
+        .exclude("**/drafts/**")?;
+
+    let files = registry.chronological_statement_files(&selection).await?;
+    let relative = files
+        .iter()
+        .map(|p| p.strip_prefix(dir.path()).unwrap().display().to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(
+        relative,
+        [
+            // Modified 2025-01-01.
+            "notes.txt",
+            // Closing balance 2025-03-31.
+            "2025/03/bank.sta",
+            // Created 2025-04-01.
+            "2025/04/camt.xml",
+            // Flex statement to 2025-04-25.
+            "2025/04/flex.xml",
+            // OFX statement ending 2025-04-30.
+            "2025/04/investments.ofx",
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_decode_text() -> Result<()> {
+    // UTF-8 is borrowed, and a byte order mark is stripped.
+    assert!(matches!(
+        decode_text("Währung".as_bytes())?,
+        std::borrow::Cow::Borrowed("Währung")
+    ));
+    assert_eq!(decode_text(b"\xef\xbb\xbfDatum;Zeit")?, "Datum;Zeit");
+
+    // UTF-16 with a byte order mark, and without one.
+    let utf16le = "Datum;Währung"
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect::<Vec<u8>>();
+    let utf16be = "Datum;Währung"
+        .encode_utf16()
+        .flat_map(|unit| unit.to_be_bytes())
+        .collect::<Vec<u8>>();
+    assert_eq!(
+        decode_text(&[&[0xff, 0xfe], utf16le.as_slice()].concat())?,
+        "Datum;Währung"
+    );
+    assert_eq!(
+        decode_text(&[&[0xfe, 0xff], utf16be.as_slice()].concat())?,
+        "Datum;Währung"
+    );
+    assert_eq!(decode_text(&utf16le)?, "Datum;Währung");
+    assert_eq!(decode_text(&utf16be)?, "Datum;Währung");
+
+    // Text that isn't UTF-8 is decoded as declared, or as Windows-1252.
+    assert_eq!(
+        decode_text(b"<?xml version=\"1.0\" encoding=\"ISO-8859-15\"?><a>\xa4</a>")?,
+        "<?xml version=\"1.0\" encoding=\"ISO-8859-15\"?><a>€</a>"
+    );
+    assert_eq!(
+        decode_text(b"Depotgeb\xfchr;\x80 12,50")?,
+        "Depotgebühr;€ 12,50"
+    );
+
+    // A truncated UTF-16 character is an error rather than a replacement character.
+    assert!(decode_text(&[0xff, 0xfe, b'a', 0, b'b']).is_err());
+
+    // The start of a file read on its own may end within a character.
+    let text = "Gebühr".as_bytes();
+    assert_eq!(trim_partial_utf8(&text[..4]), b"Geb");
+    assert_eq!(trim_partial_utf8(text), text);
+    assert_eq!(trim_partial_utf8(b"Geb\xff"), b"Geb\xff");
+
+    Ok(())
+}
+
+#[test]
+fn test_skip_xml_prolog() {
+    assert_eq!(
+        skip_xml_prolog("<FlexQueryResponse/>"),
+        "<FlexQueryResponse/>"
+    );
+    assert_eq!(
+        skip_xml_prolog(
+            "\u{feff}<?xml version=\"1.0\"?>\r\n<!-- <Comment> -->\n<!DOCTYPE x>\n<?pi?><Root/>"
+        ),
+        "<Root/>"
+    );
+    assert_eq!(skip_xml_prolog("<!-- unterminated <Root/>"), "");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_ibkr_content_matches_after_prolog(
+    single_trade_flex_prolog_pathbuf: PathBuf,
+) -> Result<()> {
+    let bytes = std::fs::read(single_trade_flex_prolog_pathbuf)?;
+    let importer = IbkrFlexStatementImporter::new();
+    let content = importer.decode_content(&bytes)?;
+    assert!(content.starts_with("<?xml"));
+    assert_eq!(
+        importer.content_matches(&content).await.confidence(),
+        MatchConfidence::Definite
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_decode_configurable_csv_encodings(
+    acme_depot_mapping_pathbuf: PathBuf,
+    acme_depot_windows_1252_csv_pathbuf: PathBuf,
+    acme_depot_utf16_csv_pathbuf: PathBuf,
+) -> Result<()> {
+    let importer = ConfigurableCsvImporter::from_file(&acme_depot_mapping_pathbuf)?;
+    for (path, execution_id) in [
+        (acme_depot_windows_1252_csv_pathbuf, "AC-2001"),
+        (acme_depot_utf16_csv_pathbuf, "AC-3001"),
+    ] {
+        let bytes = std::fs::read(&path)?;
+        let content = importer.decode_content(&bytes)?;
+        assert_eq!(
+            importer.content_matches(&content).await.confidence(),
+            MatchConfidence::Likely
+        );
+
+        let account_activities = importer.parse(&content)?;
+        assert_eq!(account_activities.len(), 1);
+        let activities = &account_activities[0].activities;
+        assert_eq!(activities.len(), 2);
+        let Activity::Trade(trade) = &activities[0] else {
+            panic!("expected a trade in {:?}", path);
+        };
+        assert_eq!(trade.brokerage_execution_id, execution_id);
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_encodings(
+    #[future] db_desc: Result<DbDesc>,
+    registry: ImporterRegistry,
+    single_trade_flex_prolog_pathbuf: PathBuf,
+    acme_depot_windows_1252_csv_pathbuf: PathBuf,
+    acme_depot_utf16_csv_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    registry
+        .import_statement_files(
+            &db_desc.db,
+            None,
+            vec![
+                single_trade_flex_prolog_pathbuf,
+                acme_depot_windows_1252_csv_pathbuf,
+                acme_depot_utf16_csv_pathbuf,
+            ],
+        )
+        .await?;
+
+    for execution_id in [
+        IBKR_SINGLE_TRADE_BROKERAGE_EXECUTION_ID,
+        "AC-2001",
+        "AC-3001",
+    ] {
+        assert!(
+            TradeExecution::find_by_brokerage_execution_id(&db_desc.db, execution_id)
+                .await?
+                .is_some()
+        );
+    }
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        ACME_BROKERAGE_ID,
+        ACME_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    assert_eq!(cash_transactions.len(), 2);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_content_match_confidence(
+    single_trade_flex_pathbuf: PathBuf,
+    schwab_transactions_pathbuf: PathBuf,
+    generic_transactions_mapping_pathbuf: PathBuf,
+) -> Result<()> {
+    assert!(MatchConfidence::Definite > MatchConfidence::Likely);
+    assert!(MatchConfidence::Likely > MatchConfidence::Possible);
+    assert!(MatchConfidence::Possible > MatchConfidence::No);
+    assert!(ContentMatch::possible("some columns").is_match());
+    assert!(!ContentMatch::no("no columns").is_match());
+
+    let flex = std::fs::read_to_string(single_trade_flex_pathbuf)?;
+    let schwab = std::fs::read_to_string(schwab_transactions_pathbuf)?;
+    let generic = ConfigurableCsvImporter::from_file(&generic_transactions_mapping_pathbuf)?;
+
+    let flex_match = IbkrFlexStatementImporter::new()
+        .content_matches(&flex)
+        .await;
+    assert_eq!(flex_match.confidence(), MatchConfidence::Definite);
+    assert_eq!(flex_match.reason(), "FlexQueryResponse root element");
+    assert_eq!(
+        SchwabCsvImporter::new().content_matches(&schwab).await,
+        ContentMatch::likely("Schwab transactions CSV header")
+    );
+    // A mapping without a preamble only requires columns that other exports may share.
+    assert_eq!(
+        generic.content_matches(&schwab).await,
+        ContentMatch::possible("mapping generic-transactions required columns")
+    );
+    assert!(
+        !SchwabCsvImporter::new()
+            .content_matches(&flex)
+            .await
+            .is_match()
+    );
+
+    Ok(())
+}
+
+/// Returns a registry with the ACME mapping registered twice under different names, so both
+/// match ACME statements with the same confidence.
+fn ambiguous_registry(
+    dir: &tempfile::TempDir,
+    acme_depot_mapping_pathbuf: &PathBuf,
+) -> Result<ImporterRegistry> {
+    let mapping = std::fs::read_to_string(acme_depot_mapping_pathbuf)?;
+    let copy_pathbuf = dir.path().join("acme_depot_copy_mapping.toml");
+    std::fs::write(
+        &copy_pathbuf,
+        mapping.replace("name = \"acme-depot\"", "name = \"acme-depot-copy\""),
+    )?;
+
+    let mut registry = ImporterRegistry::new();
+    registry.register_csv_mapping_files(&[acme_depot_mapping_pathbuf.clone(), copy_pathbuf])?;
+    Ok(registry)
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_ambiguous_importers(
+    acme_depot_mapping_pathbuf: PathBuf,
+    acme_depot_csv_pathbuf: PathBuf,
+) -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let registry = ambiguous_registry(&dir, &acme_depot_mapping_pathbuf)?;
+    let selection = FileSelection::new().path(&acme_depot_csv_pathbuf.to_string_lossy());
+
+    let error = registry
+        .chronological_statement_files(&selection)
+        .await
+        .expect_err("equally confident importers should be ambiguous")
+        .to_string();
+    assert!(error.contains("Ambiguous importers"));
+    assert!(error.contains("acme-depot (mapping acme-depot preamble and required columns)"));
+    assert!(error.contains("acme-depot-copy"));
+
+    // Forcing an importer resolves the ambiguity.
+    assert!(
+        registry
+            .chronological_statement_files_with(&selection, Some("unknown"))
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        registry
+            .chronological_statement_files_with(&selection, Some("acme-depot-copy"))
+            .await?,
+        vec![acme_depot_csv_pathbuf]
+    );
+
+    // The override applies to that call only.
+    assert!(
+        registry
+            .chronological_statement_files(&selection)
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_most_confident_importer(
+    #[future] db_desc: Result<DbDesc>,
+    mut registry: ImporterRegistry,
+    generic_transactions_mapping_pathbuf: PathBuf,
+    schwab_transactions_pathbuf: PathBuf,
+    acme_depot_mapping_pathbuf: PathBuf,
+    acme_depot_csv_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+
+    // The Schwab importer is more confident than the generic mapping that also matches.
+    registry.register_csv_mapping_files(&[generic_transactions_mapping_pathbuf])?;
+    registry
+        .import_statement_files(&db_desc.db, None, vec![schwab_transactions_pathbuf.clone()])
+        .await?;
+    let imported =
+        ImportedFile::find_by_path(&db_desc.db, &schwab_transactions_pathbuf.to_string_lossy())
+            .await?;
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].importer_name(), "schwab-csv");
+
+    // Ambiguous files fail to import unless an importer is forced.
+    let dir = tempfile::tempdir()?;
+    let registry = ambiguous_registry(&dir, &acme_depot_mapping_pathbuf)?;
+    assert!(
+        registry
+            .import_statement_files(&db_desc.db, None, vec![acme_depot_csv_pathbuf.clone()])
+            .await
+            .is_err()
+    );
+    registry
+        .import_statement_files_with(
+            &db_desc.db,
+            None,
+            vec![acme_depot_csv_pathbuf.clone()],
+            Some("acme-depot-copy"),
+        )
+        .await?;
+    let imported =
+        ImportedFile::find_by_path(&db_desc.db, &acme_depot_csv_pathbuf.to_string_lossy()).await?;
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].importer_name(), "acme-depot-copy");
+
+    Ok(())
+}
+
+/// Returns a Flex query response with a statement for each account, with the given number of
+/// copies of the single-trade fixture's trade, numbered by their execution ids.
+fn generated_flex(accounts: &[(&str, usize)]) -> String {
+    let trade_start = single_trade_flex().find("<Trade ").unwrap();
+    let trade_end = trade_start + single_trade_flex()[trade_start..].find("/>").unwrap() + 2;
+    let trade = &single_trade_flex()[trade_start..trade_end];
+
+    let mut flex = String::from("<FlexQueryResponse queryName=\"generated\" type=\"AF\">\n");
+    flex.push_str("  <FlexStatements count=\"2\">\n");
+    for (account_id, trade_count) in accounts {
+        flex.push_str(&format!(
+            "    <FlexStatement accountId=\"{0}\" fromDate=\"2025-04-25\" toDate=\"2025-04-25\">\n      <AccountInformation accountId=\"{0}\" />\n      <Trades>\n",
+            account_id
+        ));
+        for index in 0..*trade_count {
+            flex.push_str(&trade.replace(IBKR_ACCOUNT_ID, account_id).replace(
+                IBKR_SINGLE_TRADE_BROKERAGE_EXECUTION_ID,
+                &format!("{}.{:04}", account_id, index),
+            ));
+            flex.push('\n');
+        }
+        flex.push_str("      </Trades>\n    </FlexStatement>\n");
+    }
+    flex.push_str("  </FlexStatements>\n</FlexQueryResponse>\n");
+    flex
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_flex_trade_reader(single_trade_flex_prolog_pathbuf: PathBuf) -> Result<()> {
+    let flex = generated_flex(&[("U1111111", 5), ("U2222222", 2)]);
+    let mut reader = FlexTradeReader::new(flex.as_bytes(), 2)?;
+    let mut batches = Vec::new();
+    while let Some(batch) = reader.next_batch().await? {
+        batches.push((
+            batch.account_id,
+            batch
+                .trades
+                .iter()
+                .map(|trade| trade.execution_id.clone())
+                .collect::<Vec<String>>(),
+        ));
+    }
+    assert_eq!(
+        batches,
+        vec![
+            (
+                "U1111111".to_owned(),
+                vec!["U1111111.0000".to_owned(), "U1111111.0001".to_owned()]
+            ),
+            (
+                "U1111111".to_owned(),
+                vec!["U1111111.0002".to_owned(), "U1111111.0003".to_owned()]
+            ),
+            ("U1111111".to_owned(), vec!["U1111111.0004".to_owned()]),
+            (
+                "U2222222".to_owned(),
+                vec!["U2222222.0000".to_owned(), "U2222222.0001".to_owned()]
+            ),
+        ]
+    );
+
+    // Streamed trades are parsed as they are from whole statements, after any prolog.
+    let statements =
+        ibkr_flex_statement::Parser::new()?.parse_flex_query_response(single_trade_flex())?;
+    let bytes = std::fs::read(single_trade_flex_prolog_pathbuf)?;
+    let mut reader = FlexTradeReader::new(bytes.as_slice(), 100)?;
+    let batch = reader
+        .next_batch()
+        .await?
+        .expect("the trade should be read");
+    assert_eq!(batch.account_id, IBKR_ACCOUNT_ID);
+    assert_eq!(batch.trades, statements[0].trades);
+    assert!(reader.next_batch().await?.is_none());
+
+    assert!(FlexTradeReader::new(bytes.as_slice(), 0).is_err());
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_streaming_registry_settings(
+    mut registry: ImporterRegistry,
+    single_trade_flex_pathbuf: PathBuf,
+    camt053_statement_pathbuf: PathBuf,
+) -> Result<()> {
+    assert!(registry.set_stream_batch_size(0).is_err());
+    registry.set_stream_batch_size(500)?;
+    assert!(
+        registry
+            .importer("ibkr-flex")
+            .is_some_and(|importer| importer.streaming().is_some())
+    );
+    assert!(
+        registry
+            .importer("camt053")
+            .is_some_and(|importer| importer.streaming().is_none())
+    );
+
+    // Statement dates of streamed files are read from their start.
+    registry.set_streaming_threshold(0);
+    let selection = FileSelection::new()
+        .path(&single_trade_flex_pathbuf.to_string_lossy())
+        .path(&camt053_statement_pathbuf.to_string_lossy());
+    assert_eq!(
+        registry.chronological_statement_files(&selection).await?,
+        vec![camt053_statement_pathbuf, single_trade_flex_pathbuf]
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_file_streaming(
+    #[future] db_desc: Result<DbDesc>,
+    mut registry: ImporterRegistry,
+    camt053_statement_pathbuf: PathBuf,
+    single_trade_flex_gz_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+    let dir = tempfile::tempdir()?;
+    let flex_pathbuf = dir.path().join("flex_2025.xml");
+    std::fs::write(
+        &flex_pathbuf,
+        generated_flex(&[("U1111111", 5), ("U2222222", 2)]),
+    )?;
+
+    // The Flex statements are streamed, the compressed one as it's decompressed, and the
+    // camt.053 statement, whose importer can't stream, is read whole.
+    registry.set_streaming_threshold(0);
+    registry.set_stream_batch_size(2)?;
+    registry
+        .import_statement_files(
+            &db_desc.db,
+            None,
+            vec![
+                flex_pathbuf.clone(),
+                camt053_statement_pathbuf,
+                single_trade_flex_gz_pathbuf.clone(),
+            ],
+        )
+        .await?;
+
+    for (account_id, trade_count) in [("U1111111", 5), ("U2222222", 2)] {
+        let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+            &db_desc.db,
+            IBKR_BROKERAGE_ID,
+            account_id,
+        )
+        .await?
+        .expect("Brokerage account should exist");
+        for index in 0..trade_count {
+            let execution = TradeExecution::find_by_brokerage_execution_id(
+                &db_desc.db,
+                &format!("{}.{:04}", account_id, index),
+            )
+            .await?
+            .expect("Trade execution should exist");
+            assert_eq!(execution.brokerage_account_id(), brokerage_account.id());
+        }
+    }
+    assert_eq!(
+        Security::find_by_ticker(&db_desc.db, IBKR_SINGLE_TRADE_TICKER)
+            .await?
+            .len(),
+        1
+    );
+    assert!(
+        BrokerageAccount::find_by_brokerage_and_account_id(
+            &db_desc.db,
+            CAMT053_BROKERAGE_ID,
+            CAMT053_ACCOUNT_ID,
+        )
+        .await?
+        .is_some()
+    );
+
+    let imported = ImportedFile::find_by_path(&db_desc.db, &flex_pathbuf.to_string_lossy()).await?;
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].importer_name(), "ibkr-flex");
+
+    assert!(
+        TradeExecution::find_by_brokerage_execution_id(
+            &db_desc.db,
+            IBKR_SINGLE_TRADE_BROKERAGE_EXECUTION_ID,
+        )
+        .await?
+        .is_some()
+    );
+    let entry_path = format!(
+        "{}!/ibkr_flex_single_trade.xml",
+        single_trade_flex_gz_pathbuf.display()
+    );
+    let imported = ImportedFile::find_by_path(&db_desc.db, &entry_path).await?;
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].importer_name(), "ibkr-flex");
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_reimport_flex_statement(
+    #[future] db_desc: Result<DbDesc>,
+    single_trade_flex_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+    let content = std::fs::read_to_string(single_trade_flex_pathbuf)?;
+
+    // Re-importing a statement skips the trades already written instead of failing on the
+    // unique execution id index.
+    for _ in 0..2 {
+        IbkrFlexStatementImporter::new()
+            .import(
+                &content,
+                &db_desc.db,
+                None,
+                ObjectId::new(),
+                DEFAULT_WRITE_BATCH_SIZE,
+            )
+            .await?;
+    }
+    assert_eq!(
+        db_desc
+            .db
+            .collection::<TradeExecution>(TradeExecution::COLLECTION_NAME)
+            .count_documents(doc! {})
+            .await?,
+        1
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[awt]
+#[traced_test]
+#[tokio::test]
+async fn test_import_write_batches(
+    #[future] db_desc: Result<DbDesc>,
+    camt053_statement_pathbuf: PathBuf,
+) -> Result<()> {
+    let db_desc = db_desc?;
+    let flex = generated_flex(&[("U1111111", 5), ("U2222222", 2)]);
+
+    assert!(ImporterRegistry::new().set_write_batch_size(0).is_err());
+
+    // Trades are written across several batches, each security only once.
+    IbkrFlexStatementImporter::new()
+        .import(&flex, &db_desc.db, None, ObjectId::new(), 3)
+        .await?;
+    for (account_id, trade_count) in [("U1111111", 5), ("U2222222", 2)] {
+        let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+            &db_desc.db,
+            IBKR_BROKERAGE_ID,
+            account_id,
+        )
+        .await?
+        .expect("Brokerage account should exist");
+        for index in 0..trade_count {
+            let execution = TradeExecution::find_by_brokerage_execution_id(
+                &db_desc.db,
+                &format!("{}.{:04}", account_id, index),
+            )
+            .await?
+            .expect("Trade execution should exist");
+            assert_eq!(execution.brokerage_account_id(), brokerage_account.id());
+        }
+    }
+    assert_eq!(
+        Security::find_by_ticker(&db_desc.db, IBKR_SINGLE_TRADE_TICKER)
+            .await?
+            .len(),
+        1
+    );
+
+    // Cash transactions that were already written are skipped, also across batches of
+    // activities.
+    let content = std::fs::read_to_string(camt053_statement_pathbuf)?;
+    for _ in 0..2 {
+        Camt053Importer::new()
+            .import(&content, &db_desc.db, None, ObjectId::new(), 2)
+            .await?;
+    }
+    let brokerage_account = BrokerageAccount::find_by_brokerage_and_account_id(
+        &db_desc.db,
+        CAMT053_BROKERAGE_ID,
+        CAMT053_ACCOUNT_ID,
+    )
+    .await?
+    .expect("Brokerage account should exist");
+    let cash_transactions =
+        CashTransaction::find_by_brokerage_account_id(&db_desc.db, brokerage_account.id()).await?;
+    assert_eq!(cash_transactions.len(), 5);
+
+    Ok(())
+}